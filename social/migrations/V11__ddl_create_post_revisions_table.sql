@@ -0,0 +1,15 @@
+CREATE TABLE post_revisions(
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    post_id UUID NOT NULL,
+    text VARCHAR NOT NULL,
+    visibility VARCHAR(16) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE,
+    CONSTRAINT fk_post
+        FOREIGN KEY (post_id) 
+        REFERENCES posts(id) 
+        ON DELETE CASCADE
+);
+
+CREATE INDEX post_revisions_post_id_created_at ON post_revisions (post_id, created_at);
+
+ALTER TABLE posts ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
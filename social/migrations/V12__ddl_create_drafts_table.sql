@@ -0,0 +1,18 @@
+CREATE TABLE drafts(
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,
+    text VARCHAR NOT NULL,
+    visibility VARCHAR(16) NOT NULL DEFAULT 'public' 
+        CHECK (visibility IN ('public', 'friends', 'private')),
+    publish_at TIMESTAMP WITH TIME ZONE,
+    claimed_until TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_user
+        FOREIGN KEY (user_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE
+);
+
+CREATE INDEX drafts_user_id_updated_at ON drafts (user_id, updated_at DESC);
+CREATE INDEX drafts_publish_at ON drafts (publish_at) WHERE publish_at IS NOT NULL;
@@ -0,0 +1,41 @@
+CREATE TABLE post_tags(
+    post_id UUID NOT NULL,
+    tag VARCHAR(64) NOT NULL,
+    PRIMARY KEY (tag, post_id),
+    CONSTRAINT fk_post
+        FOREIGN KEY (post_id) 
+        REFERENCES posts(id) 
+        ON DELETE CASCADE
+);
+
+CREATE INDEX post_tags_post_id ON post_tags (post_id);
+
+CREATE TABLE post_mentions(
+    post_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    notified_at TIMESTAMP WITH TIME ZONE,
+    PRIMARY KEY (post_id, user_id),
+    CONSTRAINT fk_post
+        FOREIGN KEY (post_id) 
+        REFERENCES posts(id) 
+        ON DELETE CASCADE,
+    CONSTRAINT fk_user
+        FOREIGN KEY (user_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE
+);
+
+CREATE TABLE user_blocks(
+    user_id UUID NOT NULL,
+    blocked_user_id UUID NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, blocked_user_id),
+    CONSTRAINT fk_user
+        FOREIGN KEY (user_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE,
+    CONSTRAINT fk_blocked_user 
+        FOREIGN KEY (blocked_user_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE
+);
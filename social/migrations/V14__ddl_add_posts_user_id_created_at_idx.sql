@@ -0,0 +1 @@
+CREATE INDEX posts_user_id_created_at ON posts (user_id, created_at DESC, id DESC);
@@ -0,0 +1,22 @@
+ALTER TABLE posts ADD COLUMN hidden_at TIMESTAMP WITH TIME ZONE;
+
+CREATE TABLE post_reports(
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    post_id UUID NOT NULL,
+    reporter_id UUID NOT NULL,
+    reason VARCHAR NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'resolved')),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (post_id, reporter_id),
+    CONSTRAINT fk_post
+        FOREIGN KEY (post_id) 
+        REFERENCES posts(id) 
+        ON DELETE CASCADE,
+    CONSTRAINT fk_reporter
+        FOREIGN KEY (reporter_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE
+);
+
+CREATE INDEX post_reports_pending ON post_reports (created_at) WHERE status = 'pending';
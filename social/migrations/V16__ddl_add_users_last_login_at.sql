@@ -0,0 +1,3 @@
+ALTER TABLE users ADD COLUMN last_login_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX users_last_login_at ON users (last_login_at DESC) WHERE last_login_at IS NOT NULL;
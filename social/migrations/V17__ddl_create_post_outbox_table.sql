@@ -0,0 +1,14 @@
+CREATE TABLE post_outbox(
+    id BIGSERIAL PRIMARY KEY,
+    routing_key VARCHAR(64) NOT NULL,
+    payload JSONB NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    last_error VARCHAR,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    sent_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX post_outbox_pending ON post_outbox (id) WHERE sent_at IS NULL;
+
+CREATE INDEX post_outbox_sent_at ON post_outbox (sent_at) WHERE sent_at IS NOT NULL;
@@ -0,0 +1,2 @@
+ALTER TABLE drafts ADD COLUMN failed_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE drafts ADD COLUMN failure_reason VARCHAR;
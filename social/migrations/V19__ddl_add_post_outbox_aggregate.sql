@@ -0,0 +1,8 @@
+ALTER TABLE post_outbox ADD COLUMN aggregate_id UUID;
+ALTER TABLE post_outbox ADD COLUMN dead_at TIMESTAMP WITH TIME ZONE;
+
+DROP INDEX post_outbox_pending;
+
+CREATE INDEX post_outbox_pending ON post_outbox (id) WHERE sent_at IS NULL AND dead_at IS NULL;
+
+CREATE INDEX post_outbox_pending_aggregate ON post_outbox (aggregate_id, id) WHERE sent_at IS NULL AND dead_at IS NULL;
@@ -0,0 +1,3 @@
+ALTER TABLE users
+    ADD COLUMN followers_count BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN following_count BIGINT NOT NULL DEFAULT 0;
\ No newline at end of file
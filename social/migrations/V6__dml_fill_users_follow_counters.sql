@@ -0,0 +1,9 @@
+UPDATE users u
+SET followers_count = q.cnt
+FROM (SELECT user_id, count(*) AS cnt FROM friends GROUP BY user_id) q
+WHERE u.id = q.user_id;
+
+UPDATE users u
+SET following_count = q.cnt
+FROM (SELECT friend_id, count(*) AS cnt FROM friends GROUP BY friend_id) q
+WHERE u.id = q.friend_id;
@@ -0,0 +1,29 @@
+CREATE TABLE post_reactions(
+    post_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    reaction VARCHAR(16) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (post_id, user_id),
+    CONSTRAINT fk_post
+        FOREIGN KEY (post_id) 
+        REFERENCES posts(id) 
+        ON DELETE CASCADE,
+    CONSTRAINT fk_user
+        FOREIGN KEY (user_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE
+);
+
+CREATE INDEX post_reactions_post_id_created_at ON post_reactions (post_id, created_at DESC);
+CREATE INDEX post_reactions_user_id ON post_reactions (user_id);
+
+CREATE TABLE post_reaction_counts(
+    post_id UUID NOT NULL,
+    reaction VARCHAR(16) NOT NULL,
+    count BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (post_id, reaction),
+    CONSTRAINT fk_post
+        FOREIGN KEY (post_id) 
+        REFERENCES posts(id) 
+        ON DELETE CASCADE
+);
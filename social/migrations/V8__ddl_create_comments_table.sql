@@ -0,0 +1,26 @@
+CREATE TABLE comments(
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    post_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    parent_id UUID,
+    text VARCHAR NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_post
+        FOREIGN KEY (post_id) 
+        REFERENCES posts(id) 
+        ON DELETE CASCADE,
+    CONSTRAINT fk_user
+        FOREIGN KEY (user_id) 
+        REFERENCES users(id) 
+        ON DELETE CASCADE,
+    CONSTRAINT fk_parent
+        FOREIGN KEY (parent_id) 
+        REFERENCES comments(id) 
+        ON DELETE CASCADE
+);
+
+CREATE INDEX comments_post_id_created_at ON comments (post_id, created_at) WHERE parent_id IS NULL;
+CREATE INDEX comments_parent_id ON comments (parent_id);
+
+ALTER TABLE posts ADD COLUMN comments_count BIGINT NOT NULL DEFAULT 0;
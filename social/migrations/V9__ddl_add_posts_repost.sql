@@ -0,0 +1,5 @@
+ALTER TABLE posts 
+    ADD COLUMN repost_of UUID REFERENCES posts(id) ON DELETE CASCADE,
+    ADD COLUMN original_author_id UUID;
+
+CREATE INDEX posts_repost_of ON posts (repost_of) WHERE repost_of IS NOT NULL;
@@ -2,7 +2,7 @@ use deadpool_postgres::{Config, ManagerConfig, Pool, RecyclingMethod, Runtime, O
 use tokio_postgres::{NoTls};
 use std::{env, time::Duration};
 use fred::{prelude::{Error, ReconnectPolicy}, prelude::*};
-use crate::modules::{common::ws::ws_manager::WebSocketManager, dialog::{self, service_provider::DialogService}, post::{self, followers::followers_service::FollowersService, service_provider::PostService}};
+use crate::modules::{common::{idempotency::idempotency_store::IdempotencyStore, metrics::{feed_cache_metrics::FeedCacheMetrics, post_cache_metrics::PostCacheMetrics, publisher_metrics::PublisherMetrics}, ws::ws_manager::WebSocketManager}, dialog::{self, service_provider::DialogService}, friend::in_memory_repository::InMemoryFriendRepository, user::in_memory_repository::InMemoryUserRepository, post::{self, cache_invalidator::CacheInvalidator, comment::service_provider::CommentService, dlq::service_provider::{DlqConfig, DlqService}, draft::service_provider::DraftService, followers::{followers_service::FollowersService, in_process_publisher::InProcessPublisher, service_provider::ConsumerConfig}, in_memory_post_cache::InMemoryPostCache, in_memory_repository::InMemoryPostRepository, local_post_cache::LocalPostStore, moderation::{filter::FilterConfig, service_provider::ModerationService}, outbox::service_provider::{OutboxConfig, OutboxRelay}, post_cache::{FeedCache, FeedLimits, PostCacheImpl}, rabbitmq::{PublisherConfig, RabbitPublisher}, reaction::service_provider::ReactionService, service_provider::PostService, warmup::service_provider::{WarmupConfig, WarmupService}}};
 use std::sync::Arc;
 use messenger_client::apis::configuration::Configuration;
 
@@ -15,8 +15,30 @@ use messenger_client::apis::configuration::Configuration;
     pub post_service: Arc<dyn PostService + Send + Sync>,
     pub dialog_service: Arc<dyn DialogService + Send + Sync>,    
     pub followers_service: Arc<dyn FollowersService + Send + Sync>,    
+    pub reaction_service: Arc<dyn ReactionService + Send + Sync>,
+    pub comment_service: Arc<dyn CommentService + Send + Sync>,
+    pub draft_service: Arc<dyn DraftService + Send + Sync>,
+    pub moderation_service: Arc<dyn ModerationService + Send + Sync>,
+    pub warmup_service: Arc<dyn WarmupService + Send + Sync>,
+    pub dlq_service: Arc<dyn DlqService + Send + Sync>,
+    /// Followers count from which public posts of an author are merged into feeds on read instead of being pushed
+    pub celebrity_threshold: u64,
+    /// Feed cache of the post service, friendship changes drop the followed celebrities kept there
+    pub feed_cache: Arc<dyn FeedCache + Send + Sync>,
     pub port: i32,    
     pub ws_manager: Arc<WebSocketManager>,
+    pub idempotency_store: Arc<IdempotencyStore>,
+    pub feed_cache_metrics: Arc<FeedCacheMetrics>,
+    pub post_cache_metrics: Arc<PostCacheMetrics>,
+    pub publisher_metrics: Arc<PublisherMetrics>,
+    pub cache_invalidator: Arc<CacheInvalidator>,
+    pub outbox_relay: Arc<dyn OutboxRelay + Send + Sync>,
+    /// Set by `APP_PROFILE=dev`: users, posts, feeds, reactions and the follower graph live in memory.
+    /// Postgres, Redis and RabbitMQ are never connected, no background consumers run and the routes
+    /// of comments, drafts, reports, moderation and the admin tools answer 501
+    pub dev_profile: bool,
+    pub dev_friends: Option<InMemoryFriendRepository>,
+    pub dev_users: Option<InMemoryUserRepository>,
 }
 
 fn init_config(port_key: &str) -> Config {
@@ -31,25 +53,62 @@ fn init_config(port_key: &str) -> Config {
     config
 }
 
-async fn init_redis_pool() -> Result<fred::prelude::Pool, Error> {
+/// Parses `host:port` pairs separated by commas
+fn parse_redis_nodes(key: &str) -> Vec<(String, u16)> {
+    env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|node| {
+            let (host, port) = node.trim().rsplit_once(':')?;
+            Some((host.to_string(), port.parse().ok()?))
+        })
+        .collect()
+}
+
+/// `REDIS_MODE` picks the deployment: `standalone` connects by `REDIS_URL`,
+/// `sentinel` and `cluster` take their nodes and credentials from separate variables
+fn init_redis_config() -> fred::prelude::Config {
+    let mode = env::var("REDIS_MODE").unwrap_or("standalone".to_string());
+    let server = match mode.as_str() {
+        "cluster" => ServerConfig::new_clustered(parse_redis_nodes("REDIS_CLUSTER_NODES")),
+        "sentinel" => ServerConfig::new_sentinel(
+            parse_redis_nodes("REDIS_SENTINEL_NODES"),
+            env::var("REDIS_SENTINEL_SERVICE").unwrap_or("mymaster".to_string())
+        ),
+        _ => return fred::prelude::Config::from_url(&env::var("REDIS_URL").unwrap()).expect("Failed to create redis config from url")
+    };
+    tracing::info!("Redis mode: {}", mode);
+    fred::prelude::Config {
+        server,
+        username: env::var("REDIS_USERNAME").ok(),
+        password: env::var("REDIS_PASSWORD").ok(),
+        ..fred::prelude::Config::default()
+    }
+}
+
+fn build_redis_pool(config: fred::prelude::Config) -> fred::prelude::Pool {
     let pool_size = env::var("REDIS_POOL_SIZE")
         .ok()
         .and_then(|v| v.parse::<usize>().ok())
         .unwrap_or(8);
-    let config = fred::prelude::Config::from_url(&env::var("REDIS_URL").unwrap()).expect("Failed to create redis config from url");
     let pool = fred::prelude::Builder::from_config(config)
         .with_connection_config(|config| {
             config.connection_timeout = Duration::from_secs(10);
         })        
         .set_policy(ReconnectPolicy::new_exponential(0, 100, 30_000, 2))
         .build_pool(pool_size)
-        .expect("Failed to create redis pool");            
+        .expect("Failed to create redis pool")
+}
+
+async fn init_redis_pool() -> Result<fred::prelude::Pool, Error> {
+    let pool = build_redis_pool(init_redis_config());
     pool.init().await.expect("Failed to connect to redis");
     tracing::info!("Connected to Redis");
     Ok(pool)
 }
 
-async fn init_rabbitmq_pool() -> Result<deadpool_lapin::Pool, deadpool_lapin::CreatePoolError> {
+/// Connections are opened on first use, so nothing waits for the broker here
+fn build_rabbitmq_pool() -> Result<deadpool_lapin::Pool, deadpool_lapin::CreatePoolError> {
     let mut cfg = deadpool_lapin::Config::default();
     cfg.url = env::var("RABBITMQ_URL").ok();
     cfg.create_pool(Some(Runtime::Tokio1))
@@ -74,23 +133,154 @@ impl AppState {
             .create_pool(Some(Runtime::Tokio1), NoTls).unwrap();
         replica_pool2.resize(10);        
         let master_pool = Arc::new(master_pool);
-        let redis = Arc::new(init_redis_pool().await?);
-        let rabbitmq = Arc::new(init_rabbitmq_pool().await?);
+        let dev_profile = env::var("APP_PROFILE").is_ok_and(|profile| profile == "dev");
+        // The dev profile turns off every route using Redis, the pool only fills the fields of their services and is never connected
+        let redis = Arc::new(if dev_profile { build_redis_pool(fred::prelude::Config::default()) } else { init_redis_pool().await? });
+        let rabbitmq = Arc::new(build_rabbitmq_pool()?);
         let ws_manager = Arc::new(WebSocketManager::new());
         let exchange = "post.feed.events".to_string();
-        let post_service = post::service_provider::create_service(
-            Arc::clone(&master_pool),
-            Arc::clone(&redis),
+        let celebrity_threshold = env::var("CELEBRITY_FOLLOWERS_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(10000);
+        let restore_window_days = env::var("POST_RESTORE_WINDOW_DAYS").ok().and_then(|v| v.parse().ok()).unwrap_or(30);
+        let feed_limits = FeedLimits::from_env();
+        let post_cache_metrics = Arc::new(PostCacheMetrics::new());
+        let publisher_metrics = Arc::new(PublisherMetrics::new());
+        // Shared by every publishing service so they reuse the same confirm channels
+        let rabbit_publisher = Arc::new(RabbitPublisher::new(
             Arc::clone(&rabbitmq),
-            exchange.clone()
-        );    
-        let followers_service = post::followers::service_provider::create_service(
+            exchange.clone(),
+            PublisherConfig::from_env(),
+            Arc::clone(&publisher_metrics)
+        ));
+        let local_posts = Arc::new(LocalPostStore::new(
+            env::var("POST_LOCAL_CACHE_SIZE").ok().and_then(|v| v.parse().ok()).unwrap_or(10000),
+            Duration::from_millis(env::var("POST_LOCAL_CACHE_TTL_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(5000)),
+            Arc::clone(&post_cache_metrics)
+        ));
+        let (post_service, feed_warmer, feed_cache, followers_service, dev_friends, dev_posts) = if dev_profile {
+            tracing::info!("Dev profile: users, posts, feeds, reactions and friendships are kept in memory");
+            let friends = InMemoryFriendRepository::new();
+            let posts = InMemoryPostRepository::new(friends.clone());
+            let cache = InMemoryPostCache::new(feed_limits);
+            let followers_service = post::followers::service_provider::create_in_memory_service(
+                friends.clone(),
+                posts.clone(),
+                cache.clone(),
+                Arc::clone(&rabbitmq),
+                Arc::clone(&ws_manager),
+                exchange.clone(),
+                celebrity_threshold,
+                ConsumerConfig::from_env()
+            );
+            let feed_cache: Arc<dyn FeedCache + Send + Sync> = Arc::new(cache.clone());
+            let (post_service, feed_warmer) = post::service_provider::create_in_memory_service(
+                posts.clone(),
+                cache,
+                Arc::new(InProcessPublisher::new(Arc::clone(&followers_service))),
+                FilterConfig::from_env(),
+                celebrity_threshold,
+                restore_window_days,
+                feed_limits
+            );
+            (post_service, feed_warmer, feed_cache, followers_service, Some(friends), Some(posts))
+        } else {
+            let (post_service, feed_warmer) = post::service_provider::create_service(
+                Arc::clone(&master_pool),
+                Arc::clone(&redis),
+                FilterConfig::from_env(),
+                celebrity_threshold,
+                restore_window_days,
+                feed_limits,
+                Arc::clone(&local_posts),
+                env::var("REDIS_REPLICA_READS").ok().and_then(|v| v.parse().ok()).unwrap_or(false)
+            );    
+            let followers_service = post::followers::service_provider::create_service(
+                Arc::clone(&master_pool),
+                Arc::clone(&redis),
+                Arc::clone(&rabbitmq),
+                Arc::clone(&ws_manager),
+                exchange.clone(),
+                celebrity_threshold,
+                feed_limits,
+                ConsumerConfig::from_env()
+            );
+            // Author timelines of the key layout before hash tags never expire, they are dropped once in the background
+            let legacy_cache = PostCacheImpl::new(Arc::clone(&redis), feed_limits, false);
+            tokio::spawn(async move {
+                match legacy_cache.delete_legacy_keys().await {
+                    Ok(deleted) if deleted > 0 => tracing::info!("Deleted {} legacy author timelines", deleted),
+                    Ok(_) => {},
+                    Err(e) => tracing::warn!("Failed to delete legacy author timelines {:?}", e)
+                }
+            });
+            let feed_cache: Arc<dyn FeedCache + Send + Sync> = Arc::new(PostCacheImpl::new(Arc::clone(&redis), feed_limits, false));
+            (post_service, feed_warmer, feed_cache, followers_service, None, None)
+        };
+        let reaction_service = match dev_posts {
+            Some(posts) => post::reaction::service_provider::create_in_memory_service(posts, Arc::clone(&ws_manager)),
+            None => post::reaction::service_provider::create_service(
+                Arc::clone(&master_pool),
+                Arc::clone(&redis),
+                Arc::clone(&ws_manager)
+            )
+        };
+        let comment_service = post::comment::service_provider::create_service(
             Arc::clone(&master_pool),
             Arc::clone(&redis),
+            feed_limits,
+            Arc::clone(&local_posts)
+        );
+        let dev_users = dev_friends.clone().map(InMemoryUserRepository::new);
+        let cache_invalidator = Arc::new(CacheInvalidator::new(
+            local_posts,
             Arc::clone(&rabbitmq),
-            Arc::clone(&ws_manager),
             exchange.clone()
+        ));
+        let outbox_relay = post::outbox::service_provider::create_relay(
+            Arc::clone(&master_pool),
+            Arc::clone(&rabbit_publisher),
+            OutboxConfig::from_env()
         );
+        let draft_service = post::draft::service_provider::create_service(
+            Arc::clone(&master_pool),
+            Arc::clone(&post_service)
+        );
+        let admin_ids: Vec<uuid::Uuid> = env::var("ADMIN_USER_IDS")
+            .map(|ids| ids.split(',').filter_map(|id| uuid::Uuid::parse_str(id.trim()).ok()).collect())
+            .unwrap_or_default();
+        let moderation_service = post::moderation::service_provider::create_service(
+            Arc::clone(&master_pool),
+            Arc::clone(&post_service),
+            admin_ids.clone()
+        );
+        let warmup_service = post::warmup::service_provider::create_service(
+            Arc::clone(&master_pool),
+            feed_warmer,
+            admin_ids.clone(),
+            WarmupConfig::from_env()
+        );
+        let dlq_service = post::dlq::service_provider::create_service(
+            rabbitmq,
+            rabbit_publisher,
+            admin_ids,
+            DlqConfig::from_env()
+        );
+        let idempotency_ttl_seconds = env::var("IDEMPOTENCY_TTL_SECONDS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(86400);
+        let idempotency_lock_seconds = env::var("IDEMPOTENCY_LOCK_SECONDS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(60);
+        let idempotency_store = Arc::new(IdempotencyStore::new(Arc::clone(&redis), idempotency_ttl_seconds, idempotency_lock_seconds));
+        let feed_cache_metrics = Arc::new(FeedCacheMetrics::new(
+            Arc::clone(&redis),
+            env::var("FEED_CACHE_METRICS_INTERVAL_SECONDS").ok().and_then(|v| v.parse().ok()).unwrap_or(60),
+            env::var("FEED_CACHE_MEMORY_BUDGET_BYTES").ok().and_then(|v| v.parse().ok())
+        ));
         let port = env::var("APPLICATION_PORT").ok().map(|port| port.parse().unwrap()).unwrap();
         let mut config = Configuration::new();   
         if let Some(messenger_url) = env::var("MESSENGER_URL").ok() {
@@ -107,7 +297,24 @@ impl AppState {
                 post_service,        
                 dialog_service,         
                 ws_manager,
-                followers_service: followers_service
+                followers_service: followers_service,
+                reaction_service,
+                comment_service,
+                draft_service,
+                moderation_service,
+                warmup_service,
+                dlq_service,
+                celebrity_threshold,
+                feed_cache,
+                idempotency_store,
+                feed_cache_metrics,
+                post_cache_metrics,
+                publisher_metrics,
+                cache_invalidator,
+                outbox_relay,
+                dev_profile,
+                dev_friends,
+                dev_users
             }
         )
     }
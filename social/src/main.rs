@@ -1,6 +1,6 @@
 use tokio_postgres::{Error};
 use dotenv::dotenv;
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 use app_state::AppState;
 use application::Application;
 use axum::{
@@ -13,7 +13,7 @@ use tower_http::{
     trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer},
 };
 use tracing::Level;
-use crate::{middleware::{CURRENT_CONTEXT, RequestContext}, modules::post::followers::async_handler::post_feed_ws_handler};
+use crate::{middleware::{CURRENT_CONTEXT, RequestContext}, modules::{common::{dev_profile::dev_profile_middleware::dev_profile_middleware, idempotency::idempotency_middleware::idempotency_middleware, metrics::metrics_handler::metrics_handler}, post::followers::async_handler::post_feed_ws_handler}};
 use axum::{
     response::Response,
     http::Request,
@@ -49,6 +49,7 @@ async fn request_id_context_middleware(req: Request<axum::body::Body>, next: axu
 fn async_routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/post/feed/posted", get(post_feed_ws_handler))
+        .route("/metrics", get(metrics_handler))
         .with_state(state)
 }
 
@@ -68,12 +69,22 @@ fn init_env() {
 async fn main() -> Result<(), Error> {
     init_env();
     let app_state = Arc::new(AppState::init().await.unwrap());
-    migrations::run_migrations(app_state.clone()).await;
-    load_metric_utils::generate_load_data(app_state.clone()).await;    
+    // The dev profile keeps its data in memory and never connects to Postgres
+    if !app_state.dev_profile {
+        migrations::run_migrations(app_state.clone()).await;
+        load_metric_utils::generate_load_data(app_state.clone()).await;    
+    }
     let sync_routes = openapi::server::new(Application::new(Arc::clone(&app_state)));
     let async_routes = async_routes(Arc::clone(&app_state));
     let x_request_id = HeaderName::from_static("x-request-id");
-    let app = sync_routes.merge(async_routes)        
+    let mut app = sync_routes.merge(async_routes);
+    // The dev profile turns off the routes it has no stores for. Idempotency keys are kept in Redis, which it runs without
+    if app_state.dev_profile {
+        app = app.layer(axum::middleware::from_fn(dev_profile_middleware));
+    } else {
+        app = app.layer(axum::middleware::from_fn_with_state(Arc::clone(&app_state), idempotency_middleware));
+    }
+    let app = app
         .layer(PropagateRequestIdLayer::new(x_request_id.clone()))        
         .layer(
             TraceLayer::new_for_http()
@@ -88,12 +99,37 @@ async fn main() -> Result<(), Error> {
         .layer(SetRequestIdLayer::new(x_request_id.clone(), MakeRequestUuid));
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", app_state.port)).await.unwrap();
     tracing::info!("Server is running on port {}", app_state.port);    
-    let followers_service = Arc::clone(&app_state.followers_service);    
-    tokio::spawn(async move {
-        if let Err(e) = followers_service.run_consumer().await {
-            tracing::error!("RabbitMQ Consumer error: {:?}", e);
-        }
-    });
-    axum::serve(listener, app).await.unwrap();
+    // The dev profile handles post events in process and has no Redis or broker to watch
+    if !app_state.dev_profile {
+        let followers_service = Arc::clone(&app_state.followers_service);    
+        tokio::spawn(async move {
+            if let Err(e) = followers_service.run_consumer().await {
+                tracing::error!("RabbitMQ Consumer error: {:?}", e);
+            }
+        });
+        let outbox_relay = Arc::clone(&app_state.outbox_relay);
+        tokio::spawn(async move {
+            outbox_relay.run().await;
+        });
+        let cache_invalidator = Arc::clone(&app_state.cache_invalidator);
+        tokio::spawn(async move {
+            if let Err(e) = cache_invalidator.run_consumer().await {
+                tracing::error!("Cache invalidator error: {:?}", e);
+            }
+        });
+        let feed_cache_metrics = Arc::clone(&app_state.feed_cache_metrics);
+        tokio::spawn(async move {
+            feed_cache_metrics.run_collector().await;
+        });
+        let warmup_service = Arc::clone(&app_state.warmup_service);
+        tokio::spawn(async move {
+            warmup_service.run_on_startup().await;
+        });
+        let draft_service = Arc::clone(&app_state.draft_service);
+        tokio::spawn(async move {
+            draft_service.run_scheduler().await;
+        });
+    }
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
     Ok(())
 } 
\ No newline at end of file
@@ -6,4 +6,10 @@ pub async fn authenticate_user(client: Object, id: &Uuid, password: &String) ->
     let res = client.query_one("SELECT pwd FROM users WHERE id=$1", &[&id]).await.unwrap();
     let hash: String = res.get(0);    
     Ok(password_hash::check_password(password, hash))
+}
+
+/// Recently logged in users are the first to get their feeds warmed up
+pub async fn touch_last_login(client: Object, id: &Uuid) -> Result<(), tokio_postgres::Error> {
+    client.execute("UPDATE users SET last_login_at=NOW() WHERE id=$1", &[&id]).await?;
+    Ok(())
 }
\ No newline at end of file
@@ -24,16 +24,29 @@ impl Auth for Application {
             Ok(id) => id,
             Err(_) => return Ok(LoginPostResponse::Status400)
         };
-        match auth_service::authenticate_user(
-            self.state.get_master_client().await,
-            &uuid,
-            &login_data.password,
-        ).await {
-            Ok(true) => 
+        let authenticated = match &self.state.dev_users {
+            Some(users) => users.authenticate(&uuid, &login_data.password),
+            None => auth_service::authenticate_user(
+                self.state.get_master_client().await,
+                &uuid,
+                &login_data.password,
+            ).await
+        };
+        match authenticated {
+            Ok(true) => {
+                // Last logins only order the feed warmup, which the dev profile does not run
+                let touched = match &self.state.dev_users {
+                    Some(_) => Ok(()),
+                    None => auth_service::touch_last_login(self.state.get_master_client().await, &uuid).await
+                };
+                if let Err(e) = touched {
+                    tracing::warn!("Failed to update last login of {}: {:?}", uuid, e);
+                }
                 match auth::create_token(&uuid, self.state.secret.as_bytes(), self.state.jwt_token_ttl_minutes) {
                     Ok(token) => Ok(LoginPostResponse::Status200(models::LoginPost200Response{token: Some(token)})),
                     Err(_) => Ok(LoginPostResponse::Status400)
-                },
+                }
+            },
             _ => Ok(
                 LoginPostResponse::Status400
             )
@@ -0,0 +1,33 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use openapi::models;
+
+/// Routes backed by Postgres, Redis or RabbitMQ, none of which the dev profile runs.
+/// Comments, drafts, reports, moderation and the admin tools have no in-memory stores
+fn is_turned_off(path: &str) -> bool {
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["moderation", ..] | ["admin", ..] => true,
+        ["post", "draft" | "drafts" | "comment", ..] => true,
+        ["post", _, "comment" | "comments" | "report"] => true,
+        _ => false
+    }
+}
+
+/// Answers the routes the dev profile cannot serve with 501 instead of letting them reach a store that is not there
+pub async fn dev_profile_middleware(req: Request<Body>, next: Next) -> Response {
+    if !is_turned_off(req.uri().path()) {
+        return next.run(req).await;
+    }
+    let body = models::LoginPost500Response {
+        message: format!("{} is not available in the dev profile", req.uri().path()),
+        request_id: None,
+        code: None
+    };
+    (StatusCode::NOT_IMPLEMENTED, Json(body)).into_response()
+}
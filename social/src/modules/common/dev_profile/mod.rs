@@ -0,0 +1 @@
+pub mod dev_profile_middleware;
@@ -1 +1,2 @@
-pub mod extensions;
\ No newline at end of file
+pub mod extensions;
+pub mod redis_ext;
\ No newline at end of file
@@ -0,0 +1,38 @@
+use fred::prelude::*;
+use fred::error::Error;
+use fred::util::redis_keyslot;
+use std::collections::HashMap;
+
+/// MGET which works on a cluster: keys are split per hash slot and read with one MGET per slot.
+/// The values come back in the order of `keys`
+pub async fn mget_by_slot(client: &Client, keys: Vec<String>, from_replicas: bool) -> Result<Vec<Option<String>>, Error> {
+    let keys_len = keys.len();
+    let groups: Vec<Vec<(usize, String)>> = if client.is_clustered() {
+        let mut slots: HashMap<u16, Vec<(usize, String)>> = HashMap::new();
+        for (idx, key) in keys.into_iter().enumerate() {
+            slots.entry(redis_keyslot(key.as_bytes())).or_default().push((idx, key));
+        }
+        slots.into_values().collect()
+    } else {
+        vec!(keys.into_iter().enumerate().collect())
+    };
+    // Commands issued concurrently on one client are pipelined by the driver
+    let reads = groups.iter().map(|group| {
+        let keys: Vec<String> = group.iter().map(|(_, key)| key.clone()).collect();
+        async move {
+            if from_replicas {
+                client.replicas().mget::<Vec<Option<String>>, _>(keys).await
+            } else {
+                client.mget::<Vec<Option<String>>, _>(keys).await
+            }
+        }
+    });
+    let results = futures::future::try_join_all(reads).await?;
+    let mut values = vec![None; keys_len];
+    for (group, group_values) in groups.iter().zip(results) {
+        for ((idx, _), value) in group.iter().zip(group_values) {
+            values[*idx] = value;
+        }
+    }
+    Ok(values)
+}
@@ -0,0 +1,100 @@
+use std::{net::SocketAddr, sync::Arc};
+use axum::{
+    body::{Body, to_bytes},
+    extract::{ConnectInfo, State},
+    http::{HeaderName, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use reqwest::header::AUTHORIZATION;
+use crate::app_state::AppState;
+use crate::modules::auth::auth::verify_token;
+use crate::modules::common::ext::extensions::ResultExt;
+use crate::modules::common::idempotency::idempotency_store::StoredResponse;
+
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+const IDEMPOTENCY_REPLAYED_HEADER: &str = "idempotency-replayed";
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+const MAX_KEY_LENGTH: usize = 255;
+
+/// Replays the stored response for POST and PUT requests repeated with the same `Idempotency-Key`
+pub async fn idempotency_middleware(State(state): State<Arc<AppState>>, req: Request<Body>, next: Next) -> Response {
+    if req.method() != Method::POST && req.method() != Method::PUT {
+        return next.run(req).await;
+    }
+    let key = req.headers().get(IDEMPOTENCY_KEY_HEADER).map(|value| value.to_str().map(|key| key.to_string()));
+    let key = match key {
+        None => return next.run(req).await,
+        Some(Ok(key)) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => key,
+        Some(_) => return (StatusCode::BAD_REQUEST, "Invalid Idempotency-Key").into_response()
+    };
+    // Keys are per user, so one user can never get a response stored for another.
+    // Requests without a user are kept apart by the client address
+    let scope = req.headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| verify_token(token, state.secret.as_bytes()).ok())
+        .map(|claims| claims.user_id.to_string())
+        .or_else(|| req.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| format!("ip/{}", addr.ip())));
+    let Some(scope) = scope else {
+        return next.run(req).await;
+    };
+    let (parts, body) = req.into_parts();
+    let bytes = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return (StatusCode::PAYLOAD_TOO_LARGE, "Request body is too large").into_response()
+    };
+    let target = parts.uri.path_and_query().map(|target| target.as_str()).unwrap_or(parts.uri.path());
+    let request = format!("{} {}\n{}", parts.method, target, String::from_utf8_lossy(&bytes));
+    let store = &state.idempotency_store;
+    match store.begin(&scope, &key, &request).await {
+        Ok(None) => {},
+        Ok(Some(record)) if record.request != request =>
+            return (StatusCode::UNPROCESSABLE_ENTITY, "Idempotency-Key was used with another request").into_response(),
+        Ok(Some(record)) => return match record.response {
+            Some(response) => replay(response),
+            None => (StatusCode::CONFLICT, "Request with this Idempotency-Key is in progress").into_response()
+        },
+        Err(e) => {
+            tracing::warn!("Idempotency check failed, processing request as is: {}", e);
+            return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+        }
+    }
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    if response.status().is_server_error() {
+        store.abort(&scope, &key).await.warn(format!("Failed to release idempotency key {}", key));
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to read response body: {}", e);
+            store.abort(&scope, &key).await.warn(format!("Failed to release idempotency key {}", key));
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let stored = StoredResponse {
+        status: parts.status.as_u16(),
+        headers: parts.headers.iter()
+            .filter_map(|(name, value)| value.to_str().ok().map(|value| (name.to_string(), value.to_string())))
+            .collect(),
+        body: bytes.to_vec(),
+    };
+    store.complete(&scope, &key, &request, stored).await.warn(format!("Failed to store response for idempotency key {}", key));
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let mut response = Response::new(Body::from(stored.body));
+    *response.status_mut() = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let headers = response.headers_mut();
+    for (name, value) in stored.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            headers.append(name, value);
+        }
+    }
+    headers.insert(IDEMPOTENCY_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
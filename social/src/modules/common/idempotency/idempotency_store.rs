@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use fred::prelude::*;
+use fred::error::Error;
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IdempotencyRecord {
+    pub request: String,
+    pub response: Option<StoredResponse>,
+}
+
+pub struct IdempotencyStore {
+    pool: Arc<Pool>,
+    /// How long a completed response is replayed
+    ttl_seconds: i64,
+    /// How long a request in progress holds its key, a crashed request frees the key once it expires
+    lock_seconds: i64,
+}
+
+impl IdempotencyStore {
+    pub fn new(pool: Arc<Pool>, ttl_seconds: i64, lock_seconds: i64) -> Self {
+        IdempotencyStore { pool, ttl_seconds, lock_seconds }
+    }
+
+    fn get_key(&self, scope: &str, key: &str) -> String {
+        format!("highload/idempotency/{}/{}", scope, key)
+    }
+
+    /// Reserves the key for the request. Returns the record of an earlier request if the key is taken
+    pub async fn begin(&self, scope: &str, key: &str, request: &String) -> Result<Option<IdempotencyRecord>, Error> {
+        let redis_key = self.get_key(scope, key);
+        let record = IdempotencyRecord { request: request.clone(), response: None };
+        let json = serde_json::to_string(&record).map_err(|e| Error::new(ErrorKind::Parse, e.to_string()))?;
+        let reserved: Option<String> = self.pool.next().set(
+            &redis_key,
+            json,
+            Some(Expiration::EX(self.lock_seconds)),
+            Some(SetOptions::NX),
+            false
+        ).await?;
+        if reserved.is_some() {
+            return Ok(None);
+        }
+        let existing: Option<String> = self.pool.next().get(&redis_key).await?;
+        existing
+            .map(|json| serde_json::from_str(&json).map_err(|e| Error::new(ErrorKind::Parse, e.to_string())))
+            .transpose()
+    }
+
+    pub async fn complete(&self, scope: &str, key: &str, request: &String, response: StoredResponse) -> Result<(), Error> {
+        let record = IdempotencyRecord { request: request.clone(), response: Some(response) };
+        let json = serde_json::to_string(&record).map_err(|e| Error::new(ErrorKind::Parse, e.to_string()))?;
+        self.pool.next().set(
+            self.get_key(scope, key),
+            json,
+            Some(Expiration::EX(self.ttl_seconds)),
+            Some(SetOptions::XX),
+            false
+        ).await
+    }
+
+    /// Releases the key so the request may be retried
+    pub async fn abort(&self, scope: &str, key: &str) -> Result<(), Error> {
+        self.pool.next().del(self.get_key(scope, key)).await
+    }
+}
@@ -0,0 +1,2 @@
+pub mod idempotency_store;
+pub mod idempotency_middleware;
\ No newline at end of file
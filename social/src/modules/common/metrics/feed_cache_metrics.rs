@@ -0,0 +1,84 @@
+use std::{sync::{Arc, atomic::{AtomicU64, Ordering}}, time::Duration};
+use fred::prelude::*;
+use fred::error::Error;
+use fred::types::scan::{ScanResult, ScanType, Scanner};
+use futures::{Stream, TryStreamExt};
+use std::pin::Pin;
+
+const FEED_KEYS_PATTERN: &str = "highload/post/feed/*/ids";
+const SCAN_PAGE_SIZE: u32 = 1000;
+
+/// Periodically measured size of the cached feed zsets
+pub struct FeedCacheMetrics {
+    pool: Arc<Pool>,
+    interval_seconds: u64,
+    memory_budget_bytes: Option<u64>,
+    keys: AtomicU64,
+    memory_bytes: AtomicU64,
+}
+
+impl FeedCacheMetrics {
+    pub fn new(pool: Arc<Pool>, interval_seconds: u64, memory_budget_bytes: Option<u64>) -> Self {
+        FeedCacheMetrics {
+            pool,
+            interval_seconds,
+            memory_budget_bytes,
+            keys: AtomicU64::new(0),
+            memory_bytes: AtomicU64::new(0),
+        }
+    }
+
+    pub fn keys(&self) -> u64 {
+        self.keys.load(Ordering::Relaxed)
+    }
+
+    pub fn memory_bytes(&self) -> u64 {
+        self.memory_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn memory_budget_bytes(&self) -> Option<u64> {
+        self.memory_budget_bytes
+    }
+
+    /// SCAN walks the keyspace in pages, so measuring does not block Redis the way KEYS would.
+    /// On a cluster every primary holds its own part of the keyspace and is scanned in turn
+    async fn measure(&self) -> Result<(u64, u64), Error> {
+        let client = self.pool.next();
+        let mut pages: Pin<Box<dyn Stream<Item = Result<ScanResult, Error>> + Send>> = if client.is_clustered() {
+            Box::pin(client.scan_cluster(FEED_KEYS_PATTERN, Some(SCAN_PAGE_SIZE), Some(ScanType::ZSet)))
+        } else {
+            Box::pin(client.scan(FEED_KEYS_PATTERN, Some(SCAN_PAGE_SIZE), Some(ScanType::ZSet)))
+        };
+        let (mut keys, mut memory_bytes) = (0u64, 0u64);
+        while let Some(mut page) = pages.try_next().await? {
+            if let Some(page_keys) = page.take_results() && !page_keys.is_empty() {
+                let pipeline = client.pipeline();
+                for key in &page_keys {
+                    let _: () = pipeline.memory_usage(key, Some(0)).await?;
+                }
+                let usages: Vec<Option<u64>> = pipeline.all().await?;
+                keys += page_keys.len() as u64;
+                memory_bytes += usages.into_iter().flatten().sum::<u64>();
+            }
+            let _ = page.next();
+        }
+        Ok((keys, memory_bytes))
+    }
+
+    pub async fn run_collector(&self) -> () {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.interval_seconds));
+        loop {
+            interval.tick().await;
+            match self.measure().await {
+                Ok((keys, memory_bytes)) => {
+                    self.keys.store(keys, Ordering::Relaxed);
+                    self.memory_bytes.store(memory_bytes, Ordering::Relaxed);
+                    if let Some(budget) = self.memory_budget_bytes && memory_bytes > budget {
+                        tracing::warn!("Feed cache uses {} bytes in {} feeds, over the budget of {} bytes", memory_bytes, keys, budget);
+                    }
+                },
+                Err(e) => tracing::warn!("Failed to measure feed cache {:?}", e)
+            }
+        }
+    }
+}
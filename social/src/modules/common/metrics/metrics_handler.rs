@@ -0,0 +1,70 @@
+use std::sync::Arc;
+use axum::{extract::State, http::header, response::IntoResponse};
+use crate::app_state::AppState;
+use crate::modules::common::metrics::publisher_metrics::{LATENCY_BUCKETS, PublishOutcome};
+
+/// Serves the metrics in the Prometheus text format
+pub async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let metrics = &state.feed_cache_metrics;
+    let mut body = format!(
+        "# HELP highload_feed_cache_keys Number of cached feeds\n\
+        # TYPE highload_feed_cache_keys gauge\n\
+        highload_feed_cache_keys {}\n\
+        # HELP highload_feed_cache_memory_bytes Memory used by cached feeds\n\
+        # TYPE highload_feed_cache_memory_bytes gauge\n\
+        highload_feed_cache_memory_bytes {}\n",
+        metrics.keys(),
+        metrics.memory_bytes()
+    );
+    if let Some(budget) = metrics.memory_budget_bytes() {
+        body.push_str(&format!(
+            "# HELP highload_feed_cache_memory_budget_bytes Memory budget of cached feeds\n\
+            # TYPE highload_feed_cache_memory_budget_bytes gauge\n\
+            highload_feed_cache_memory_budget_bytes {}\n",
+            budget
+        ));
+    }
+    let posts = &state.post_cache_metrics;
+    body.push_str(&format!(
+        "# HELP highload_post_cache_hits_total Post cache hits per tier\n\
+        # TYPE highload_post_cache_hits_total counter\n\
+        highload_post_cache_hits_total{{tier=\"local\"}} {}\n\
+        highload_post_cache_hits_total{{tier=\"redis\"}} {}\n\
+        # HELP highload_post_cache_misses_total Post cache misses per tier\n\
+        # TYPE highload_post_cache_misses_total counter\n\
+        highload_post_cache_misses_total{{tier=\"local\"}} {}\n\
+        highload_post_cache_misses_total{{tier=\"redis\"}} {}\n",
+        posts.local_hits(),
+        posts.redis_hits(),
+        posts.local_misses(),
+        posts.redis_misses()
+    ));
+    let publisher = &state.publisher_metrics;
+    body.push_str(
+        "# HELP highload_events_published_total Events published to the broker by outcome\n\
+        # TYPE highload_events_published_total counter\n"
+    );
+    for outcome in PublishOutcome::ALL {
+        body.push_str(&format!(
+            "highload_events_published_total{{outcome=\"{}\"}} {}\n",
+            outcome.as_str(),
+            publisher.outcome(outcome)
+        ));
+    }
+    body.push_str(
+        "# HELP highload_event_publish_seconds Time from publish to the broker confirm\n\
+        # TYPE highload_event_publish_seconds histogram\n"
+    );
+    for (bound, count) in LATENCY_BUCKETS.iter().zip(publisher.latency_buckets()) {
+        body.push_str(&format!("highload_event_publish_seconds_bucket{{le=\"{}\"}} {}\n", bound, count));
+    }
+    body.push_str(&format!(
+        "highload_event_publish_seconds_bucket{{le=\"+Inf\"}} {}\n\
+        highload_event_publish_seconds_sum {}\n\
+        highload_event_publish_seconds_count {}\n",
+        publisher.latency_count(),
+        publisher.latency_sum_seconds(),
+        publisher.latency_count()
+    ));
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
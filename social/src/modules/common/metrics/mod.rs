@@ -0,0 +1,4 @@
+pub mod feed_cache_metrics;
+pub mod post_cache_metrics;
+pub mod publisher_metrics;
+pub mod metrics_handler;
\ No newline at end of file
@@ -0,0 +1,42 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Hit and miss counters of the post cache tiers: the in-process cache and Redis behind it
+#[derive(Default)]
+pub struct PostCacheMetrics {
+    local_hits: AtomicU64,
+    local_misses: AtomicU64,
+    redis_hits: AtomicU64,
+    redis_misses: AtomicU64,
+}
+
+impl PostCacheMetrics {
+    pub fn new() -> Self {
+        PostCacheMetrics::default()
+    }
+
+    pub fn record_local(&self, hits: u64, misses: u64) {
+        self.local_hits.fetch_add(hits, Ordering::Relaxed);
+        self.local_misses.fetch_add(misses, Ordering::Relaxed);
+    }
+
+    pub fn record_redis(&self, hits: u64, misses: u64) {
+        self.redis_hits.fetch_add(hits, Ordering::Relaxed);
+        self.redis_misses.fetch_add(misses, Ordering::Relaxed);
+    }
+
+    pub fn local_hits(&self) -> u64 {
+        self.local_hits.load(Ordering::Relaxed)
+    }
+
+    pub fn local_misses(&self) -> u64 {
+        self.local_misses.load(Ordering::Relaxed)
+    }
+
+    pub fn redis_hits(&self) -> u64 {
+        self.redis_hits.load(Ordering::Relaxed)
+    }
+
+    pub fn redis_misses(&self) -> u64 {
+        self.redis_misses.load(Ordering::Relaxed)
+    }
+}
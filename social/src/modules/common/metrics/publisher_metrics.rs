@@ -0,0 +1,71 @@
+use std::{sync::atomic::{AtomicU64, Ordering}, time::Duration};
+
+/// Upper bounds of the publish latency histogram buckets, in seconds
+pub const LATENCY_BUCKETS: [f64; 10] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+#[derive(Clone, Copy, Debug)]
+pub enum PublishOutcome {
+    Acked,
+    Nacked,
+    Returned,
+    Timeout,
+    Error,
+}
+
+impl PublishOutcome {
+    pub const ALL: [PublishOutcome; 5] = [PublishOutcome::Acked, PublishOutcome::Nacked, PublishOutcome::Returned, PublishOutcome::Timeout, PublishOutcome::Error];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PublishOutcome::Acked => "acked",
+            PublishOutcome::Nacked => "nacked",
+            PublishOutcome::Returned => "returned",
+            PublishOutcome::Timeout => "timeout",
+            PublishOutcome::Error => "error",
+        }
+    }
+}
+
+/// Outcomes and confirm latency of events published to the broker
+#[derive(Default)]
+pub struct PublisherMetrics {
+    outcomes: [AtomicU64; 5],
+    latency_buckets: [AtomicU64; 10],
+    latency_count: AtomicU64,
+    latency_sum_micros: AtomicU64,
+}
+
+impl PublisherMetrics {
+    pub fn new() -> Self {
+        PublisherMetrics::default()
+    }
+
+    pub fn record(&self, outcome: PublishOutcome, latency: Duration) {
+        self.outcomes[outcome as usize].fetch_add(1, Ordering::Relaxed);
+        let seconds = latency.as_secs_f64();
+        for (bucket, bound) in self.latency_buckets.iter().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.latency_count.fetch_add(1, Ordering::Relaxed);
+        self.latency_sum_micros.fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn outcome(&self, outcome: PublishOutcome) -> u64 {
+        self.outcomes[outcome as usize].load(Ordering::Relaxed)
+    }
+
+    /// Cumulative counts per bucket of `LATENCY_BUCKETS`
+    pub fn latency_buckets(&self) -> Vec<u64> {
+        self.latency_buckets.iter().map(|bucket| bucket.load(Ordering::Relaxed)).collect()
+    }
+
+    pub fn latency_count(&self) -> u64 {
+        self.latency_count.load(Ordering::Relaxed)
+    }
+
+    pub fn latency_sum_seconds(&self) -> f64 {
+        self.latency_sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+    }
+}
@@ -1,2 +1,5 @@
 pub mod ws;
-pub mod ext;
\ No newline at end of file
+pub mod ext;
+pub mod idempotency;
+pub mod metrics;
+pub mod dev_profile;
\ No newline at end of file
@@ -1,5 +1,5 @@
 use dashmap::DashMap;
-use tokio::sync::{mpsc::{UnboundedSender, unbounded_channel}};
+use tokio::sync::{broadcast, mpsc::{UnboundedSender, unbounded_channel}};
 use axum::extract::ws::{Message, WebSocket};
 use uuid::Uuid;
 use futures_util::StreamExt;
@@ -11,13 +11,41 @@ use serde_json::Error;
 type UserId = Uuid;
 type Tx = UnboundedSender<Message>; 
 
+/// Messages an author channel buffers for a slow connection before it skips them
+const AUTHOR_CHANNEL_CAPACITY: usize = 256;
+
 pub struct WebSocketManager {
-    user_connections: DashMap<UserId, Vec<Tx>>
+    user_connections: DashMap<UserId, Vec<Tx>>,
+    /// Broadcast channels of celebrities, whose followers are not loaded to notify them one by one
+    author_channels: DashMap<UserId, broadcast::Sender<Message>>
 }
 
 impl WebSocketManager {
     pub fn new() -> Self {
-        WebSocketManager { user_connections: DashMap::new() }
+        WebSocketManager { user_connections: DashMap::new(), author_channels: DashMap::new() }
+    }
+
+    fn subscribe(&self, author_id: UserId) -> broadcast::Receiver<Message> {
+        self.author_channels
+            .entry(author_id)
+            .or_insert_with(|| broadcast::channel(AUTHOR_CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Drops channels of the authors nobody listens to anymore
+    fn unsubscribe(&self, author_ids: &[UserId]) {
+        for author_id in author_ids {
+            self.author_channels.remove_if(author_id, |_, sender| sender.receiver_count() == 0);
+        }
+    }
+
+    /// Sends the message once to every connection subscribed to the author, returns the number of them
+    pub fn send_to_subscribers<T: Serialize>(&self, author_id: UserId, message: &T) -> Result<usize, Error> {
+        let Some(sender) = self.author_channels.get(&author_id) else {
+            return Ok(0);
+        };
+        let msg = Message::Text(serde_json::to_string(message)?.into());
+        Ok(sender.send(msg).unwrap_or(0))
     }
 
     pub fn register(&self, user_id: UserId, tx: Tx) {
@@ -61,11 +89,28 @@ impl WebSocketManager {
         Ok(results)
     }
 
-    pub async fn handle_connection(&self, user_id: UserId, socket: WebSocket) {
+    /// Serves the connection of the user, which also gets what the followed celebrities broadcast.
+    /// Celebrities are subscribed to on connect, the ones followed later are picked up on reconnect
+    pub async fn handle_connection(&self, user_id: UserId, socket: WebSocket, celebrities: Vec<UserId>) {
         let (mut sender, mut receiver) = socket.split();
         let (tx, mut rx) = unbounded_channel::<Message>();
 
         self.register(user_id, tx.clone());
+        let subscriptions: Vec<_> = celebrities.iter()
+            .map(|author_id| {
+                let mut author_rx = self.subscribe(*author_id);
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    loop {
+                        match author_rx.recv().await {
+                            Ok(msg) => if tx.send(msg).is_err() { break; },
+                            Err(broadcast::error::RecvError::Lagged(skipped)) => tracing::warn!("Connection skipped {} broadcast messages", skipped),
+                            Err(broadcast::error::RecvError::Closed) => break
+                        }
+                    }
+                })
+            })
+            .collect();
 
         let mut send_task = tokio::spawn(async move {
             while let Some(msg) = rx.recv().await {
@@ -90,6 +135,12 @@ impl WebSocketManager {
             _ = (&mut recv_task) => send_task.abort(),
         }
 
+        // Aborted tasks are awaited, so their receivers are dropped before the channels are checked
+        for subscription in subscriptions {
+            subscription.abort();
+            let _ = subscription.await;
+        }
+        self.unsubscribe(&celebrities);
         self.unregister(user_id, &tx);
     }
 }
\ No newline at end of file
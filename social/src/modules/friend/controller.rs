@@ -8,6 +8,7 @@ use crate::modules::friend::friend_service;
 use uuid::Uuid;
 use crate::modules::auth::auth;
 use crate::Application;
+use crate::modules::common::ext::extensions::ResultExt;
 
 #[async_trait]
 impl Friend for Application {
@@ -25,11 +26,26 @@ impl Friend for Application {
             Ok(id) => id,
             Err(_) => return Ok(FriendSetUserIdPutResponse::Status400)
         };
-        match friend_service::add_friend(
-            self.state.get_master_client().await, 
-            claims.user_id, 
-            uuid
-        ).await {
+        // The dev profile keeps the follower graph in memory only
+        let res = match &self.state.dev_friends {
+            Some(friends) => friends.follow(claims.user_id, uuid, self.state.celebrity_threshold),
+            None => friend_service::add_friend(
+                self.state.get_master_client().await, 
+                claims.user_id, 
+                uuid,
+                self.state.celebrity_threshold
+            ).await
+        };
+        if let Ok(change) = &res {
+            self.refresh_crossed_celebrities(&change.crossed_threshold).await;
+        }
+        let res = res.map(|change| change.result);
+        // The user may follow a celebrity now, whose posts are read from the timeline rather than fanned out
+        if let Ok(friend_service::FriendshipCreateResult::Mutual | friend_service::FriendshipCreateResult::Subscribed) = &res {
+            self.state.feed_cache.delete_followed_celebrities(claims.user_id).await
+                .warn(format!("Failed to drop celebrities followed by {}", claims.user_id));
+        }
+        match res {
             Ok(friend_service::FriendshipCreateResult::Mutual) => Ok(FriendSetUserIdPutResponse::Status200),
             Ok(friend_service::FriendshipCreateResult::Subscribed) => Ok(FriendSetUserIdPutResponse::Status200),
             Ok(friend_service::FriendshipCreateResult::AlreadyExists) => Ok(FriendSetUserIdPutResponse::Status400),             
@@ -54,18 +70,36 @@ impl Friend for Application {
         _: &CookieJar,
         claims: &Self::Claims,
       path_params: &models::FriendDeleteUserIdPutPathParams,
+      query_params: &models::FriendDeleteUserIdPutQueryParams,
     ) -> Result<FriendDeleteUserIdPutResponse, ()> {
         let cur_user_id = match Uuid::parse_str(&path_params.user_id) {
             Ok(id) => id,
             Err(_) => return Ok(FriendDeleteUserIdPutResponse::Status400)
         };        
-        match friend_service::delete_friend(
-            self.state.get_master_client().await, 
-            claims.user_id, 
-            cur_user_id,
-            false
-        ).await {
-            Ok(friend_service::FriendshipEndResult::Removed) => Ok(FriendDeleteUserIdPutResponse::Status200),
+        let block = query_params.block.unwrap_or(false);
+        let res = match &self.state.dev_friends {
+            Some(friends) => friends.unfollow(claims.user_id, cur_user_id, block, self.state.celebrity_threshold),
+            None => friend_service::delete_friend(
+                self.state.get_master_client().await, 
+                claims.user_id, 
+                cur_user_id,
+                block,
+                self.state.celebrity_threshold
+            ).await
+        };
+        if let Ok(change) = &res {
+            self.refresh_crossed_celebrities(&change.crossed_threshold).await;
+        }
+        let res = res.map(|change| change.result);
+        // Either user may have stopped following the other, a block drops the links in both directions
+        if let Ok(friend_service::FriendshipEndResult::Unsubscribed | friend_service::FriendshipEndResult::Blocked) = &res {
+            for user_id in [claims.user_id, cur_user_id] {
+                self.state.feed_cache.delete_followed_celebrities(user_id).await
+                    .warn(format!("Failed to drop celebrities followed by {}", user_id));
+            }
+        }
+        match res {
+            Ok(friend_service::FriendshipEndResult::Blocked) => Ok(FriendDeleteUserIdPutResponse::Status200),
             Ok(friend_service::FriendshipEndResult::Unsubscribed) => Ok(FriendDeleteUserIdPutResponse::Status200),
             Ok(friend_service::FriendshipEndResult::NotInFriendship) => Ok(FriendDeleteUserIdPutResponse::Status400),             
             Err(e) => {
@@ -82,3 +116,14 @@ impl Friend for Application {
         }
     }
 }
+
+impl Application {
+    /// Authors who crossed the celebrity threshold are merged into feeds on read or pushed from now on,
+    /// so followers drop the celebrities they cached
+    async fn refresh_crossed_celebrities(&self, authors: &Vec<Uuid>) {
+        for author_id in authors {
+            self.state.followers_service.celebrity_changed(*author_id).await
+                .warn(format!("Failed to refresh followers of {}", author_id));
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use uuid::Uuid;
-use deadpool_postgres::{Object};
+use deadpool_postgres::{Object, Transaction};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -21,11 +21,49 @@ pub enum FriendshipCreateResult {
 }
 pub enum FriendshipEndResult {
     Unsubscribed,
-    Removed,
+    /// Links in both directions are removed and the user is blocked, whether they were friends or not
+    Blocked,
     NotInFriendship
 }
 
-pub async fn add_friend(mut client: Object, initiator_user_id: Uuid, user_id: Uuid) -> Result<FriendshipCreateResult, FriendServiceError> {         
+/// Result of a friendship change with the users whose followers count crossed the celebrity threshold by it.
+/// Feeds read celebrities by `users.followers_count`, so the ones which cached them must be refreshed
+pub struct FriendshipChange<T> {
+    pub result: T,
+    pub crossed_threshold: Vec<Uuid>,
+}
+
+impl <T> FriendshipChange<T> {
+    pub(super) fn of(result: T) -> Self {
+        FriendshipChange { result, crossed_threshold: vec!() }
+    }
+}
+
+/// Returns whether the followers count of the followed user crossed the threshold
+async fn adjust_follow_counters(tx: &Transaction<'_>, follower_id: Uuid, followed_id: Uuid, delta: i64, celebrity_threshold: u64) -> Result<bool, FriendServiceError> {
+    // Rows are locked in id order, so opposite follows of the same pair do not deadlock
+    tx.query(
+        "SELECT id FROM users WHERE id IN ($1, $2) ORDER BY id FOR UPDATE",
+        &[&follower_id, &followed_id]
+    ).await?;
+    let rows = tx.query(
+        "UPDATE users SET 
+            followers_count = followers_count + CASE WHEN id = $2 THEN $3 ELSE 0 END,
+            following_count = following_count + CASE WHEN id = $1 THEN $3 ELSE 0 END
+        WHERE id IN ($1, $2)
+        RETURNING id, followers_count",
+        &[&follower_id, &followed_id, &delta]
+    ).await?;
+    let threshold = celebrity_threshold as i64;
+    Ok(rows.iter()
+        .filter(|row| row.get::<_, Uuid>("id") == followed_id)
+        .any(|row| {
+            let count: i64 = row.get("followers_count");
+            (count >= threshold) != (count - delta >= threshold)
+        }))
+}
+
+pub async fn add_friend(mut client: Object, initiator_user_id: Uuid, user_id: Uuid, celebrity_threshold: u64) -> Result<FriendshipChange<FriendshipCreateResult>, FriendServiceError> {         
     if initiator_user_id == user_id {
         return Err(FriendServiceError::IllegalState("Cannot add self as friend".to_string()));
     }
@@ -39,49 +77,66 @@ pub async fn add_friend(mut client: Object, initiator_user_id: Uuid, user_id: Uu
         "INSERT INTO friends (user_id, friend_id) VALUES($1, $2) ON CONFLICT (user_id, friend_id) DO NOTHING",
         &[&user_id, &initiator_user_id]
     ).await?;
+    let crossed = rows_affected > 0 && adjust_follow_counters(&tx, initiator_user_id, user_id, 1, celebrity_threshold).await?;
     tx.commit().await?;
-    if rows_affected > 0 {
+    let result = if rows_affected > 0 {
         if count > 0 {
             tracing::info!("Friendship request accepted");
-            return Ok(FriendshipCreateResult::Mutual);
+            FriendshipCreateResult::Mutual
         } else {
             tracing::info!("Subscribed to {}", user_id);
-            return Ok(FriendshipCreateResult::Subscribed);
+            FriendshipCreateResult::Subscribed
         }        
     }        
     else {
         tracing::info!("Friendship request already exists");
-        return Ok(FriendshipCreateResult::AlreadyExists);
-    }    
+        FriendshipCreateResult::AlreadyExists
+    };
+    Ok(FriendshipChange { result, crossed_threshold: if crossed { vec!(user_id) } else { vec!() } })
 }
 
-async fn unsubscribe(mut client: Object, initiator_user_id: Uuid, user_id: Uuid) -> Result<FriendshipEndResult, FriendServiceError> {
+async fn unsubscribe(mut client: Object, initiator_user_id: Uuid, user_id: Uuid, celebrity_threshold: u64) -> Result<FriendshipChange<FriendshipEndResult>, FriendServiceError> {
     let tx = client.transaction().await?;
     let rows_affected = tx.execute(
         "DELETE FROM friends WHERE user_id = $1 AND friend_id = $2", 
         &[&initiator_user_id, &user_id]
     ).await?;
     if rows_affected > 0 {
-        return Ok(FriendshipEndResult::Unsubscribed);
+        let crossed = adjust_follow_counters(&tx, user_id, initiator_user_id, -1, celebrity_threshold).await?;
+        tx.commit().await?;
+        return Ok(FriendshipChange { 
+            result: FriendshipEndResult::Unsubscribed, 
+            crossed_threshold: if crossed { vec!(initiator_user_id) } else { vec!() } 
+        });
     }
-    return Ok(FriendshipEndResult::NotInFriendship);
+    return Ok(FriendshipChange::of(FriendshipEndResult::NotInFriendship));
 }
 
-async fn delete_and_block(client: Object, initiator_user_id: Uuid, user_id: Uuid) -> Result<FriendshipEndResult, FriendServiceError> {
-    let rows_affected = client.execute(
-        "DELETE FROM friends WHERE user_id = $1 AND friend_id = $2 OR user_id = $2 AND friend_id = $1", 
+async fn delete_and_block(mut client: Object, initiator_user_id: Uuid, user_id: Uuid, celebrity_threshold: u64) -> Result<FriendshipChange<FriendshipEndResult>, FriendServiceError> {
+    let tx = client.transaction().await?;
+    let rows = tx.query(
+        "DELETE FROM friends WHERE user_id = $1 AND friend_id = $2 OR user_id = $2 AND friend_id = $1 RETURNING user_id, friend_id", 
         &[&initiator_user_id, &user_id]
     ).await?;
-    if rows_affected > 0 {
-        return Ok(FriendshipEndResult::Removed);
+    let mut change = FriendshipChange::of(FriendshipEndResult::Blocked);
+    for row in &rows {
+        let followed_id: Uuid = row.get("user_id");
+        if adjust_follow_counters(&tx, row.get("friend_id"), followed_id, -1, celebrity_threshold).await? {
+            change.crossed_threshold.push(followed_id);
+        }
     }
-    return Ok(FriendshipEndResult::NotInFriendship);
+    tx.execute(
+        "INSERT INTO user_blocks (user_id, blocked_user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING", 
+        &[&initiator_user_id, &user_id]
+    ).await?;
+    tx.commit().await?;
+    Ok(change)
 }
 
-pub async fn delete_friend(client: Object, initiator_user_id: Uuid, user_id: Uuid, block: bool) -> Result<FriendshipEndResult, FriendServiceError> {         
+pub async fn delete_friend(client: Object, initiator_user_id: Uuid, user_id: Uuid, block: bool, celebrity_threshold: u64) -> Result<FriendshipChange<FriendshipEndResult>, FriendServiceError> {         
     if block {
-        delete_and_block(client, initiator_user_id, user_id).await
+        delete_and_block(client, initiator_user_id, user_id, celebrity_threshold).await
     } else {
-        unsubscribe(client, initiator_user_id, user_id).await
+        unsubscribe(client, initiator_user_id, user_id, celebrity_threshold).await
     }    
-}
\ No newline at end of file
+}
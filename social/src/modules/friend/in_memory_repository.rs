@@ -0,0 +1,111 @@
+use std::{collections::HashSet, sync::{Arc, RwLock}};
+use uuid::Uuid;
+use async_trait::async_trait;
+use crate::modules::friend::{friend_service::{FriendServiceError, FriendshipChange, FriendshipCreateResult, FriendshipEndResult}, repository::{FriendRepository, FriendRepositoryError}};
+
+/// `FriendRepository` over a process-local set of `friends` rows, for the dev profile.
+/// Clones share the same rows
+#[derive(Clone, Default)]
+pub struct InMemoryFriendRepository {
+    links: Arc<RwLock<HashSet<(Uuid, Uuid)>>>,
+    /// `user_blocks` rows as `(user_id, blocked_user_id)`
+    blocks: Arc<RwLock<HashSet<(Uuid, Uuid)>>>
+}
+
+impl InMemoryFriendRepository {
+    pub fn new() -> Self {
+        InMemoryFriendRepository::default()
+    }
+
+    /// Mirrors `friend_service::add_friend`: the initiator becomes a friend of the user
+    pub fn follow(&self, initiator_user_id: Uuid, user_id: Uuid, celebrity_threshold: u64) -> Result<FriendshipChange<FriendshipCreateResult>, FriendServiceError> {
+        if initiator_user_id == user_id {
+            return Err(FriendServiceError::IllegalState("Cannot add self as friend".to_string()));
+        }
+        let mut links = self.links.write().unwrap();
+        if !links.insert((user_id, initiator_user_id)) {
+            return Ok(FriendshipChange::of(FriendshipCreateResult::AlreadyExists));
+        }
+        let result = if links.contains(&(initiator_user_id, user_id)) {
+            FriendshipCreateResult::Mutual
+        } else {
+            FriendshipCreateResult::Subscribed
+        };
+        let crossed = Self::crossed_threshold(&links, user_id, 1, celebrity_threshold);
+        Ok(FriendshipChange { result, crossed_threshold: if crossed { vec!(user_id) } else { vec!() } })
+    }
+
+    /// Mirrors `friend_service::delete_friend`, a block drops the links in both directions
+    pub fn unfollow(&self, initiator_user_id: Uuid, user_id: Uuid, block: bool, celebrity_threshold: u64) -> Result<FriendshipChange<FriendshipEndResult>, FriendServiceError> {
+        let mut links = self.links.write().unwrap();
+        if !block {
+            if !links.remove(&(initiator_user_id, user_id)) {
+                return Ok(FriendshipChange::of(FriendshipEndResult::NotInFriendship));
+            }
+            let crossed = Self::crossed_threshold(&links, initiator_user_id, -1, celebrity_threshold);
+            return Ok(FriendshipChange { 
+                result: FriendshipEndResult::Unsubscribed, 
+                crossed_threshold: if crossed { vec!(initiator_user_id) } else { vec!() } 
+            });
+        }
+        let mut change = FriendshipChange::of(FriendshipEndResult::Blocked);
+        for (followed_id, follower_id) in [(initiator_user_id, user_id), (user_id, initiator_user_id)] {
+            if links.remove(&(followed_id, follower_id)) && Self::crossed_threshold(&links, followed_id, -1, celebrity_threshold) {
+                change.crossed_threshold.push(followed_id);
+            }
+        }
+        self.blocks.write().unwrap().insert((initiator_user_id, user_id));
+        Ok(change)
+    }
+
+    /// Same check as `adjust_follow_counters`, on the followers count after the change
+    fn crossed_threshold(links: &HashSet<(Uuid, Uuid)>, followed_id: Uuid, delta: i64, celebrity_threshold: u64) -> bool {
+        let count = links.iter().filter(|(user, _)| *user == followed_id).count() as i64;
+        let threshold = celebrity_threshold as i64;
+        (count >= threshold) != (count - delta >= threshold)
+    }
+
+    /// Whether either of the users blocked the other
+    pub(crate) fn is_blocked_between(&self, user_id: Uuid, other_id: Uuid) -> bool {
+        let blocks = self.blocks.read().unwrap();
+        blocks.contains(&(user_id, other_id)) || blocks.contains(&(other_id, user_id))
+    }
+
+    pub(crate) fn is_linked(&self, user_id: Uuid, friend_id: Uuid) -> bool {
+        self.links.read().unwrap().contains(&(user_id, friend_id))
+    }
+
+    /// Friends of the user, i.e. `friend_id` of the rows where `user_id` matches
+    pub(crate) fn friend_ids(&self, user_id: Uuid) -> Vec<Uuid> {
+        self.links.read().unwrap().iter()
+            .filter(|(user, _)| *user == user_id)
+            .map(|(_, friend)| *friend)
+            .collect()
+    }
+
+    /// Users having the given user as a friend
+    pub(crate) fn linked_to(&self, friend_id: Uuid) -> Vec<Uuid> {
+        self.links.read().unwrap().iter()
+            .filter(|(_, friend)| *friend == friend_id)
+            .map(|(user, _)| *user)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl FriendRepository for InMemoryFriendRepository {
+
+    async fn get_followers_ids(&self, user_id: Uuid) -> Result<Vec<Uuid>, FriendRepositoryError> {
+        Ok(self.friend_ids(user_id))
+    }
+
+    async fn get_mutual_ids(&self, user_id: Uuid) -> Result<Vec<Uuid>, FriendRepositoryError> {
+        Ok(self.friend_ids(user_id).into_iter()
+            .filter(|friend_id| self.is_linked(*friend_id, user_id))
+            .collect())
+    }
+
+    async fn get_followers_count(&self, user_id: Uuid) -> Result<i64, FriendRepositoryError> {
+        Ok(self.friend_ids(user_id).len() as i64)
+    }
+}
@@ -1,3 +1,4 @@
 pub mod controller;
 pub mod repository;
-mod friend_service;
\ No newline at end of file
+pub mod in_memory_repository;
+pub mod friend_service;
\ No newline at end of file
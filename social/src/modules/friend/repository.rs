@@ -18,6 +18,8 @@ pub enum FriendRepositoryError {
 #[async_trait]
 pub trait FriendRepository {
     async fn get_followers_ids(&self, user_id: Uuid) -> Result<Vec<Uuid>, FriendRepositoryError>;   
+    async fn get_mutual_ids(&self, user_id: Uuid) -> Result<Vec<Uuid>, FriendRepositoryError>;
+    async fn get_followers_count(&self, user_id: Uuid) -> Result<i64, FriendRepositoryError>;
 }
 
 pub struct FriendRepositoryImpl {
@@ -41,4 +43,24 @@ impl FriendRepository for FriendRepositoryImpl {
         ).await?;
         Ok(res.iter().map(|row| row.get(0)).collect()) 
     }
-}
\ No newline at end of file
+
+    async fn get_mutual_ids(&self, user_id: Uuid) -> Result<Vec<Uuid>, FriendRepositoryError> {
+        let client = self.pool.get().await?;
+        let res = client.query(
+            "SELECT f.friend_id FROM friends f 
+                JOIN friends b ON b.user_id = f.friend_id AND b.friend_id = f.user_id 
+                WHERE f.user_id = $1", 
+            &[&user_id]
+        ).await?;
+        Ok(res.iter().map(|row| row.get(0)).collect())
+    }
+
+    async fn get_followers_count(&self, user_id: Uuid) -> Result<i64, FriendRepositoryError> {
+        let client = self.pool.get().await?;
+        let res = client.query_opt(
+            "SELECT followers_count FROM users WHERE id = $1", 
+            &[&user_id]
+        ).await?;
+        Ok(res.map(|row| row.get(0)).unwrap_or(0))
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+use deadpool_lapin::{Pool, lapin::{ExchangeKind, options::{BasicConsumeOptions, ExchangeDeclareOptions, QueueBindOptions, QueueDeclareOptions}, types::FieldTable}};
+use tokio_stream::StreamExt;
+use crate::modules::post::{event::DomainEvent, local_post_cache::LocalPostStore};
+
+/// Evicts posts changed on other instances from the in-process store.
+/// Every instance consumes from its own exclusive queue, so each of them sees every event
+pub struct CacheInvalidator {
+    store: Arc<LocalPostStore>,
+    pool: Arc<Pool>,
+    exchange: String,
+}
+
+impl CacheInvalidator {
+    pub fn new(store: Arc<LocalPostStore>, pool: Arc<Pool>, exchange: String) -> Self {
+        CacheInvalidator { store, pool, exchange }
+    }
+
+    pub async fn run_consumer(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let conn = self.pool.get().await?;
+        let channel = conn.create_channel().await?;
+        channel.exchange_declare(
+            &self.exchange,
+            ExchangeKind::Topic,
+            ExchangeDeclareOptions { durable: true, ..ExchangeDeclareOptions::default() },
+            FieldTable::default()
+        ).await?;
+        let queue = channel.queue_declare(
+            "",
+            QueueDeclareOptions { exclusive: true, auto_delete: true, ..QueueDeclareOptions::default() },
+            FieldTable::default()
+        ).await?;
+        let queue_name = queue.name().as_str();
+        for routing_key in ["post.updated", "post.deleted", "comment.created"] {
+            channel.queue_bind(queue_name, &self.exchange, routing_key, QueueBindOptions::default(), FieldTable::default()).await?;
+        }
+        let mut consumer = channel.basic_consume(
+            queue_name,
+            "cache_invalidator",
+            BasicConsumeOptions { no_ack: true, ..BasicConsumeOptions::default() },
+            FieldTable::default()
+        ).await?;
+        tracing::info!("Cache invalidator started...");
+        while let Some(delivery) = consumer.next().await {
+            let delivery = delivery?;
+            match serde_json::from_slice::<DomainEvent>(&delivery.data) {
+                Ok(DomainEvent::PostUpdated { post, .. }) => self.store.remove(&post.id),
+                Ok(DomainEvent::PostDeleted { post_id, .. }) => self.store.remove(&post_id),
+                // Cached bodies carry the comments counter
+                Ok(DomainEvent::CommentCreated { comment, .. }) => self.store.remove(&comment.post_id),
+                Ok(_) => {},
+                Err(e) => tracing::warn!("Failed to parse invalidation event {:?}", e)
+            }
+        }
+        Ok(())
+    }
+}
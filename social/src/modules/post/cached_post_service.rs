@@ -1,77 +1,460 @@
+use std::{collections::{HashMap, HashSet}, sync::Arc, time::Duration};
+use dashmap::DashMap;
+use rand::Rng;
+use tokio::sync::Mutex;
 use uuid::Uuid;
-use crate::modules::post::{model::Post, post_cache::PostCache, service_provider::{PostService, PostServiceError}};
+use crate::modules::post::{cursor::FeedPage, model::{Post, PostRevision, Visibility}, post_cache::{AUTHOR_TIMELINE_SIZE, DEFAULT_FEED_SIZE, FeedLimits, PostCache}, service_provider::{FeedWarmer, PostService, PostServiceError}};
 use crate::modules::common::ext::extensions::ResultExt;
 use async_trait::async_trait; 
 
+// The mark is refreshed this many seconds before it expires, plus a random jitter so feeds do not expire together
+const FEED_REFRESH_AHEAD_SECONDS: i64 = 60;
+const FEED_REFRESH_JITTER_SECONDS: i64 = 120;
+const FEED_LOCK_WAIT_ATTEMPTS: u32 = 20;
+const FEED_LOCK_WAIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Turns a page into a window read from the start of a cached list, with the number of posts
+/// to skip and to take from it once posts the viewer may not see are filtered out
+fn page_window(page: FeedPage) -> (FeedPage, u64, u64) {
+    match page {
+        FeedPage::Offset { limit, offset } => {
+            let limit = limit.unwrap_or(DEFAULT_FEED_SIZE);
+            let offset = offset.unwrap_or(0);
+            (FeedPage::Offset { limit: Some(offset + limit), offset: None }, offset, limit)
+        },
+        FeedPage::After { limit, cursor } => {
+            let limit = limit.unwrap_or(DEFAULT_FEED_SIZE);
+            (FeedPage::After { limit: Some(limit), cursor }, 0, limit)
+        }
+    }
+}
+
+/// Everything is shared, so a clone can refresh a feed in the background
 pub struct CachedPostService <C, S>
 where    
     C: PostCache,
     S: PostService {
-    service: S,
-    post_cache: C,
+    service: Arc<S>,
+    post_cache: Arc<C>,
+    in_flight: Arc<DashMap<Uuid, Arc<Mutex<()>>>>,
+    feed_limits: FeedLimits,
+}
+
+impl <C, S> Clone for CachedPostService<C, S>
+where
+    C: PostCache,
+    S: PostService {
+    fn clone(&self) -> Self {
+        CachedPostService {
+            service: Arc::clone(&self.service),
+            post_cache: Arc::clone(&self.post_cache),
+            in_flight: Arc::clone(&self.in_flight),
+            feed_limits: self.feed_limits
+        }
+    }
 }
 
 impl <C, S> CachedPostService<C, S> 
 where 
-    C: PostCache + Send + Sync,
-    S: PostService + Send + Sync {
-    pub fn new(service: S, post_cache: C) -> Self {
+    C: PostCache + Send + Sync + 'static,
+    S: PostService + Send + Sync + 'static {
+    pub fn new(service: S, post_cache: C, feed_limits: FeedLimits) -> Self {
         CachedPostService { 
-            service,
-            post_cache
+            service: Arc::new(service),
+            post_cache: Arc::new(post_cache),
+            in_flight: Arc::new(DashMap::new()),
+            feed_limits
+        }
+    }
+
+    /// Rebuilds the feed if no other instance holds the feed lock. Returns false if the lock was not taken.
+    /// With `replace` the cached feed is dropped under the lock first, so a lost lock leaves it in place
+    async fn rebuild_feed(&self, user_id: Uuid, replace: bool) -> bool {
+        let token = Uuid::new_v4().to_string();
+        match self.post_cache.lock_feed(user_id, &token).await {
+            Ok(true) => {},
+            Ok(false) => return false,
+            Err(e) => {
+                tracing::warn!("Failed to lock feed {}: {:?}", user_id, e);
+                return false;
+            }
+        }
+        if replace {
+            self.post_cache.delete_user_feed(user_id).await.warn(format!("Failed to drop feed {}", user_id));
+        }
+        let page = FeedPage::Offset { limit: Some(self.feed_limits.size), offset: None };
+        match self.service.feed(user_id, page).await {
+            // An empty feed is not marked, otherwise fan-out would skip it until the mark expires
+            Ok(posts) if !posts.is_empty() => {
+                self.post_cache.save_posts(&posts).await.warn(format!("Failed to save posts {}", user_id));
+                if self.post_cache.save_user_feed(user_id, &posts).await.warn(format!("Failed to save user's {} feed", user_id)).is_some() {
+                    self.post_cache.mark_feed_exists(user_id).await.warn(format!("Failed to mark feed exists. Feed {}", user_id));
+                }
+            },
+            Ok(_) => {},
+            Err(e) => tracing::warn!("Failed to load feed {}: {:?}", user_id, e)
+        }
+        self.post_cache.unlock_feed(user_id, &token).await.warn(format!("Failed to unlock feed {}", user_id));
+        true
+    }
+
+    /// Single-flight feed rebuild: one caller per process, and one process per feed through the Redis lock.
+    /// With `wait` the caller queues behind the rebuild and returns whether the feed is cached now,
+    /// otherwise the refresh is skipped when one is already running and the current feed is served.
+    async fn refresh_feed(&self, user_id: Uuid, wait: bool) -> bool {
+        let flight = self.in_flight.entry(user_id).or_default().clone();
+        let cached = {
+            let guard = if wait { Some(flight.lock().await) } else { flight.try_lock().ok() };
+            match guard {
+                Some(_guard) if wait => self.await_feed(user_id).await,
+                Some(_guard) => {
+                    self.rebuild_feed(user_id, false).await;
+                    true
+                },
+                None => true
+            }
+        };
+        drop(flight);
+        self.in_flight.remove_if(&user_id, |_, flight| Arc::strong_count(flight) == 1);
+        cached
+    }
+
+    async fn await_feed(&self, user_id: Uuid) -> bool {
+        for _ in 0..FEED_LOCK_WAIT_ATTEMPTS {
+            // The previous holder may have rebuilt the feed while this caller was waiting
+            if matches!(self.post_cache.check_feed_exists(user_id).await, Ok(true)) {
+                return true;
+            }
+            if self.rebuild_feed(user_id, false).await {
+                return matches!(self.post_cache.check_feed_exists(user_id).await, Ok(true));
+            }
+            tokio::time::sleep(FEED_LOCK_WAIT_INTERVAL).await;
+        }
+        false
+    }
+
+    /// Loads the newest posts of the author into the timeline cache
+    async fn rebuild_author_timeline(&self, author_id: Uuid) {
+        let page = FeedPage::Offset { limit: Some(AUTHOR_TIMELINE_SIZE), offset: None };
+        let posts = match self.service.author_feed(author_id, author_id, page).await {
+            Ok(posts) => posts,
+            Err(e) => {
+                tracing::warn!("Failed to load author {} timeline: {:?}", author_id, e);
+                return;
+            }
+        };
+        self.post_cache.save_posts(&posts).await.warn(format!("Failed to save posts {}", author_id));
+        if self.post_cache.save_author_timeline(author_id, &posts).await.warn(format!("Failed to save author {} timeline", author_id)).is_some() {
+            self.post_cache.mark_author_timeline_exists(author_id).await.warn(format!("Failed to mark author timeline exists {}", author_id));
+        }
+    }
+
+    /// The timeline keeps only the newest posts, a short page is trusted only if nothing was cut off
+    async fn is_complete_page(&self, author_id: Uuid, page: FeedPage, ids_len: usize) -> bool {
+        match page.limit() {
+            Some(limit) if ids_len as u64 >= limit => true,
+            _ => matches!(self.post_cache.author_timeline_size(author_id).await, Ok(size) if size < AUTHOR_TIMELINE_SIZE)
+        }
+    }
+
+    /// Keeps the posts the viewer may read. Cached bodies carry no audience: private posts of others are dropped
+    /// and friends-only ones are rechecked by the database in one batch, since friendship may have changed after fan-out
+    async fn visible_posts(&self, viewer_id: Uuid, posts: Vec<Post>) -> Result<Vec<Post>, PostServiceError> {
+        let restricted: Vec<Uuid> = posts.iter()
+            .filter(|post| !post.is_public() && post.author_user_id != viewer_id && post.visibility == Visibility::Friends)
+            .map(|post| post.id)
+            .collect();
+        let allowed: HashSet<Uuid> = if restricted.is_empty() {
+            HashSet::new()
+        } else {
+            self.service.get_many(viewer_id, &restricted).await?.into_iter().map(|post| post.id).collect()
+        };
+        Ok(posts.into_iter()
+            .filter(|post| post.is_public() || post.author_user_id == viewer_id || allowed.contains(&post.id))
+            .collect())
+    }
+
+    async fn cached_posts(&self, ids: &Vec<String>) -> HashMap<String, Post> {
+        self.post_cache.get_posts_by_ids(ids.clone()).await.unwrap_or_default().into_iter()
+            .map(|post| (post.id.to_string(), post))
+            .collect()
+    }
+
+    /// Loads the posts of `ids` which are not in `found` as `viewer_id` sees them and caches them
+    async fn load_missing(&self, found: &mut HashMap<String, Post>, viewer_id: Uuid, ids: &Vec<String>) -> Result<(), PostServiceError> {
+        let missing: Vec<Uuid> = ids.iter()
+            .filter(|id| !found.contains_key(*id))
+            .filter_map(|id| Uuid::parse_str(id).ok())
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        let loaded = self.service.get_many(viewer_id, &missing).await?;
+        self.post_cache.save_posts(&loaded).await.warn("Failed to save loaded posts".to_string());
+        found.extend(loaded.into_iter().map(|post| (post.id.to_string(), post)));
+        Ok(())
+    }
+
+    async fn followed_celebrities(&self, user_id: Uuid) -> Option<Vec<Uuid>> {
+        if let Ok(Some(authors)) = self.post_cache.get_followed_celebrities(user_id).await {
+            return Some(authors);
+        }
+        let authors = match self.service.followed_celebrities(user_id).await {
+            Ok(authors) => authors,
+            Err(e) => {
+                tracing::warn!("Failed to load celebrities followed by {}: {:?}", user_id, e);
+                return None;
+            }
+        };
+        self.post_cache.save_followed_celebrities(user_id, &authors).await.warn(format!("Failed to save celebrities followed by {}", user_id));
+        Some(authors)
+    }
+
+    /// The page and the timeline size are read together, a short page is trusted only if nothing was cut off
+    async fn celebrity_page(&self, author_id: Uuid, page: FeedPage) -> Option<Vec<String>> {
+        if !self.post_cache.check_author_timeline_exists(author_id).await.ok()? {
+            self.rebuild_author_timeline(author_id).await;
+        }
+        let (ids, size) = tokio::try_join!(
+            self.post_cache.get_author_timeline(author_id, page),
+            self.post_cache.author_timeline_size(author_id)
+        ).ok()?;
+        let complete = page.limit().is_some_and(|limit| ids.len() as u64 >= limit) || size < AUTHOR_TIMELINE_SIZE;
+        complete.then_some(ids)
+    }
+
+    /// Removes ids of posts that are gone from the database, so the next read of the feed is served from cache
+    async fn drop_stale_ids(&self, user_id: Uuid, pushed: &Vec<String>, timelines: &Vec<(Uuid, Vec<String>)>, found: &HashSet<String>) {
+        let stale: Vec<String> = pushed.iter().filter(|id| !found.contains(*id)).cloned().collect();
+        if !stale.is_empty() {
+            self.post_cache.remove_from_user_feed(user_id, &stale).await.warn(format!("Failed to remove stale posts from feed {}", user_id));
+        }
+        for (author_id, timeline) in timelines {
+            for post_id in timeline.iter().filter(|id| !found.contains(*id)).filter_map(|id| Uuid::parse_str(id).ok()) {
+                self.post_cache.delete_author_post(author_id, &post_id).await.warn(format!("Failed to remove stale post {} from timeline {}", post_id, author_id));
+            }
+        }
+    }
+
+    /// Merges the pushed feed with timelines of followed celebrities, whose public posts are not fanned out.
+    /// Every source is read for the whole requested window; a source that filled it may hold older posts
+    /// beyond it, so merged posts past the newest such cut are dropped. `None` sends the read to the database.
+    async fn merged_feed(&self, user_id: Uuid, page: FeedPage) -> Option<Vec<Post>> {
+        let celebrities = self.followed_celebrities(user_id).await?;
+        let (window, skip, limit) = page_window(page);
+        let window_size = skip + limit;
+        let pushed = self.post_cache.get_user_feed(user_id, window).await.ok()?;
+        let mut cut_ids: Vec<String> = vec!();
+        if pushed.len() as u64 >= window_size {
+            cut_ids.extend(pushed.last().cloned());
+        } else if self.post_cache.is_user_feed_full(user_id).await.unwrap_or(true) {
+            // Older posts were trimmed off the feed, the rest of it is only in the database
+            return None;
+        }
+        let mut ids: Vec<String> = pushed.clone();
+        let mut timelines: Vec<(Uuid, Vec<String>)> = vec!();
+        // Timelines are read concurrently, the driver pipelines the commands of all celebrities
+        let pages = futures::future::join_all(celebrities.iter().map(|author_id| self.celebrity_page(*author_id, window))).await;
+        for (author_id, timeline) in celebrities.into_iter().zip(pages) {
+            let timeline = timeline?;
+            if timeline.len() as u64 >= window_size {
+                cut_ids.extend(timeline.last().cloned());
+            }
+            ids.extend(timeline.iter().cloned());
+            timelines.push((author_id, timeline));
+        }
+        ids.sort();
+        ids.dedup();
+        // Missing bodies are loaded as their source sees them: pushed posts by the reader, timeline posts by
+        // their author, so a post left out is gone for the source rather than hidden from this reader
+        let mut found = self.cached_posts(&ids).await;
+        self.load_missing(&mut found, user_id, &pushed).await.ok()?;
+        for (author_id, timeline) in &timelines {
+            self.load_missing(&mut found, *author_id, timeline).await.ok()?;
+        }
+        let posts: Vec<Post> = ids.iter().filter_map(|id| found.remove(id)).collect();
+        if posts.len() != ids.len() {
+            let found: HashSet<String> = posts.iter().map(|post| post.id.to_string()).collect();
+            self.drop_stale_ids(user_id, &pushed, &timelines, &found).await;
+            return None;
+        }
+        let cut = posts.iter()
+            .filter(|post| cut_ids.contains(&post.id.to_string()))
+            .map(|post| (post.created_at, post.id))
+            .max();
+        let pushed: HashSet<String> = pushed.into_iter().collect();
+        // Restricted posts of celebrities reach their audience through fan-out, so only pushed ones are kept
+        let posts: Vec<Post> = posts.into_iter()
+            .filter(|post| cut.is_none_or(|cut| (post.created_at, post.id) >= cut))
+            .filter(|post| post.is_public() || pushed.contains(&post.id.to_string()))
+            .collect();
+        let candidates: Vec<String> = posts.iter().map(|post| post.id.to_string()).collect();
+        let mut posts = self.visible_posts(user_id, posts).await.warn(format!("Failed to check feed posts of {}", user_id))?;
+        // Pushed posts the user may no longer see are dropped from the feed, so they are not checked again
+        let visible: HashSet<String> = posts.iter().map(|post| post.id.to_string()).collect();
+        let revoked: Vec<String> = candidates.into_iter().filter(|id| !visible.contains(id)).collect();
+        if !revoked.is_empty() {
+            self.post_cache.remove_from_user_feed(user_id, &revoked).await.warn(format!("Failed to remove revoked posts from feed {}", user_id));
+        }
+        posts.sort_by(|a, b| (b.created_at, b.id).cmp(&(a.created_at, a.id)));
+        let posts: Vec<Post> = posts.into_iter().skip(skip as usize).take(limit as usize).collect();
+        if cut.is_some() && (posts.len() as u64) < limit {
+            return None;
         }
+        Some(posts)
     }
 }
 
 #[async_trait]
 impl <C, S> PostService for CachedPostService <C, S>
 where
-    C: PostCache + Send + Sync,
-    S: PostService + Send + Sync {    
-    async fn create(&self, user_id: Uuid, text: &String) -> Result<Post, PostServiceError> {                
-        let post = self.service.create(user_id, text).await?;
+    C: PostCache + Send + Sync + 'static,
+    S: PostService + Send + Sync + 'static {    
+    async fn create(&self, user_id: Uuid, text: &String, visibility: Visibility, post_id: Option<Uuid>) -> Result<Post, PostServiceError> {                
+        let post = self.service.create(user_id, text, visibility, post_id).await?;
         self.post_cache.save_post(&post).await.warn("Saving post to cache failed".to_string());                                                        
         Ok(post)
     }
 
-    async fn update(&self, user_id: Uuid, post_id: Uuid, text: &String) -> Result<Post, PostServiceError> {        
-        let post = self.service.update(user_id, post_id, text).await?;
+    async fn repost(&self, user_id: Uuid, post_id: Uuid, text: &Option<String>) -> Result<Post, PostServiceError> {
+        let post = self.service.repost(user_id, post_id, text).await?;
+        self.post_cache.save_post(&post).await.warn("Saving post to cache failed".to_string());
+        Ok(post)
+    }
+
+    async fn update(&self, user_id: Uuid, post_id: Uuid, text: &String, visibility: Option<Visibility>) -> Result<Post, PostServiceError> {        
+        let post = self.service.update(user_id, post_id, text, visibility).await?;
         self.post_cache.save_post(&post).await.warn("Saving post to cache failed".to_string());                
         Ok(post)        
     }
 
     async fn delete(&self, user_id: Uuid, post_id: Uuid) -> Result<(), PostServiceError> {
+        let reposts = self.service.reposts(post_id).await?;
         let _ = self.service.delete(user_id, post_id).await?;
         self.post_cache.delete_post(&post_id).await.warn("Deleting post from cache failed".to_string());
+        for repost in reposts {
+            self.post_cache.delete_post(&repost.id).await.warn("Deleting repost from cache failed".to_string());
+        }
         Ok(())        
     }
 
-    async fn get(&self, post_id: Uuid) -> Result<Post, PostServiceError> {           
-        if let Ok(Some(post)) = self.post_cache.get_post(&post_id).await {
+    async fn restore(&self, user_id: Uuid, post_id: Uuid) -> Result<Post, PostServiceError> {
+        let post = self.service.restore(user_id, post_id).await?;
+        self.post_cache.save_post(&post).await.warn("Saving post to cache failed".to_string());
+        Ok(post)
+    }
+
+    async fn hide(&self, post_id: Uuid) -> Result<Post, PostServiceError> {
+        let reposts = self.service.reposts(post_id).await?;
+        let post = self.service.hide(post_id).await?;
+        self.post_cache.delete_post(&post_id).await.warn("Deleting post from cache failed".to_string());
+        for repost in reposts {
+            self.post_cache.delete_post(&repost.id).await.warn("Deleting repost from cache failed".to_string());
+        }
+        Ok(post)
+    }
+
+    async fn unhide(&self, post_id: Uuid) -> Result<Post, PostServiceError> {
+        let post = self.service.unhide(post_id).await?;
+        self.post_cache.save_post(&post).await.warn("Saving post to cache failed".to_string());
+        Ok(post)
+    }
+
+    async fn get(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Post, PostServiceError> {           
+        // Cached bodies carry no audience, so only public or own posts are served from cache
+        if let Ok(Some(post)) = self.post_cache.get_post(&post_id).await 
+            && (post.is_public() || post.author_user_id == viewer_id) {
             return Ok(post);
         }
-        let post = self.service.get(post_id).await?;
+        let post = self.service.get(viewer_id, post_id).await?;
         self.post_cache.save_post(&post).await.warn("Save to Redis (HSET) failed".to_string());        
         Ok(post)        
     }   
 
-    async fn feed(&self, user_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<Post>, PostServiceError> {        
-        if let Ok(exists) = self.post_cache.check_feed_exists(user_id).await && exists {
-            tracing::info!("Cache hit: {}", user_id);
-            if let Ok(ids) = self.post_cache.get_user_feed(user_id, limit, offset).await {
-                tracing::info!("Ids from cache: {:?}", ids);
+    async fn get_many(&self, viewer_id: Uuid, post_ids: &Vec<Uuid>) -> Result<Vec<Post>, PostServiceError> {
+        let ids: Vec<String> = post_ids.iter().map(Uuid::to_string).collect();
+        let mut found = self.cached_posts(&ids).await;
+        self.load_missing(&mut found, viewer_id, &ids).await?;
+        let posts: Vec<Post> = ids.iter().filter_map(|id| found.remove(id)).collect();
+        self.visible_posts(viewer_id, posts).await
+    }
+
+    async fn reposts(&self, post_id: Uuid) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.service.reposts(post_id).await?)
+    }
+
+    async fn revisions(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Vec<PostRevision>, PostServiceError> {
+        Ok(self.service.revisions(viewer_id, post_id).await?)
+    }
+
+    async fn feed(&self, user_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostServiceError> {        
+        let cached = match self.post_cache.feed_mark_ttl(user_id).await {
+            Ok(Some(ttl)) => {
+                tracing::info!("Cache hit: {}", user_id);
+                let jitter = rand::rng().random_range(0..=FEED_REFRESH_JITTER_SECONDS);
+                // The cached feed is still served, so the reader doesn't wait for the refresh
+                if ttl <= FEED_REFRESH_AHEAD_SECONDS + jitter {
+                    let service = self.clone();
+                    tokio::spawn(async move {
+                        service.refresh_feed(user_id, false).await;
+                    });
+                }
+                true
+            },
+            Ok(None) => self.refresh_feed(user_id, true).await,
+            Err(e) => {
+                tracing::warn!("Failed to check feed mark {}: {:?}", user_id, e);
+                false
+            }
+        };
+        if cached && let Some(posts) = self.merged_feed(user_id, page).await {
+            return Ok(posts);
+        }
+        Ok(self.service.feed(user_id, page).await?)
+    }
+
+    async fn tag_feed(&self, viewer_id: Uuid, tag: &String, page: FeedPage) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.service.tag_feed(viewer_id, tag, page).await?)
+    }
+
+    async fn author_feed(&self, viewer_id: Uuid, author_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostServiceError> {
+        if let Ok(exists) = self.post_cache.check_author_timeline_exists(author_id).await && exists {
+            // Cached timeline holds posts of every visibility, other viewers read a window from its start
+            // so skipping counts only the posts they may see, as the database does
+            let (window, skip, limit) = if viewer_id == author_id { (page, 0, u64::MAX) } else { page_window(page) };
+            if let Ok(ids) = self.post_cache.get_author_timeline(author_id, window).await {
                 let ids_len = ids.len();
-                if let Ok(posts) = self.post_cache.get_posts_by_ids(ids).await && posts.len() == ids_len {
-                    return Ok(posts);
+                if self.is_complete_page(author_id, window, ids_len).await 
+                    && let Ok(posts) = self.post_cache.get_posts_by_ids(ids).await 
+                    && posts.len() == ids_len 
+                    && let Some(posts) = self.visible_posts(viewer_id, posts).await.warn(format!("Failed to check timeline posts of {} for {}", author_id, viewer_id)) {
+                    let posts: Vec<Post> = posts.into_iter().skip(skip as usize).take(limit as usize).collect();
+                    // A full window may have cut off visible posts which would complete the page
+                    let window_full = window.limit().is_some_and(|size| ids_len as u64 >= size);
+                    if viewer_id == author_id || (posts.len() as u64) >= limit || !window_full {
+                        return Ok(posts);
+                    }
                 }
             }
-        }       
-        let posts = self.service.feed(user_id, limit, offset).await?;
-        if !posts.is_empty() {
-            self.post_cache.save_posts(&posts).await.warn(format!("Failed to save posts {}", user_id));
-            self.post_cache.save_user_feed(user_id, &posts).await.warn(format!("Failed to save user's {} feed", user_id));
-            self.post_cache.mark_feed_exists(user_id).await.warn(format!("Failed to mark feed exists. Feed {}", user_id));
-        }        
-        Ok(posts)      
+        } else {
+            self.rebuild_author_timeline(author_id).await;
+        }
+        Ok(self.service.author_feed(viewer_id, author_id, page).await?)
+    }
+    async fn followed_celebrities(&self, user_id: Uuid) -> Result<Vec<Uuid>, PostServiceError> {
+        Ok(self.service.followed_celebrities(user_id).await?)
+    }
+}
+
+#[async_trait]
+impl <C, S> FeedWarmer for CachedPostService <C, S>
+where
+    C: PostCache + Send + Sync + 'static,
+    S: PostService + Send + Sync + 'static {
+    async fn warm_feed(&self, user_id: Uuid, force: bool) -> Result<bool, PostServiceError> {
+        if !force && self.post_cache.check_feed_exists(user_id).await.map_err(|e| PostServiceError::Inner(Box::new(e)))? {
+            return Ok(false);
+        }
+        Ok(self.rebuild_feed(user_id, force).await)
     }
 }
\ No newline at end of file
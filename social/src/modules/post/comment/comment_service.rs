@@ -0,0 +1,54 @@
+use uuid::Uuid;
+use async_trait::async_trait; 
+use crate::modules::common::ext::extensions::ResultExt;
+use crate::modules::post::post_cache::UserPostCache;
+use crate::modules::post::comment::{model::Comment, repository::CommentRepository, service_provider::{CommentService, CommentServiceError}};
+
+pub struct CommentServiceImpl<R, C>
+where 
+    R: CommentRepository,
+    C: UserPostCache {
+    repository: R,
+    post_cache: C,
+}
+
+impl <R, C> CommentServiceImpl<R, C>
+where 
+    R: CommentRepository + Send + Sync,
+    C: UserPostCache + Send + Sync {
+    pub fn new(repository: R, post_cache: C) -> Self {
+        CommentServiceImpl { 
+            repository, 
+            post_cache
+        }
+    }
+}
+
+#[async_trait]
+impl <R, C> CommentService for CommentServiceImpl<R, C>
+where 
+    R: CommentRepository + Send + Sync,
+    C: UserPostCache + Send + Sync {
+
+    async fn create(&self, user_id: Uuid, post_id: Uuid, parent_id: Option<Uuid>, text: &String) -> Result<Comment, CommentServiceError> {
+        // The comment event is written to the outbox together with the comment
+        let comment = self.repository.create(user_id, post_id, parent_id, text).await?;
+        // Cached post carries comments count, so it is dropped to be reloaded
+        self.post_cache.delete_post(&post_id).await.warn("Deleting post from cache failed".to_string());
+        Ok(comment)
+    }
+
+    async fn update(&self, user_id: Uuid, comment_id: Uuid, text: &String) -> Result<Comment, CommentServiceError> {
+        Ok(self.repository.update(user_id, comment_id, text).await?)
+    }
+
+    async fn delete(&self, user_id: Uuid, comment_id: Uuid) -> Result<(), CommentServiceError> {
+        let post_id = self.repository.delete(user_id, comment_id).await?;
+        self.post_cache.delete_post(&post_id).await.warn("Deleting post from cache failed".to_string());
+        Ok(())
+    }
+
+    async fn list(&self, viewer_id: Uuid, post_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<Comment>, CommentServiceError> {
+        Ok(self.repository.list(viewer_id, post_id, limit, offset).await?)
+    }
+}
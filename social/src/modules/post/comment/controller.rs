@@ -0,0 +1,164 @@
+use openapi::apis::comment::{Comment, PostIdCommentPostResponse, PostIdCommentsGetResponse, PostCommentCommentIdPutResponse, PostCommentCommentIdDeleteResponse};
+use axum_extra::headers::Host;
+use axum_extra::extract::CookieJar;
+use axum::http::Method;
+use async_trait::async_trait; 
+use openapi::models::{self};
+use crate::modules::post::comment::{model, repository::CommentRepositoryError, service_provider::CommentServiceError};
+use crate::modules::auth::auth;
+use crate::Application;
+use uuid::Uuid;
+
+#[async_trait]
+impl Comment for Application {
+    type Claims = auth::Claims;
+
+    async fn post_id_comment_post(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostIdCommentPostPathParams,
+        body: &Option<models::PostIdCommentPostRequest>,
+    ) -> Result<PostIdCommentPostResponse, ()> {
+        let post_id = match Uuid::parse_str(&path_params.id) {
+            Ok(id) => id,
+            Err(_) => return Ok(PostIdCommentPostResponse::Status400)
+        };
+        let body = match body {
+            Some(body) => body,
+            None => return Ok(PostIdCommentPostResponse::Status400)
+        };
+        let parent_id = match body.parent_id.as_ref().map(|id| Uuid::parse_str(id)).transpose() {
+            Ok(id) => id,
+            Err(_) => return Ok(PostIdCommentPostResponse::Status400)
+        };
+        match self.state.comment_service.create(claims.user_id, post_id, parent_id, &body.text).await {
+            Ok(comment) => Ok(PostIdCommentPostResponse::Status200(comment.id.to_string())),
+            Err(CommentServiceError::Database(CommentRepositoryError::NotFound(_) | CommentRepositoryError::IllegalState(_))) => 
+                Ok(PostIdCommentPostResponse::Status400),
+            Err(e) => {
+                tracing::error!("Create comment error: {:?}", e);
+                Ok(PostIdCommentPostResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn post_comment_comment_id_put(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostCommentCommentIdPutPathParams,
+        body: &Option<models::PostCommentCommentIdPutRequest>,
+    ) -> Result<PostCommentCommentIdPutResponse, ()> {
+        let comment_id = match Uuid::parse_str(&path_params.comment_id) {
+            Ok(id) => id,
+            Err(_) => return Ok(PostCommentCommentIdPutResponse::Status400)
+        };
+        let body = match body {
+            Some(body) => body,
+            None => return Ok(PostCommentCommentIdPutResponse::Status400)
+        };
+        match self.state.comment_service.update(claims.user_id, comment_id, &body.text).await {
+            Ok(_) => Ok(PostCommentCommentIdPutResponse::Status200),
+            Err(CommentServiceError::Database(CommentRepositoryError::NotFound(_))) => Ok(PostCommentCommentIdPutResponse::Status400),
+            Err(e) => {
+                tracing::error!("Update comment error: {:?}", e);
+                Ok(PostCommentCommentIdPutResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn post_comment_comment_id_delete(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostCommentCommentIdDeletePathParams,
+    ) -> Result<PostCommentCommentIdDeleteResponse, ()> {
+        let comment_id = match Uuid::parse_str(&path_params.comment_id) {
+            Ok(id) => id,
+            Err(_) => return Ok(PostCommentCommentIdDeleteResponse::Status400)
+        };
+        match self.state.comment_service.delete(claims.user_id, comment_id).await {
+            Ok(()) => Ok(PostCommentCommentIdDeleteResponse::Status200),
+            Err(CommentServiceError::Database(CommentRepositoryError::NotFound(_))) => Ok(PostCommentCommentIdDeleteResponse::Status400),
+            Err(CommentServiceError::Database(CommentRepositoryError::Forbidden(_))) => Ok(PostCommentCommentIdDeleteResponse::Status403),
+            Err(e) => {
+                tracing::error!("Delete comment error: {:?}", e);
+                Ok(PostCommentCommentIdDeleteResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn post_id_comments_get(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostIdCommentsGetPathParams,
+        query_params: &models::PostIdCommentsGetQueryParams,
+    ) -> Result<PostIdCommentsGetResponse, ()> {
+        let post_id = match Uuid::parse_str(&path_params.id) {
+            Ok(id) => id,
+            Err(_) => return Ok(PostIdCommentsGetResponse::Status400)
+        };
+        match self.state.comment_service.list(claims.user_id, post_id, query_params.limit, query_params.offset).await {
+            Ok(comments) => Ok(PostIdCommentsGetResponse::Status200(to_comment_dtos(comments))),
+            Err(CommentServiceError::Database(CommentRepositoryError::NotFound(_))) => Ok(PostIdCommentsGetResponse::Status400),
+            Err(e) => {
+                tracing::error!("List comments error: {:?}", e);
+                Ok(PostIdCommentsGetResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+}
+
+fn to_comment_dto(comment: model::Comment) -> models::Comment {
+    models::Comment {
+        id: comment.id.to_string(),
+        post_id: comment.post_id.to_string(),
+        parent_id: comment.parent_id.map(|id| id.to_string()),
+        author_user_id: comment.author_user_id.to_string(),
+        text: comment.text,
+        created_at: comment.timestamp,
+        replies: Some(to_comment_dtos(comment.replies))
+    }
+}
+
+fn to_comment_dtos(comments: Vec<model::Comment>) -> Vec<models::Comment> {
+    comments.into_iter().map(to_comment_dto).collect()
+}
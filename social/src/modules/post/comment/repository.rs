@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use uuid::Uuid;
+use deadpool_postgres::Pool;
+use tokio_postgres::Row;
+use thiserror::Error;
+use std::sync::Arc;
+use async_trait::async_trait; 
+use mockall::automock;
+use crate::modules::post::{comment::model::Comment, event::DomainEvent, outbox::repository::{OutboxRepositoryError, enqueue}, repository::{NOT_REMOVED, visible_to}};
+
+#[derive(Error, Debug)]
+pub enum CommentRepositoryError {
+    #[error("Database error: {0}")]
+    Database(#[from] tokio_postgres::Error),
+    
+    #[error("Pool error: {0}")]
+    Pool(#[from] deadpool_postgres::PoolError),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Illegal state: {0}")]
+    IllegalState(String),
+
+    #[error("Outbox error: {0}")]
+    Outbox(#[from] OutboxRepositoryError),
+}
+
+#[automock]
+#[async_trait]
+pub trait CommentRepository {
+    async fn create(&self, user_id: Uuid, post_id: Uuid, parent_id: Option<Uuid>, text: &String) -> Result<Comment, CommentRepositoryError>;
+    async fn update(&self, user_id: Uuid, comment_id: Uuid, text: &String) -> Result<Comment, CommentRepositoryError>;
+    async fn delete(&self, user_id: Uuid, comment_id: Uuid) -> Result<Uuid, CommentRepositoryError>;
+    async fn list(&self, viewer_id: Uuid, post_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<Comment>, CommentRepositoryError>;
+}
+
+pub struct CommentRepositoryImpl {
+    pool: Arc<Pool>
+}
+
+impl CommentRepositoryImpl {
+    pub fn new(pool: Arc<Pool>) -> Self {
+        CommentRepositoryImpl { pool }
+    }
+}
+
+fn to_comment(row: &Row) -> Comment {
+    Comment {
+        id: row.get("id"),
+        post_id: row.get("post_id"),
+        parent_id: row.get("parent_id"),
+        author_user_id: row.get("user_id"),
+        text: row.get("text"),
+        timestamp: row.get("created_at"),
+        replies: vec!()
+    }
+}
+
+#[async_trait]
+impl CommentRepository for CommentRepositoryImpl {
+
+    async fn create(&self, user_id: Uuid, post_id: Uuid, parent_id: Option<Uuid>, text: &String) -> Result<Comment, CommentRepositoryError> {
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let query = format!(
+            "SELECT p.user_id FROM posts p WHERE p.id=$1 AND p.deleted_at IS NULL AND p.hidden_at IS NULL AND {}", 
+            visible_to("$2")
+        );
+        let post_author_id: Uuid = tx.query_opt(query.as_str(), &[&post_id, &user_id]).await?
+            .ok_or(CommentRepositoryError::NotFound(format!("Post {}", post_id)))?
+            .get("user_id");
+        if let Some(parent_id) = parent_id {
+            let parent = tx.query_opt(
+                "SELECT post_id, parent_id FROM comments WHERE id=$1", 
+                &[&parent_id]
+            ).await?
+                .ok_or(CommentRepositoryError::NotFound(format!("Comment {}", parent_id)))?;
+            let parent_post_id: Uuid = parent.get("post_id");
+            let parent_parent_id: Option<Uuid> = parent.get("parent_id");
+            if parent_post_id != post_id || parent_parent_id.is_some() {
+                return Err(CommentRepositoryError::IllegalState("Only top level comments of the same post can be replied".to_string()));
+            }
+        }
+        let res = tx.query_one(
+            "INSERT INTO comments (post_id, user_id, parent_id, text) VALUES ($1, $2, $3, $4) 
+                RETURNING id, post_id, user_id, parent_id, text, created_at", 
+            &[&post_id, &user_id, &parent_id, text]
+        ).await?;
+        tx.execute(
+            "UPDATE posts SET comments_count = comments_count + 1 WHERE id=$1", 
+            &[&post_id]
+        ).await?;
+        let comment = to_comment(&res);
+        enqueue(&tx, &DomainEvent::CommentCreated { user_id, post_author_id, comment: comment.clone() }).await?;
+        tx.commit().await?;
+        Ok(comment)
+    }
+
+    async fn update(&self, user_id: Uuid, comment_id: Uuid, text: &String) -> Result<Comment, CommentRepositoryError> {
+        let res = self.pool.get().await?.query_opt(
+            "UPDATE comments SET text=$1, updated_at=NOW() WHERE id=$2 AND user_id=$3 
+                RETURNING id, post_id, user_id, parent_id, text, created_at", 
+            &[text, &comment_id, &user_id]
+        ).await?
+            .ok_or(CommentRepositoryError::NotFound(format!("Comment {}", comment_id)))?;
+        Ok(to_comment(&res))
+    }
+
+    async fn delete(&self, user_id: Uuid, comment_id: Uuid) -> Result<Uuid, CommentRepositoryError> {
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let res = tx.query_opt(
+            "SELECT c.post_id, c.user_id, p.user_id AS post_author_id 
+                FROM comments c JOIN posts p ON p.id = c.post_id 
+                WHERE c.id=$1 FOR UPDATE OF c", 
+            &[&comment_id]
+        ).await?
+            .ok_or(CommentRepositoryError::NotFound(format!("Comment {}", comment_id)))?;
+        let post_id: Uuid = res.get("post_id");
+        let author_user_id: Uuid = res.get("user_id");
+        let post_author_id: Uuid = res.get("post_author_id");
+        if user_id != author_user_id && user_id != post_author_id {
+            return Err(CommentRepositoryError::Forbidden("Only comment author or post owner can delete comment".to_string()));
+        }
+        // Replies are removed by the cascade, so they are counted beforehand
+        let replies: i64 = tx.query_one(
+            "SELECT count(*) AS replies FROM comments WHERE parent_id=$1", 
+            &[&comment_id]
+        ).await?.get("replies");
+        tx.execute("DELETE FROM comments WHERE id=$1", &[&comment_id]).await?;
+        tx.execute(
+            "UPDATE posts SET comments_count = comments_count - $2 WHERE id=$1", 
+            &[&post_id, &(replies + 1)]
+        ).await?;
+        tx.commit().await?;
+        Ok(post_id)
+    }
+
+    async fn list(&self, viewer_id: Uuid, post_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<Comment>, CommentRepositoryError> {
+        let client = self.pool.get().await?;
+        // Comments of deleted or moderated posts are not listed, they come back with an unhidden post
+        let query = format!("SELECT 1 FROM posts p WHERE p.id=$1 AND {NOT_REMOVED} AND {}", visible_to("$2"));
+        if client.query_opt(query.as_str(), &[&post_id, &viewer_id]).await?.is_none() {
+            return Err(CommentRepositoryError::NotFound(format!("Post {}", post_id)));
+        }
+        let res = client.query(
+            "SELECT id, post_id, user_id, parent_id, text, created_at FROM comments 
+                WHERE post_id=$1 AND parent_id IS NULL ORDER BY created_at LIMIT $2 OFFSET $3", 
+            &[&post_id, &limit.map(|v| v as i64), &offset.map(|v| v as i64)]
+        ).await?;
+        let mut comments: Vec<Comment> = res.iter().map(to_comment).collect();
+        if comments.is_empty() {
+            return Ok(comments);
+        }
+        let ids: Vec<Uuid> = comments.iter().map(|c| c.id).collect();
+        let res = client.query(
+            "SELECT id, post_id, user_id, parent_id, text, created_at FROM comments 
+                WHERE parent_id = ANY($1) ORDER BY created_at", 
+            &[&ids]
+        ).await?;
+        let mut replies: HashMap<Uuid, Vec<Comment>> = HashMap::new();
+        for reply in res.iter().map(to_comment) {
+            if let Some(parent_id) = reply.parent_id {
+                replies.entry(parent_id).or_default().push(reply);
+            }
+        }
+        for comment in comments.iter_mut() {
+            comment.replies = replies.remove(&comment.id).unwrap_or_default();
+        }
+        Ok(comments)
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+use fred::prelude;
+use deadpool_postgres;
+use thiserror::Error;
+use uuid::Uuid;
+use async_trait::async_trait;
+use crate::modules::post::{local_post_cache::{LocalPostCache, LocalPostStore}, post_cache::{FeedLimits, PostCacheImpl}};
+use crate::modules::post::comment::{comment_service::CommentServiceImpl, model::Comment, repository::{CommentRepositoryError, CommentRepositoryImpl}};
+
+#[derive(Error, Debug)]
+pub enum CommentServiceError {
+    #[error("Database error: {0}")]
+    Database(#[from] CommentRepositoryError),
+
+    #[error("Error: {0}")]
+    Inner(#[from] Box<dyn std::error::Error>),
+}
+
+#[async_trait]
+pub trait CommentService {
+    async fn create(&self, user_id: Uuid, post_id: Uuid, parent_id: Option<Uuid>, text: &String) -> Result<Comment, CommentServiceError>;
+    async fn update(&self, user_id: Uuid, comment_id: Uuid, text: &String) -> Result<Comment, CommentServiceError>;
+    async fn delete(&self, user_id: Uuid, comment_id: Uuid) -> Result<(), CommentServiceError>;
+    async fn list(&self, viewer_id: Uuid, post_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<Comment>, CommentServiceError>;
+}
+
+pub fn create_service(pool: Arc<deadpool_postgres::Pool>, redis: Arc<prelude::Pool>, feed_limits: FeedLimits, local_posts: Arc<LocalPostStore>) -> Arc<dyn CommentService + Send + Sync> {
+    Arc::new(
+        CommentServiceImpl::new(
+            CommentRepositoryImpl::new(pool),
+            LocalPostCache::new(PostCacheImpl::new(redis, feed_limits, false), local_posts)
+        )
+    )
+}
+
@@ -1,10 +1,11 @@
-use openapi::apis::post::{Post, PostPostResponse, PostIdGetResponse, PostPutResponse, PostIdDeleteResponse, PostFeedGetResponse};
+use openapi::apis::post::{Post, PostPostResponse, PostIdRepostPostResponse, PostIdGetResponse, PostPutResponse, PostIdDeleteResponse, PostIdRestorePostResponse, PostIdRevisionsGetResponse, PostFeedGetResponse, PostTagTagGetResponse, PostUserUserIdGetResponse};
 use axum_extra::headers::Host;
 use axum_extra::extract::CookieJar;
 use axum::http::Method;
 use async_trait::async_trait; 
 use openapi::models::{self};
-use crate::modules::post::{model};
+use crate::modules::post::{cursor::{FeedCursor, FeedPage}, links::normalize_tag, model, post_cache::DEFAULT_FEED_SIZE, repository::PostRepositoryError, service_provider::PostServiceError, reaction::{controller::{to_my_reaction_dto, to_reaction_count_dtos}, model::ReactionSummary}};
+use crate::modules::common::ext::extensions::ResultExt;
 use crate::modules::auth::auth;
 use crate::Application;
 use uuid::Uuid;
@@ -23,10 +24,11 @@ impl Post for Application {
     ) -> Result<PostPostResponse, ()> {
         match body {
             Some(post) => {                                                
-                match self.state.post_service.create(claims.user_id, &post.text).await {
+                match self.state.post_service.create(claims.user_id, &post.text, post.visibility.as_ref().map(from_visibility_dto).unwrap_or_default(), None).await {
                     Ok(post) => {                        
                         Ok(PostPostResponse::Status200(post.id.to_string()))
                     },
+                    Err(PostServiceError::Rejected(_)) => Ok(PostPostResponse::Status400),
                     Err(e) => {
                         tracing::error!("Create post error: {:?}", e);
                         Ok(PostPostResponse::Status500 {
@@ -44,19 +46,53 @@ impl Post for Application {
         }
     }
 
+    async fn post_id_repost_post(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostIdRepostPostPathParams,
+        body: &Option<models::PostIdRepostPostRequest>,
+    ) -> Result<PostIdRepostPostResponse, ()> {
+        let post_id = match Uuid::parse_str(&path_params.id) {
+            Ok(id) => id,
+            Err(_) => return Ok(PostIdRepostPostResponse::Status400)
+        };
+        let text = body.as_ref().and_then(|body| body.text.clone());
+        match self.state.post_service.repost(claims.user_id, post_id, &text).await {
+            Ok(post) => Ok(PostIdRepostPostResponse::Status200(post.id.to_string())),
+            Err(PostServiceError::Database(PostRepositoryError::NotFound(_) | PostRepositoryError::IllegalState(_)) | PostServiceError::Rejected(_)) =>
+                Ok(PostIdRepostPostResponse::Status400),
+            Err(e) => {
+                tracing::error!("Repost error: {:?}", e);
+                Ok(PostIdRepostPostResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
     async fn post_id_get(
         &self,    
         _: &Method,
         _: &Host,
         _: &CookieJar,
+        claims: &Self::Claims,
         path_params: &models::PostIdGetPathParams,
     ) -> Result<PostIdGetResponse, ()> {
         let post_id = match Uuid::parse_str(&path_params.id) {
             Ok(id) => id,
             Err(_) => return Ok(PostIdGetResponse::Status400)
         };    
-        match self.state.post_service.get(post_id).await {
-            Ok(post) => Ok(PostIdGetResponse::Status200(to_post_dto(post))),
+        match self.state.post_service.get(claims.user_id, post_id).await {
+            Ok(post) => Ok(PostIdGetResponse::Status200(self.to_post_dtos(claims.user_id, vec!(post)).await.remove(0))),
+            Err(PostServiceError::Database(PostRepositoryError::NotFound(_))) => Ok(PostIdGetResponse::Status404),
             Err(e) => {
                 tracing::error!("Get post error: {:?}", e);
                 Ok(PostIdGetResponse::Status500 {
@@ -85,8 +121,9 @@ impl Post for Application {
                     Ok(id) => id,
                     Err(_) => return Ok(PostPutResponse::Status400)
                 };                                
-                match self.state.post_service.update(claims.user_id, post_id, &post.text).await {
+                match self.state.post_service.update(claims.user_id, post_id, &post.text, post.visibility.as_ref().map(from_visibility_dto)).await {
                     Ok(_) => Ok(PostPutResponse::Status200),
+                    Err(PostServiceError::Database(PostRepositoryError::NotFound(_)) | PostServiceError::Rejected(_)) => Ok(PostPutResponse::Status400),
                     Err(e) => {
                         tracing::error!("Update post error: {:?}", e);
                         Ok(PostPutResponse::Status500 {
@@ -132,6 +169,64 @@ impl Post for Application {
         }  
     }
 
+    async fn post_id_restore_post(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostIdRestorePostPathParams
+    ) -> Result<PostIdRestorePostResponse, ()> {
+        let post_id = match Uuid::parse_str(&path_params.id) {
+            Ok(id) => id,
+            Err(_) => return Ok(PostIdRestorePostResponse::Status400)
+        };
+        match self.state.post_service.restore(claims.user_id, post_id).await {
+            Ok(_) => Ok(PostIdRestorePostResponse::Status200),
+            Err(PostServiceError::Database(PostRepositoryError::NotFound(_))) => Ok(PostIdRestorePostResponse::Status400),
+            Err(e) => {
+                tracing::error!("Restore post error: {:?}", e);
+                Ok(PostIdRestorePostResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn post_id_revisions_get(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostIdRevisionsGetPathParams
+    ) -> Result<PostIdRevisionsGetResponse, ()> {
+        let post_id = match Uuid::parse_str(&path_params.id) {
+            Ok(id) => id,
+            Err(_) => return Ok(PostIdRevisionsGetResponse::Status400)
+        };
+        match self.state.post_service.revisions(claims.user_id, post_id).await {
+            Ok(revisions) => Ok(PostIdRevisionsGetResponse::Status200(revisions.into_iter().map(to_revision_dto).collect())),
+            Err(PostServiceError::Database(PostRepositoryError::NotFound(_))) => Ok(PostIdRevisionsGetResponse::Status400),
+            Err(e) => {
+                tracing::error!("Post revisions error: {:?}", e);
+                Ok(PostIdRevisionsGetResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
     async fn post_feed_get(
         &self,    
         _: &Method,
@@ -140,8 +235,12 @@ impl Post for Application {
         claims: &Self::Claims,
         query_params: &models::PostFeedGetQueryParams,
     ) -> Result<PostFeedGetResponse, ()> {                
-        match self.state.post_service.feed(claims.user_id, query_params.limit, query_params.offset).await {
-            Ok(posts) => Ok(PostFeedGetResponse::Status200(to_post_dtos(posts))),
+        let page = match to_feed_page(query_params.limit, query_params.offset, &query_params.cursor) {
+            Some(page) => page,
+            None => return Ok(PostFeedGetResponse::Status400)
+        };
+        match self.state.post_service.feed(claims.user_id, page).await {
+            Ok(posts) => Ok(PostFeedGetResponse::Status200(self.to_post_page(claims.user_id, &page, posts).await)),
             Err(e) => {
                 tracing::error!("Feed posts error: {:?}", e);
                 Ok(PostFeedGetResponse::Status500 {
@@ -155,16 +254,150 @@ impl Post for Application {
             }
         }
     }
+
+    async fn post_tag_tag_get(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostTagTagGetPathParams,
+        query_params: &models::PostTagTagGetQueryParams,
+    ) -> Result<PostTagTagGetResponse, ()> {
+        let tag = match normalize_tag(&path_params.tag) {
+            Some(tag) => tag,
+            None => return Ok(PostTagTagGetResponse::Status400)
+        };
+        let page = match to_feed_page(query_params.limit, query_params.offset, &query_params.cursor) {
+            Some(page) => page,
+            None => return Ok(PostTagTagGetResponse::Status400)
+        };
+        match self.state.post_service.tag_feed(claims.user_id, &tag, page).await {
+            Ok(posts) => Ok(PostTagTagGetResponse::Status200(self.to_post_page(claims.user_id, &page, posts).await)),
+            Err(e) => {
+                tracing::error!("Tag feed error: {:?}", e);
+                Ok(PostTagTagGetResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn post_user_user_id_get(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostUserUserIdGetPathParams,
+        query_params: &models::PostUserUserIdGetQueryParams,
+    ) -> Result<PostUserUserIdGetResponse, ()> {
+        let author_id = match Uuid::parse_str(&path_params.user_id) {
+            Ok(id) => id,
+            Err(_) => return Ok(PostUserUserIdGetResponse::Status400)
+        };
+        let page = match to_feed_page(query_params.limit, query_params.offset, &query_params.cursor) {
+            Some(page) => page,
+            None => return Ok(PostUserUserIdGetResponse::Status400)
+        };
+        match self.state.post_service.author_feed(claims.user_id, author_id, page).await {
+            Ok(posts) => Ok(PostUserUserIdGetResponse::Status200(self.to_post_page(claims.user_id, &page, posts).await)),
+            Err(e) => {
+                tracing::error!("Author posts error: {:?}", e);
+                Ok(PostUserUserIdGetResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+}
+
+/// The default limit is resolved here, so every full page gets a cursor of the next one
+fn to_feed_page(limit: Option<u64>, offset: Option<u64>, cursor: &Option<String>) -> Option<FeedPage> {
+    let limit = Some(limit.unwrap_or(DEFAULT_FEED_SIZE));
+    match cursor {
+        Some(cursor) => FeedCursor::decode(cursor).map(|cursor| FeedPage::After { limit, cursor }),
+        None => Some(FeedPage::Offset { limit, offset })
+    }
+}
+
+fn next_cursor(page: &FeedPage, posts: &Vec<model::Post>) -> Option<String> {
+    match (page.limit(), posts.last()) {
+        (Some(limit), Some(last)) if posts.len() as u64 >= limit => Some(FeedCursor::of(last).encode()),
+        _ => None
+    }
+}
+
+impl Application {
+    async fn to_post_page(&self, viewer_id: Uuid, page: &FeedPage, posts: Vec<model::Post>) -> models::PostPage {
+        let next_cursor = next_cursor(page, &posts);
+        models::PostPage {
+            posts: self.to_post_dtos(viewer_id, posts).await,
+            next_cursor
+        }
+    }
+
+    async fn to_post_dtos(&self, viewer_id: Uuid, posts: Vec<model::Post>) -> Vec<openapi::models::Post> {
+        let ids: Vec<Uuid> = posts.iter().map(|post| post.id).collect();
+        let mut summaries = self.state.reaction_service.summaries(viewer_id, &ids).await
+            .warn(format!("Failed to load reactions for {}", viewer_id))
+            .unwrap_or_default();
+        posts.into_iter()
+            .map(|post| {
+                let summary = summaries.remove(&post.id);
+                to_post_dto(post, summary)
+            })
+            .collect()
+    }
 }
 
-fn to_post_dto(post: model::Post) -> openapi::models::Post {
+fn to_post_dto(post: model::Post, summary: Option<ReactionSummary>) -> openapi::models::Post {
     openapi::models::Post {
         id: post.id.to_string(),
         text: post.text,
-        author_user_id: post.author_user_id.to_string()
+        author_user_id: post.author_user_id.to_string(),
+        created_at: Some(post.created_at),
+        edited: Some(post.edited_at.is_some()),
+        edited_at: post.edited_at,
+        visibility: Some(to_visibility_dto(post.visibility)),
+        comments_count: Some(post.comments_count),
+        repost_of: post.repost_of.map(|id| id.to_string()),
+        original_author_user_id: post.original_author_user_id.map(|id| id.to_string()),
+        reactions: summary.as_ref().map(to_reaction_count_dtos),
+        my_reaction: summary.as_ref().and_then(to_my_reaction_dto)
     }    
 }
 
-fn to_post_dtos(posts: Vec<model::Post>) -> Vec<openapi::models::Post> {
-    posts.into_iter().map(to_post_dto).collect()
-}
\ No newline at end of file
+fn to_revision_dto(revision: model::PostRevision) -> models::PostRevision {
+    models::PostRevision {
+        text: revision.text,
+        visibility: to_visibility_dto(revision.visibility),
+        created_at: revision.timestamp
+    }
+}
+
+pub fn from_visibility_dto(visibility: &models::PostVisibility) -> model::Visibility {
+    match visibility {
+        models::PostVisibility::Public => model::Visibility::Public,
+        models::PostVisibility::Friends => model::Visibility::Friends,
+        models::PostVisibility::Private => model::Visibility::Private,
+    }
+}
+
+pub fn to_visibility_dto(visibility: model::Visibility) -> models::PostVisibility {
+    match visibility {
+        model::Visibility::Public => models::PostVisibility::Public,
+        model::Visibility::Friends => models::PostVisibility::Friends,
+        model::Visibility::Private => models::PostVisibility::Private,
+    }
+}
@@ -0,0 +1,59 @@
+use uuid::Uuid;
+use crate::modules::post::model::Post;
+
+#[derive(Debug, Clone, Copy)]
+pub struct FeedCursor {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub post_id: Uuid,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum FeedPage {
+    Offset {
+        limit: Option<u64>,
+        offset: Option<u64>,
+    },
+    After {
+        limit: Option<u64>,
+        cursor: FeedCursor,
+    },
+}
+
+impl FeedCursor {
+    /// Feeds are ordered by creation time, so edits never move a post
+    pub fn of(post: &Post) -> Self {
+        FeedCursor { 
+            timestamp: post.created_at, 
+            post_id: post.id 
+        }
+    }
+
+    pub fn score(&self) -> f64 {
+        self.timestamp.timestamp_micros() as f64
+    }
+
+    pub fn encode(&self) -> String {
+        format!("{:x}{}", self.timestamp.timestamp_micros(), self.post_id.simple())
+    }
+
+    pub fn decode(value: &str) -> Option<Self> {
+        if !value.is_ascii() || value.len() <= 32 {
+            return None;
+        }
+        let (micros, post_id) = value.split_at(value.len() - 32);
+        let micros = i64::from_str_radix(micros, 16).ok()?;
+        Some(FeedCursor {
+            timestamp: chrono::DateTime::from_timestamp_micros(micros)?,
+            post_id: Uuid::parse_str(post_id).ok()?,
+        })
+    }
+}
+
+impl FeedPage {
+    pub fn limit(&self) -> Option<u64> {
+        match self {
+            FeedPage::Offset { limit, .. } => *limit,
+            FeedPage::After { limit, .. } => *limit,
+        }
+    }
+}
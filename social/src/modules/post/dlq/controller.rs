@@ -0,0 +1,120 @@
+use openapi::apis::dlq::{Dlq, AdminDlqEventsGetResponse, AdminDlqReplayPostResponse, AdminDlqPurgePostResponse};
+use axum_extra::headers::Host;
+use axum_extra::extract::CookieJar;
+use axum::http::Method;
+use async_trait::async_trait;
+use openapi::models::{self};
+use crate::modules::post::dlq::{model::{self, Selection}, service_provider::DlqServiceError};
+use crate::modules::auth::auth;
+use crate::Application;
+
+#[async_trait]
+impl Dlq for Application {
+    type Claims = auth::Claims;
+
+    async fn admin_dlq_events_get(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        query_params: &models::AdminDlqEventsGetQueryParams,
+    ) -> Result<AdminDlqEventsGetResponse, ()> {
+        match self.state.dlq_service.list(claims.user_id, query_params.limit).await {
+            Ok(events) => Ok(AdminDlqEventsGetResponse::Status200(events.into_iter().map(to_event_dto).collect())),
+            Err(DlqServiceError::Forbidden(_)) => Ok(AdminDlqEventsGetResponse::Status403),
+            Err(DlqServiceError::AlreadyRunning) => Ok(AdminDlqEventsGetResponse::Status409),
+            Err(e) => {
+                tracing::error!("List dead-lettered events error: {:?}", e);
+                Ok(AdminDlqEventsGetResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn admin_dlq_replay_post(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        body: &Option<models::AdminDlqReplayPostRequest>,
+    ) -> Result<AdminDlqReplayPostResponse, ()> {
+        let selection = match body.as_ref().and_then(|body| to_selection(body.all, &body.ids)) {
+            Some(selection) => selection,
+            None => return Ok(AdminDlqReplayPostResponse::Status400)
+        };
+        match self.state.dlq_service.replay(claims.user_id, selection).await {
+            Ok(count) => Ok(AdminDlqReplayPostResponse::Status202(models::DlqOperationResult { count: count as i64 })),
+            Err(DlqServiceError::Forbidden(_)) => Ok(AdminDlqReplayPostResponse::Status403),
+            Err(DlqServiceError::AlreadyRunning) => Ok(AdminDlqReplayPostResponse::Status409),
+            Err(e) => {
+                tracing::error!("Replay dead-lettered events error: {:?}", e);
+                Ok(AdminDlqReplayPostResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn admin_dlq_purge_post(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        body: &Option<models::AdminDlqPurgePostRequest>,
+    ) -> Result<AdminDlqPurgePostResponse, ()> {
+        let selection = match body.as_ref().and_then(|body| to_selection(body.all, &body.ids)) {
+            Some(selection) => selection,
+            None => return Ok(AdminDlqPurgePostResponse::Status400)
+        };
+        match self.state.dlq_service.purge(claims.user_id, selection).await {
+            Ok(count) => Ok(AdminDlqPurgePostResponse::Status200(models::DlqOperationResult { count: count as i64 })),
+            Err(DlqServiceError::Forbidden(_)) => Ok(AdminDlqPurgePostResponse::Status403),
+            Err(DlqServiceError::AlreadyRunning) => Ok(AdminDlqPurgePostResponse::Status409),
+            Err(e) => {
+                tracing::error!("Purge dead-lettered events error: {:?}", e);
+                Ok(AdminDlqPurgePostResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+}
+
+/// `all` has to be set explicitly, a request without ids is rejected rather than applied to the whole queue
+fn to_selection(all: Option<bool>, ids: &Option<Vec<String>>) -> Option<Selection> {
+    match (all, ids) {
+        (Some(true), _) => Some(Selection::All),
+        (_, Some(ids)) if !ids.is_empty() => Some(Selection::Ids(ids.clone())),
+        _ => None
+    }
+}
+
+fn to_event_dto(event: model::DeadLetteredEvent) -> models::DeadLetteredEvent {
+    models::DeadLetteredEvent {
+        id: event.id,
+        routing_key: event.routing_key,
+        retry_count: event.retry_count,
+        reason: event.reason,
+        dead_lettered_at: event.dead_lettered_at,
+        payload: event.payload
+    }
+}
@@ -0,0 +1,223 @@
+use std::{sync::Arc, time::Duration};
+use async_trait::async_trait;
+use deadpool_lapin::{Pool, lapin::{Channel, message::Delivery, options::{BasicAckOptions, BasicGetOptions, QueueDeclareOptions, QueuePurgeOptions}, types::{AMQPValue, FieldTable}}};
+use tokio::{sync::Mutex, time::MissedTickBehavior};
+use uuid::Uuid;
+use crate::modules::post::{dlq::{model::{DeadLetteredEvent, Selection}, service_provider::{DlqConfig, DlqService, DlqServiceError}}, event::{DomainEvent, EventPublisher}, followers::followers_service::{DEAD_LETTERED_AT_HEADER, FAILED_QUEUE, LAST_ERROR_HEADER, RETRY_COUNT_HEADER, int_header}};
+
+const DEATH_HEADER: &str = "x-death";
+const DEFAULT_LIMIT: u64 = 10;
+
+/// Works on `failed_posts` with `basic_get` without auto-ack. Fetched messages stay unacked on the channel
+/// of the operation until they are acked or the channel is closed, which returns them to the queue in their order.
+/// The queue has no consumers, so live followers consumers are not affected, operations of this instance run one at a time.
+/// Clones share the lock, a replay runs on a clone in the background
+#[derive(Clone)]
+pub struct DlqServiceImpl {
+    pool: Arc<Pool>,
+    publisher: Arc<dyn EventPublisher + Send + Sync>,
+    admin_ids: Vec<Uuid>,
+    config: DlqConfig,
+    running: Arc<Mutex<()>>,
+}
+
+impl DlqServiceImpl {
+    pub fn new(pool: Arc<Pool>, publisher: Arc<dyn EventPublisher + Send + Sync>, admin_ids: Vec<Uuid>, config: DlqConfig) -> Self {
+        DlqServiceImpl {
+            pool,
+            publisher,
+            admin_ids,
+            config,
+            running: Arc::new(Mutex::new(()))
+        }
+    }
+
+    fn check_admin(&self, user_id: Uuid) -> Result<(), DlqServiceError> {
+        if self.admin_ids.contains(&user_id) {
+            Ok(())
+        } else {
+            Err(DlqServiceError::Forbidden(format!("User {} is not an administrator", user_id)))
+        }
+    }
+
+    /// Opens a channel for a single operation, returns it with the number of messages ready in the queue
+    async fn open(&self) -> Result<(Channel, u32), DlqServiceError> {
+        let conn = self.pool.get().await?;
+        let channel = conn.create_channel().await?;
+        // Same declaration as the followers consumer, so the queue exists before the consumer has run
+        let queue = channel.queue_declare(FAILED_QUEUE, QueueDeclareOptions { durable: true, ..QueueDeclareOptions::default() }, FieldTable::default()).await?;
+        Ok((channel, queue.message_count()))
+    }
+
+    /// Closing the channel returns the messages which are still unacked to the queue
+    async fn close(channel: Channel) {
+        if let Err(e) = channel.close(200, "DLQ operation done").await {
+            tracing::warn!("Failed to close DLQ channel {:?}", e);
+        }
+    }
+
+    async fn next(channel: &Channel) -> Result<Option<Delivery>, DlqServiceError> {
+        let message = channel.basic_get(FAILED_QUEUE, BasicGetOptions { no_ack: false }).await?;
+        Ok(message.map(|message| message.delivery))
+    }
+
+    async fn peek(&self, channel: &Channel, limit: u64) -> Result<Vec<DeadLetteredEvent>, DlqServiceError> {
+        let mut events = vec!();
+        while (events.len() as u64) < limit {
+            match Self::next(channel).await? {
+                Some(delivery) => events.push(to_event(&delivery)),
+                None => break
+            }
+        }
+        Ok(events)
+    }
+
+    /// Walks the messages which were in the queue when the operation started, so replayed events
+    /// failing again are not picked up twice. Unselected messages are held until the channel is closed
+    async fn replay_selected(&self, channel: &Channel, count: u32, selection: &Selection) -> Result<u64, DlqServiceError> {
+        let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / self.config.replay_rate_per_second as f64));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut replayed = 0;
+        for _ in 0..count {
+            let Some(delivery) = Self::next(channel).await? else {
+                break;
+            };
+            let id = message_id(&delivery);
+            if !selection.contains(id.as_deref()) {
+                continue;
+            }
+            let event = match serde_json::from_slice::<DomainEvent>(&delivery.data) {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::warn!("Skipping malformed dead-lettered event {:?}: {:?}", id, e);
+                    continue;
+                }
+            };
+            ticker.tick().await;
+            // A replayed event is a new message, it gets a new id and the full number of retries
+            self.publisher.publish(&event).await
+                .map_err(|e| DlqServiceError::Publish(e.to_string()))?;
+            delivery.ack(BasicAckOptions::default()).await?;
+            replayed += 1;
+        }
+        Ok(replayed)
+    }
+
+    async fn purge_selected(&self, channel: &Channel, count: u32, selection: &Selection) -> Result<u64, DlqServiceError> {
+        let mut purged = 0;
+        for _ in 0..count {
+            let Some(delivery) = Self::next(channel).await? else {
+                break;
+            };
+            if selection.contains(message_id(&delivery).as_deref()) {
+                delivery.ack(BasicAckOptions::default()).await?;
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+}
+
+#[async_trait]
+impl DlqService for DlqServiceImpl {
+
+    async fn list(&self, admin_id: Uuid, limit: Option<u64>) -> Result<Vec<DeadLetteredEvent>, DlqServiceError> {
+        self.check_admin(admin_id)?;
+        let _guard = self.running.try_lock().map_err(|_| DlqServiceError::AlreadyRunning)?;
+        let (channel, count) = self.open().await?;
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(self.config.max_peek).min(count as u64);
+        let res = self.peek(&channel, limit).await;
+        Self::close(channel).await;
+        res
+    }
+
+    async fn replay(&self, admin_id: Uuid, selection: Selection) -> Result<u64, DlqServiceError> {
+        self.check_admin(admin_id)?;
+        let guard = Arc::clone(&self.running).try_lock_owned().map_err(|_| DlqServiceError::AlreadyRunning)?;
+        let (channel, count) = self.open().await?;
+        tracing::info!("Replaying dead-lettered events {:?} of {}...", selection, count);
+        // Replay is rate limited and may take minutes, so it does not depend on the request staying open
+        let service = self.clone();
+        tokio::spawn(async move {
+            let _guard = guard;
+            let res = service.replay_selected(&channel, count, &selection).await;
+            Self::close(channel).await;
+            match res {
+                Ok(replayed) => tracing::info!("Replayed {} dead-lettered events", replayed),
+                Err(e) => tracing::error!("Replay of dead-lettered events stopped: {:?}", e)
+            }
+        });
+        Ok(count as u64)
+    }
+
+    async fn purge(&self, admin_id: Uuid, selection: Selection) -> Result<u64, DlqServiceError> {
+        self.check_admin(admin_id)?;
+        let _guard = self.running.try_lock().map_err(|_| DlqServiceError::AlreadyRunning)?;
+        let (channel, count) = self.open().await?;
+        let res = match &selection {
+            Selection::All => channel.queue_purge(FAILED_QUEUE, QueuePurgeOptions::default()).await
+                .map(|purged| purged as u64)
+                .map_err(DlqServiceError::from),
+            Selection::Ids(_) => self.purge_selected(&channel, count, &selection).await
+        };
+        Self::close(channel).await;
+        if let Ok(purged) = &res {
+            tracing::info!("Purged {} dead-lettered events", purged);
+        }
+        res
+    }
+}
+
+/// Id set by the publisher. Messages published without one have no id and are only selected by `Selection::All`
+fn message_id(delivery: &Delivery) -> Option<String> {
+    delivery.properties.message_id().as_ref().map(|id| id.as_str().to_string())
+}
+
+fn header<'a>(delivery: &'a Delivery, name: &str) -> Option<&'a AMQPValue> {
+    delivery.properties.headers().as_ref()?.inner().iter()
+        .find(|(key, _)| key.as_str() == name)
+        .map(|(_, value)| value)
+}
+
+fn text(value: &AMQPValue) -> Option<String> {
+    match value {
+        AMQPValue::LongString(v) => Some(String::from_utf8_lossy(v.as_bytes()).into_owned()),
+        AMQPValue::ShortString(v) => Some(v.as_str().to_string()),
+        _ => None
+    }
+}
+
+/// The most recent entry of the `x-death` header the broker adds on dead-lettering
+fn last_death(delivery: &Delivery) -> Option<&FieldTable> {
+    match header(delivery, DEATH_HEADER)? {
+        AMQPValue::FieldArray(deaths) => match deaths.as_slice().first()? {
+            AMQPValue::FieldTable(death) => Some(death),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+fn death_field<'a>(death: &'a FieldTable, name: &str) -> Option<&'a AMQPValue> {
+    death.inner().iter().find(|(key, _)| key.as_str() == name).map(|(_, value)| value)
+}
+
+fn to_event(delivery: &Delivery) -> DeadLetteredEvent {
+    let death = last_death(delivery);
+    DeadLetteredEvent {
+        id: message_id(delivery),
+        routing_key: serde_json::from_slice::<DomainEvent>(&delivery.data).ok().map(|event| event.routing_key().to_string()),
+        retry_count: int_header(delivery, RETRY_COUNT_HEADER).unwrap_or(0),
+        // The consumer records the handling error, the broker only records why the message was dead-lettered
+        reason: header(delivery, LAST_ERROR_HEADER).and_then(text)
+            .or_else(|| death.and_then(|death| death_field(death, "reason")).and_then(text)),
+        // Events the consumer gave up on are republished with the time, earlier deaths are expired retries
+        dead_lettered_at: header(delivery, DEAD_LETTERED_AT_HEADER)
+            .or_else(|| death.and_then(|death| death_field(death, "time")))
+            .and_then(|time| match time {
+                AMQPValue::Timestamp(seconds) => chrono::DateTime::from_timestamp(*seconds as i64, 0),
+                _ => None
+            }),
+        payload: String::from_utf8_lossy(&delivery.data).into_owned()
+    }
+}
@@ -0,0 +1,4 @@
+pub mod controller;
+pub mod model;
+mod dlq_service;
+pub mod service_provider;
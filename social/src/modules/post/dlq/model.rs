@@ -0,0 +1,30 @@
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeadLetteredEvent {
+    /// Missing when the message was published without an id
+    pub id: Option<String>,
+    /// Missing when the payload is not a domain event
+    pub routing_key: Option<String>,
+    pub retry_count: i64,
+    pub reason: Option<String>,
+    pub dead_lettered_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub payload: String
+}
+
+/// Events an operation applies to
+#[derive(Debug, Clone)]
+pub enum Selection {
+    All,
+    Ids(Vec<String>)
+}
+
+impl Selection {
+    /// Messages without an id are only selected by `All`
+    pub fn contains(&self, id: Option<&str>) -> bool {
+        match self {
+            Selection::All => true,
+            Selection::Ids(ids) => id.is_some_and(|id| ids.iter().any(|selected| selected == id))
+        }
+    }
+}
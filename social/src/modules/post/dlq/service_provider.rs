@@ -0,0 +1,63 @@
+use std::{env, sync::Arc};
+use deadpool_lapin::Pool;
+use thiserror::Error;
+use uuid::Uuid;
+use async_trait::async_trait;
+use crate::modules::post::dlq::{dlq_service::DlqServiceImpl, model::{DeadLetteredEvent, Selection}};
+use crate::modules::post::rabbitmq::RabbitPublisher;
+
+#[derive(Error, Debug)]
+pub enum DlqServiceError {
+    #[error("Pool error: {0}")]
+    Pool(#[from] deadpool_lapin::PoolError),
+
+    #[error("AMQP error: {0}")]
+    Amqp(#[from] deadpool_lapin::lapin::Error),
+
+    #[error("Publish error: {0}")]
+    Publish(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Already running")]
+    AlreadyRunning,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DlqConfig {
+    /// Upper bound of events returned by a single listing
+    pub max_peek: u64,
+    pub replay_rate_per_second: u32,
+}
+
+impl DlqConfig {
+    pub fn from_env() -> Self {
+        DlqConfig {
+            max_peek: env::var("DLQ_MAX_PEEK").ok().and_then(|v| v.parse().ok()).unwrap_or(100).max(1),
+            replay_rate_per_second: env::var("DLQ_REPLAY_RATE_PER_SECOND").ok().and_then(|v| v.parse().ok()).unwrap_or(50).max(1),
+        }
+    }
+}
+
+#[async_trait]
+pub trait DlqService {
+    /// Returns the events at the head of the dead-letter queue, leaving them in the queue
+    async fn list(&self, admin_id: Uuid, limit: Option<u64>) -> Result<Vec<DeadLetteredEvent>, DlqServiceError>;
+    /// Starts publishing the selected events to the exchange again and removing them from the queue in the background.
+    /// Returns the number of events in the queue the replay walks, other operations are rejected until it is done
+    async fn replay(&self, admin_id: Uuid, selection: Selection) -> Result<u64, DlqServiceError>;
+    /// Removes the selected events from the queue, returns their count
+    async fn purge(&self, admin_id: Uuid, selection: Selection) -> Result<u64, DlqServiceError>;
+}
+
+pub fn create_service(rabbitmq: Arc<Pool>, publisher: Arc<RabbitPublisher>, admin_ids: Vec<Uuid>, config: DlqConfig) -> Arc<dyn DlqService + Send + Sync> {
+    Arc::new(
+        DlqServiceImpl::new(
+            rabbitmq,
+            publisher,
+            admin_ids,
+            config
+        )
+    )
+}
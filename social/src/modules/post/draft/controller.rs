@@ -0,0 +1,173 @@
+use openapi::apis::draft::{Draft, PostDraftPostResponse, PostDraftsGetResponse, PostDraftDraftIdPutResponse, PostDraftDraftIdDeleteResponse, PostDraftDraftIdPublishPostResponse};
+use axum_extra::headers::Host;
+use axum_extra::extract::CookieJar;
+use axum::http::Method;
+use async_trait::async_trait;
+use openapi::models::{self};
+use crate::modules::post::controller::{from_visibility_dto, to_visibility_dto};
+use crate::modules::post::draft::{model, repository::DraftRepositoryError, service_provider::DraftServiceError};
+use crate::modules::auth::auth;
+use crate::Application;
+use uuid::Uuid;
+
+#[async_trait]
+impl Draft for Application {
+    type Claims = auth::Claims;
+
+    async fn post_draft_post(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        body: &Option<models::PostDraftPostRequest>,
+    ) -> Result<PostDraftPostResponse, ()> {
+        let body = match body {
+            Some(body) => body,
+            None => return Ok(PostDraftPostResponse::Status400)
+        };
+        let visibility = body.visibility.as_ref().map(from_visibility_dto).unwrap_or_default();
+        match self.state.draft_service.create(claims.user_id, &body.text, visibility, body.publish_at).await {
+            Ok(draft) => Ok(PostDraftPostResponse::Status200(draft.id.to_string())),
+            Err(e) => {
+                tracing::error!("Create draft error: {:?}", e);
+                Ok(PostDraftPostResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn post_drafts_get(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        query_params: &models::PostDraftsGetQueryParams,
+    ) -> Result<PostDraftsGetResponse, ()> {
+        match self.state.draft_service.list(claims.user_id, query_params.limit, query_params.offset).await {
+            Ok(drafts) => Ok(PostDraftsGetResponse::Status200(drafts.into_iter().map(to_draft_dto).collect())),
+            Err(e) => {
+                tracing::error!("List drafts error: {:?}", e);
+                Ok(PostDraftsGetResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn post_draft_draft_id_put(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostDraftDraftIdPutPathParams,
+        body: &Option<models::PostDraftDraftIdPutRequest>,
+    ) -> Result<PostDraftDraftIdPutResponse, ()> {
+        let draft_id = match Uuid::parse_str(&path_params.draft_id) {
+            Ok(id) => id,
+            Err(_) => return Ok(PostDraftDraftIdPutResponse::Status400)
+        };
+        let body = match body {
+            Some(body) => body,
+            None => return Ok(PostDraftDraftIdPutResponse::Status400)
+        };
+        let visibility = body.visibility.as_ref().map(from_visibility_dto).unwrap_or_default();
+        match self.state.draft_service.update(claims.user_id, draft_id, &body.text, visibility, body.publish_at).await {
+            Ok(_) => Ok(PostDraftDraftIdPutResponse::Status200),
+            Err(DraftServiceError::Database(DraftRepositoryError::NotFound(_))) => Ok(PostDraftDraftIdPutResponse::Status400),
+            Err(e) => {
+                tracing::error!("Update draft error: {:?}", e);
+                Ok(PostDraftDraftIdPutResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn post_draft_draft_id_delete(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostDraftDraftIdDeletePathParams,
+    ) -> Result<PostDraftDraftIdDeleteResponse, ()> {
+        let draft_id = match Uuid::parse_str(&path_params.draft_id) {
+            Ok(id) => id,
+            Err(_) => return Ok(PostDraftDraftIdDeleteResponse::Status400)
+        };
+        match self.state.draft_service.delete(claims.user_id, draft_id).await {
+            Ok(()) => Ok(PostDraftDraftIdDeleteResponse::Status200),
+            Err(DraftServiceError::Database(DraftRepositoryError::NotFound(_))) => Ok(PostDraftDraftIdDeleteResponse::Status400),
+            Err(e) => {
+                tracing::error!("Delete draft error: {:?}", e);
+                Ok(PostDraftDraftIdDeleteResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn post_draft_draft_id_publish_post(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostDraftDraftIdPublishPostPathParams,
+    ) -> Result<PostDraftDraftIdPublishPostResponse, ()> {
+        let draft_id = match Uuid::parse_str(&path_params.draft_id) {
+            Ok(id) => id,
+            Err(_) => return Ok(PostDraftDraftIdPublishPostResponse::Status400)
+        };
+        match self.state.draft_service.publish(claims.user_id, draft_id).await {
+            Ok(post) => Ok(PostDraftDraftIdPublishPostResponse::Status200(post.id.to_string())),
+            Err(DraftServiceError::Database(DraftRepositoryError::NotFound(_)) | DraftServiceError::Rejected(_)) => Ok(PostDraftDraftIdPublishPostResponse::Status400),
+            Err(e) => {
+                tracing::error!("Publish draft error: {:?}", e);
+                Ok(PostDraftDraftIdPublishPostResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+}
+
+fn to_draft_dto(draft: model::Draft) -> models::Draft {
+    models::Draft {
+        id: draft.id.to_string(),
+        text: draft.text,
+        visibility: to_visibility_dto(draft.visibility),
+        publish_at: draft.publish_at,
+        updated_at: draft.timestamp,
+        failure_reason: draft.failure_reason
+    }
+}
@@ -0,0 +1,100 @@
+use std::{sync::Arc, time::Duration};
+use uuid::Uuid;
+use async_trait::async_trait;
+use crate::modules::common::ext::extensions::ResultExt;
+use crate::modules::post::{model::{Post, Visibility}, service_provider::{PostService, PostServiceError}};
+use crate::modules::post::draft::{model::Draft, repository::DraftRepository, service_provider::{DraftService, DraftServiceError}};
+
+const SCHEDULER_INTERVAL_SECONDS: u64 = 10;
+const SCHEDULER_BATCH_SIZE: i64 = 100;
+
+pub struct DraftServiceImpl<R>
+where
+    R: DraftRepository {
+    repository: R,
+    post_service: Arc<dyn PostService + Send + Sync>,
+}
+
+impl <R> DraftServiceImpl<R>
+where
+    R: DraftRepository + Send + Sync {
+    pub fn new(repository: R, post_service: Arc<dyn PostService + Send + Sync>) -> Self {
+        DraftServiceImpl {
+            repository,
+            post_service
+        }
+    }
+
+    /// Publishes a claimed draft through the regular post service chain, so feeds and
+    /// notifications are the same as for an immediate post. The post takes the id of the draft,
+    /// so a draft released after its post was created publishes the same post again instead of a duplicate
+    async fn publish_claimed(&self, draft: Draft) -> Result<Post, DraftServiceError> {
+        let created = self.post_service.create(draft.author_user_id, &draft.text, draft.visibility, Some(draft.id)).await
+            .map_err(|e| match e {
+                PostServiceError::Rejected(reason) => DraftServiceError::Rejected(reason),
+                e => DraftServiceError::Publish(e.to_string())
+            });
+        match created {
+            Ok(post) => {
+                self.repository.complete(draft.id).await?;
+                Ok(post)
+            },
+            // A rejected draft isn't retried, the reason stays on it until the user edits or publishes it
+            Err(DraftServiceError::Rejected(reason)) => {
+                self.repository.fail(draft.id, &reason).await.warn(format!("Failed to mark draft {} as failed", draft.id));
+                Err(DraftServiceError::Rejected(reason))
+            },
+            Err(e) => {
+                self.repository.release(draft.id).await.warn(format!("Failed to release draft {}", draft.id));
+                Err(e)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl <R> DraftService for DraftServiceImpl<R>
+where
+    R: DraftRepository + Send + Sync {
+
+    async fn create(&self, user_id: Uuid, text: &String, visibility: Visibility, publish_at: Option<chrono::DateTime<chrono::Utc>>) -> Result<Draft, DraftServiceError> {
+        Ok(self.repository.create(user_id, text, visibility, publish_at).await?)
+    }
+
+    async fn update(&self, user_id: Uuid, draft_id: Uuid, text: &String, visibility: Visibility, publish_at: Option<chrono::DateTime<chrono::Utc>>) -> Result<Draft, DraftServiceError> {
+        Ok(self.repository.update(user_id, draft_id, text, visibility, publish_at).await?)
+    }
+
+    async fn delete(&self, user_id: Uuid, draft_id: Uuid) -> Result<(), DraftServiceError> {
+        Ok(self.repository.delete(user_id, draft_id).await?)
+    }
+
+    async fn list(&self, user_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<Draft>, DraftServiceError> {
+        Ok(self.repository.list(user_id, limit, offset).await?)
+    }
+
+    async fn publish(&self, user_id: Uuid, draft_id: Uuid) -> Result<Post, DraftServiceError> {
+        // Claiming guards against the scheduler publishing the same draft concurrently
+        let draft = self.repository.claim(user_id, draft_id).await?;
+        self.publish_claimed(draft).await
+    }
+
+    async fn run_scheduler(&self) -> () {
+        let mut interval = tokio::time::interval(Duration::from_secs(SCHEDULER_INTERVAL_SECONDS));
+        tracing::info!("Drafts scheduler started...");
+        loop {
+            interval.tick().await;
+            let drafts = match self.repository.claim_due(SCHEDULER_BATCH_SIZE).await {
+                Ok(drafts) => drafts,
+                Err(e) => {
+                    tracing::warn!("Failed to claim scheduled drafts {:?}", e);
+                    continue;
+                }
+            };
+            for draft in drafts {
+                let draft_id = draft.id;
+                self.publish_claimed(draft).await.warn(format!("Failed to publish scheduled draft {}", draft_id));
+            }
+        }
+    }
+}
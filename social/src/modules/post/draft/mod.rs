@@ -0,0 +1,5 @@
+pub mod controller;
+pub mod model;
+mod repository;
+mod draft_service;
+pub mod service_provider;
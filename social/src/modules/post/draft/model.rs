@@ -0,0 +1,15 @@
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use crate::modules::post::model::Visibility;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Draft {
+    pub id: Uuid,
+    pub author_user_id: Uuid,
+    pub text: String,
+    pub visibility: Visibility,
+    pub publish_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Why the last scheduled publication was rejected
+    pub failure_reason: Option<String>
+}
@@ -0,0 +1,158 @@
+use uuid::Uuid;
+use deadpool_postgres::Pool;
+use tokio_postgres::Row;
+use thiserror::Error;
+use std::sync::Arc;
+use async_trait::async_trait;
+use mockall::automock;
+use crate::modules::post::{draft::model::Draft, model::Visibility};
+
+const CLAIM_LEASE_MINUTES: i32 = 5;
+
+#[derive(Error, Debug)]
+pub enum DraftRepositoryError {
+    #[error("Database error: {0}")]
+    Database(#[from] tokio_postgres::Error),
+
+    #[error("Pool error: {0}")]
+    Pool(#[from] deadpool_postgres::PoolError),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+}
+
+#[automock]
+#[async_trait]
+pub trait DraftRepository {
+    async fn create(&self, user_id: Uuid, text: &String, visibility: Visibility, publish_at: Option<chrono::DateTime<chrono::Utc>>) -> Result<Draft, DraftRepositoryError>;
+    async fn update(&self, user_id: Uuid, draft_id: Uuid, text: &String, visibility: Visibility, publish_at: Option<chrono::DateTime<chrono::Utc>>) -> Result<Draft, DraftRepositoryError>;
+    async fn delete(&self, user_id: Uuid, draft_id: Uuid) -> Result<(), DraftRepositoryError>;
+    async fn list(&self, user_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<Draft>, DraftRepositoryError>;
+    async fn claim(&self, user_id: Uuid, draft_id: Uuid) -> Result<Draft, DraftRepositoryError>;
+    async fn claim_due(&self, limit: i64) -> Result<Vec<Draft>, DraftRepositoryError>;
+    async fn release(&self, draft_id: Uuid) -> Result<(), DraftRepositoryError>;
+    async fn complete(&self, draft_id: Uuid) -> Result<(), DraftRepositoryError>;
+    async fn fail(&self, draft_id: Uuid, reason: &String) -> Result<(), DraftRepositoryError>;
+}
+
+pub struct DraftRepositoryImpl {
+    pool: Arc<Pool>
+}
+
+impl DraftRepositoryImpl {
+    pub fn new(pool: Arc<Pool>) -> Self {
+        DraftRepositoryImpl { pool }
+    }
+}
+
+fn to_draft(row: &Row) -> Draft {
+    Draft {
+        id: row.get("id"),
+        author_user_id: row.get("user_id"),
+        text: row.get("text"),
+        visibility: Visibility::parse(row.get("visibility")).unwrap_or_default(),
+        publish_at: row.get("publish_at"),
+        timestamp: row.get("updated_at"),
+        failure_reason: row.get("failure_reason")
+    }
+}
+
+#[async_trait]
+impl DraftRepository for DraftRepositoryImpl {
+
+    async fn create(&self, user_id: Uuid, text: &String, visibility: Visibility, publish_at: Option<chrono::DateTime<chrono::Utc>>) -> Result<Draft, DraftRepositoryError> {
+        let res = self.pool.get().await?.query_one(
+            "INSERT INTO drafts (user_id, text, visibility, publish_at) VALUES ($1, $2, $3, $4)
+                RETURNING id, user_id, text, visibility, publish_at, updated_at, failure_reason",
+            &[&user_id, text, &visibility.as_str(), &publish_at]
+        ).await?;
+        Ok(to_draft(&res))
+    }
+
+    /// A claimed draft is being published and can't be changed, same as for `claim` it's reported as not found.
+    /// Editing a failed draft schedules it again
+    async fn update(&self, user_id: Uuid, draft_id: Uuid, text: &String, visibility: Visibility, publish_at: Option<chrono::DateTime<chrono::Utc>>) -> Result<Draft, DraftRepositoryError> {
+        let res = self.pool.get().await?.query_opt(
+            "UPDATE drafts SET text=$3, visibility=$4, publish_at=$5, updated_at=NOW(), failed_at=NULL, failure_reason=NULL
+                WHERE user_id=$1 AND id=$2 AND (claimed_until IS NULL OR claimed_until < NOW())
+                RETURNING id, user_id, text, visibility, publish_at, updated_at, failure_reason",
+            &[&user_id, &draft_id, text, &visibility.as_str(), &publish_at]
+        ).await?
+            .ok_or(DraftRepositoryError::NotFound(format!("Draft {}", draft_id)))?;
+        Ok(to_draft(&res))
+    }
+
+    async fn delete(&self, user_id: Uuid, draft_id: Uuid) -> Result<(), DraftRepositoryError> {
+        let rows_affected = self.pool.get().await?.execute(
+            "DELETE FROM drafts WHERE user_id=$1 AND id=$2 AND (claimed_until IS NULL OR claimed_until < NOW())",
+            &[&user_id, &draft_id]
+        ).await?;
+        if rows_affected > 0 {
+            Ok(())
+        } else {
+            Err(DraftRepositoryError::NotFound(format!("Draft {}", draft_id)))
+        }
+    }
+
+    async fn list(&self, user_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<Draft>, DraftRepositoryError> {
+        let res = self.pool.get().await?.query(
+            "SELECT id, user_id, text, visibility, publish_at, updated_at, failure_reason FROM drafts
+                WHERE user_id=$1 ORDER BY updated_at DESC LIMIT $2 OFFSET $3",
+            &[&user_id, &limit.map(|v| v as i64), &offset.map(|v| v as i64)]
+        ).await?;
+        Ok(res.iter().map(to_draft).collect())
+    }
+
+    async fn claim(&self, user_id: Uuid, draft_id: Uuid) -> Result<Draft, DraftRepositoryError> {
+        let res = self.pool.get().await?.query_opt(
+            "UPDATE drafts SET claimed_until = NOW() + make_interval(mins => $3)
+                WHERE user_id=$1 AND id=$2 AND (claimed_until IS NULL OR claimed_until < NOW())
+                RETURNING id, user_id, text, visibility, publish_at, updated_at, failure_reason",
+            &[&user_id, &draft_id, &CLAIM_LEASE_MINUTES]
+        ).await?
+            .ok_or(DraftRepositoryError::NotFound(format!("Draft {}", draft_id)))?;
+        Ok(to_draft(&res))
+    }
+
+    async fn claim_due(&self, limit: i64) -> Result<Vec<Draft>, DraftRepositoryError> {
+        // SKIP LOCKED lets concurrent instances take disjoint batches, while the lease
+        // hands drafts of a crashed instance over to the others once it expires
+        let res = self.pool.get().await?.query(
+            "UPDATE drafts SET claimed_until = NOW() + make_interval(mins => $2)
+                WHERE id IN (
+                    SELECT id FROM drafts
+                        WHERE publish_at <= NOW() AND failed_at IS NULL AND (claimed_until IS NULL OR claimed_until < NOW())
+                        ORDER BY publish_at LIMIT $1
+                        FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id, user_id, text, visibility, publish_at, updated_at, failure_reason",
+            &[&limit, &CLAIM_LEASE_MINUTES]
+        ).await?;
+        Ok(res.iter().map(to_draft).collect())
+    }
+
+    async fn release(&self, draft_id: Uuid) -> Result<(), DraftRepositoryError> {
+        self.pool.get().await?.execute(
+            "UPDATE drafts SET claimed_until = NULL WHERE id=$1",
+            &[&draft_id]
+        ).await?;
+        Ok(())
+    }
+
+    async fn complete(&self, draft_id: Uuid) -> Result<(), DraftRepositoryError> {
+        self.pool.get().await?.execute(
+            "DELETE FROM drafts WHERE id=$1",
+            &[&draft_id]
+        ).await?;
+        Ok(())
+    }
+
+    /// A failed draft stays with the user and is not claimed by the scheduler until it's edited or published by hand
+    async fn fail(&self, draft_id: Uuid, reason: &String) -> Result<(), DraftRepositoryError> {
+        self.pool.get().await?.execute(
+            "UPDATE drafts SET claimed_until = NULL, failed_at = NOW(), failure_reason = $2 WHERE id=$1",
+            &[&draft_id, reason]
+        ).await?;
+        Ok(())
+    }
+}
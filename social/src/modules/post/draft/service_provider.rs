@@ -0,0 +1,38 @@
+use std::sync::Arc;
+use deadpool_postgres;
+use thiserror::Error;
+use uuid::Uuid;
+use async_trait::async_trait;
+use crate::modules::post::{model::{Post, Visibility}, service_provider::PostService};
+use crate::modules::post::draft::{draft_service::DraftServiceImpl, model::Draft, repository::{DraftRepositoryError, DraftRepositoryImpl}};
+
+#[derive(Error, Debug)]
+pub enum DraftServiceError {
+    #[error("Database error: {0}")]
+    Database(#[from] DraftRepositoryError),
+
+    #[error("Publish error: {0}")]
+    Publish(String),
+
+    #[error("Rejected: {0}")]
+    Rejected(String),
+}
+
+#[async_trait]
+pub trait DraftService {
+    async fn create(&self, user_id: Uuid, text: &String, visibility: Visibility, publish_at: Option<chrono::DateTime<chrono::Utc>>) -> Result<Draft, DraftServiceError>;
+    async fn update(&self, user_id: Uuid, draft_id: Uuid, text: &String, visibility: Visibility, publish_at: Option<chrono::DateTime<chrono::Utc>>) -> Result<Draft, DraftServiceError>;
+    async fn delete(&self, user_id: Uuid, draft_id: Uuid) -> Result<(), DraftServiceError>;
+    async fn list(&self, user_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<Draft>, DraftServiceError>;
+    async fn publish(&self, user_id: Uuid, draft_id: Uuid) -> Result<Post, DraftServiceError>;
+    async fn run_scheduler(&self) -> ();
+}
+
+pub fn create_service(pool: Arc<deadpool_postgres::Pool>, post_service: Arc<dyn PostService + Send + Sync>) -> Arc<dyn DraftService + Send + Sync> {
+    Arc::new(
+        DraftServiceImpl::new(
+            DraftRepositoryImpl::new(pool),
+            post_service
+        )
+    )
+}
@@ -1,6 +1,7 @@
 use uuid::Uuid;
 use serde::{Deserialize, Serialize};
-use crate::modules::post::model::Post;
+use async_trait::async_trait;
+use crate::modules::post::{comment::model::Comment, model::Post};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum DomainEvent {
@@ -16,6 +17,15 @@ pub enum DomainEvent {
         user_id: Uuid,
         post_id: Uuid,        
     },
+    CommentCreated {
+        user_id: Uuid,
+        post_author_id: Uuid,
+        comment: Comment,
+    },
+    UsersMentioned {
+        user_id: Uuid,
+        post: Post,
+    },
 }
 
 impl DomainEvent {
@@ -23,7 +33,36 @@ impl DomainEvent {
         match self {
             DomainEvent::PostCreated {user_id, ..} => user_id,
             DomainEvent::PostUpdated {user_id, ..} => user_id,
-            DomainEvent::PostDeleted {user_id, ..} => user_id
+            DomainEvent::PostDeleted {user_id, ..} => user_id,
+            DomainEvent::CommentCreated {user_id, ..} => user_id,
+            DomainEvent::UsersMentioned {user_id, ..} => user_id
         }        
     }
+
+    /// Post the event belongs to, events of one post are delivered in order
+    pub fn post_id(&self) -> &Uuid {
+        match self {
+            DomainEvent::PostCreated {post, ..} => &post.id,
+            DomainEvent::PostUpdated {post, ..} => &post.id,
+            DomainEvent::PostDeleted {post_id, ..} => post_id,
+            DomainEvent::CommentCreated {comment, ..} => &comment.post_id,
+            DomainEvent::UsersMentioned {post, ..} => &post.id
+        }
+    }
+
+    pub fn routing_key(&self) -> &'static str {
+        match self {
+            DomainEvent::PostCreated { .. } => "post.created",
+            DomainEvent::PostUpdated { .. } => "post.updated",
+            DomainEvent::PostDeleted { .. } => "post.deleted",
+            DomainEvent::CommentCreated { .. } => "comment.created",
+            DomainEvent::UsersMentioned { .. } => "post.mentioned",
+        }
+    }
+}
+
+/// Delivers domain events to their consumers: the broker in production, the followers service directly in the dev profile
+#[async_trait]
+pub trait EventPublisher {
+    async fn publish(&self, event: &DomainEvent) -> Result<(), Box<dyn std::error::Error>>;
 }
@@ -7,6 +7,7 @@ use axum::extract::Query;
 use std::sync::Arc;
 use crate::app_state::AppState;
 use crate::modules::auth::auth::verify_token;
+use crate::modules::common::ext::extensions::ResultExt;
 use serde::Deserialize;
 
 
@@ -26,7 +27,11 @@ pub async fn post_feed_ws_handler(
             return (StatusCode::UNAUTHORIZED, "Invalid token").into_response();
         }
     };      
+    // Posts of followed celebrities are not sent to followers one by one, the connection subscribes to their broadcasts
+    let celebrities = state.post_service.followed_celebrities(claims.user_id).await
+        .warn(format!("Failed to load celebrities followed by {}", claims.user_id))
+        .unwrap_or_default();
     ws.on_upgrade(move |socket| async move {
-        state.ws_manager.handle_connection(claims.user_id, socket).await;}
+        state.ws_manager.handle_connection(claims.user_id, socket, celebrities).await;}
     )
 }
\ No newline at end of file
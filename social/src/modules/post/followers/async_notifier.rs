@@ -3,7 +3,7 @@ use async_trait::async_trait;
 use std::sync::Arc;
 use serde::Serialize;
 
-use crate::modules::{common::ws::ws_manager::WebSocketManager, post::{followers::follower_event_bus::FollowerEventListener, model::Post}};
+use crate::modules::{common::ws::ws_manager::WebSocketManager, post::{comment::model::Comment, event::DomainEvent, followers::follower_event_bus::{FollowerEventListener, ListenerError}, model::Post}};
 
 pub struct AsyncNotifier {    
     ws_manager: Arc<WebSocketManager>
@@ -13,7 +13,9 @@ pub struct AsyncNotifier {
 enum PostEvent {
     Create,
     Update,
-    Delete
+    Delete,
+    Comment,
+    Mention
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -33,7 +35,7 @@ impl AsyncNotifier {
 
 #[async_trait]
 impl FollowerEventListener for AsyncNotifier {    
-    async fn create(&self, _: &Uuid, followers: &Vec<Uuid>, post: &Post) {
+    async fn create(&self, _: &Uuid, followers: &Vec<Uuid>, post: &Post) -> Result<(), ListenerError> {
         let _ = self.ws_manager.send_to_users(
             followers, 
             &PostNotification {
@@ -43,8 +45,10 @@ impl FollowerEventListener for AsyncNotifier {
                 text: Some(post.text.clone())
             }
         );
+        // Notifications are best effort, a retry would repeat them to the users who got them
+        Ok(())
     }    
-    async fn update(&self, _: &Uuid, followers: &Vec<Uuid>, post: &Post) {
+    async fn update(&self, _: &Uuid, followers: &Vec<Uuid>, post: &Post) -> Result<(), ListenerError> {
         let _ = self.ws_manager.send_to_users(
             followers, 
             &PostNotification {
@@ -54,8 +58,9 @@ impl FollowerEventListener for AsyncNotifier {
                 text: Some(post.text.clone())
             }
         );
+        Ok(())
     }   
-    async fn delete(&self, _: &Uuid, followers: &Vec<Uuid>, post_id: &Uuid) {
+    async fn delete(&self, _: &Uuid, followers: &Vec<Uuid>, post_id: &Uuid) -> Result<(), ListenerError> {
         let _ = self.ws_manager.send_to_users(
             followers, 
             &PostNotification {
@@ -64,5 +69,38 @@ impl FollowerEventListener for AsyncNotifier {
                 text: None
             }
         );
+        Ok(())
     }
-}
\ No newline at end of file
+    async fn comment(&self, _: &Uuid, recipients: &Vec<Uuid>, comment: &Comment) -> Result<(), ListenerError> {
+        let _ = self.ws_manager.send_to_users(
+            recipients, 
+            &PostNotification {
+                event: PostEvent::Comment, 
+                post_id: comment.post_id,
+                text: Some(comment.text.clone())
+            }
+        );
+        Ok(())
+    }
+    async fn mention(&self, _: &Uuid, recipients: &Vec<Uuid>, post: &Post) -> Result<(), ListenerError> {
+        let _ = self.ws_manager.send_to_users(
+            recipients, 
+            &PostNotification {
+                event: PostEvent::Mention, 
+                post_id: post.id,
+                text: Some(post.text.clone())
+            }
+        );
+        Ok(())
+    }
+    async fn broadcast(&self, user_id: &Uuid, event: &DomainEvent) -> Result<(), ListenerError> {
+        let notification = match event {
+            DomainEvent::PostCreated { post, .. } => PostNotification { event: PostEvent::Create, post_id: post.id, text: Some(post.text.clone()) },
+            DomainEvent::PostUpdated { post, .. } => PostNotification { event: PostEvent::Update, post_id: post.id, text: Some(post.text.clone()) },
+            DomainEvent::PostDeleted { post_id, .. } => PostNotification { event: PostEvent::Delete, post_id: *post_id, text: None },
+            _ => return Ok(())
+        };
+        let _ = self.ws_manager.send_to_subscribers(*user_id, &notification);
+        Ok(())
+    }
+}
@@ -1,17 +1,17 @@
 use async_trait::async_trait;
 use uuid::Uuid;
-use crate::modules::{common::ext::extensions::ResultExt, post::{followers::follower_event_bus::FollowerEventListener, model::Post, post_cache::FeedCache}};
+use crate::modules::post::{followers::follower_event_bus::{FollowerEventListener, ListenerError}, model::Post, post_cache::{AuthorCache, FeedCache}};
 
 
 pub struct CachingPostListener<C> 
 where 
-    C: FeedCache,{
+    C: FeedCache + AuthorCache,{
     pub cache: C,
 }
 
 impl <C> CachingPostListener<C> 
 where 
-    C: FeedCache, {
+    C: FeedCache + AuthorCache, {
     pub fn new(cache: C) -> Self {
         CachingPostListener {             
             cache
@@ -19,20 +19,34 @@ where
     }
 }
 
+/// Posts of celebrities come without followers, they are only saved to the author timeline which feeds merge on read
 #[async_trait]
 impl <C> FollowerEventListener for CachingPostListener<C> 
 where 
-    C: FeedCache + Send + Sync, {
+    C: FeedCache + AuthorCache + Send + Sync, {
     
-    async fn create(&self, _: &Uuid, followers: &Vec<Uuid>, post: &Post) {
-        self.cache.process_save(followers, &post).await.warn("Failed to notify followers feeds on post create".to_string());
+    async fn create(&self, _: &Uuid, followers: &Vec<Uuid>, post: &Post) -> Result<(), ListenerError> {
+        self.cache.process_save(followers, &post).await?;
+        self.cache.save_author_post(&post).await?;
+        Ok(())
     }
 
-    async fn update(&self, _: &Uuid, followers: &Vec<Uuid>, post: &Post) {
-        self.cache.process_save(followers, &post).await.warn("Failed to notify followers feeds on post update".to_string());
+    async fn update(&self, _: &Uuid, followers: &Vec<Uuid>, post: &Post) -> Result<(), ListenerError> {
+        self.cache.process_save(followers, &post).await?;
+        self.cache.save_author_post(&post).await?;
+        Ok(())
     } 
 
-    async fn delete(&self, _: &Uuid, followers: &Vec<Uuid>, post_id: &Uuid) {
-        self.cache.process_delete(followers, post_id).await.warn("Failed to notify followers feeds on post create".to_string());
+    async fn delete(&self, user_id: &Uuid, followers: &Vec<Uuid>, post_id: &Uuid) -> Result<(), ListenerError> {
+        self.cache.process_delete(followers, post_id).await?;
+        self.cache.delete_author_post(user_id, post_id).await?;
+        Ok(())
     }
-}
\ No newline at end of file
+
+    async fn celebrity_changed(&self, _: &Uuid, followers: &Vec<Uuid>) -> Result<(), ListenerError> {
+        for follower_id in followers {
+            self.cache.delete_followed_celebrities(*follower_id).await?;
+        }
+        Ok(())
+    }
+}
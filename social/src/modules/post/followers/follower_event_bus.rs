@@ -1,49 +1,81 @@
 use serde::Serialize;
 use uuid::Uuid;
 use crate::modules::post::event::DomainEvent;
-use crate::modules::post::model::Post;
+use crate::modules::post::{comment::model::Comment, model::Post};
 use async_trait::async_trait;
 use std::sync::Arc; 
 
 #[derive(Clone, Debug, Serialize)]
 pub struct FollowerEvent {
     pub domain_event: DomainEvent,   
-    pub followers: Vec<Uuid>
+    pub followers: Vec<Uuid>,
+    /// Public post event of a celebrity: followers are not loaded, feeds merge the post on read
+    /// and live updates go to the subscribers of the author
+    pub fanned_out_on_read: bool
 }
 
+impl FollowerEvent {
+    pub fn new(domain_event: DomainEvent, followers: Vec<Uuid>) -> Self {
+        FollowerEvent { domain_event, followers, fanned_out_on_read: false }
+    }
+}
+
+/// Error of a listener, the event is retried when any listener fails
+pub type ListenerError = Box<dyn std::error::Error + Send + Sync>;
+
 #[async_trait]
 pub trait FollowerEventListener {
-    async fn create(&self, user_id: &Uuid, followers: &Vec<Uuid>, post: &Post) -> ();    
-    async fn update(&self, user_id: &Uuid, followers: &Vec<Uuid>, post: &Post) -> ();    
-    async fn delete(&self, user_id: &Uuid, followers: &Vec<Uuid>, post_id: &Uuid) -> ();    
+    async fn create(&self, user_id: &Uuid, followers: &Vec<Uuid>, post: &Post) -> Result<(), ListenerError>;
+    async fn update(&self, user_id: &Uuid, followers: &Vec<Uuid>, post: &Post) -> Result<(), ListenerError>;
+    async fn delete(&self, user_id: &Uuid, followers: &Vec<Uuid>, post_id: &Uuid) -> Result<(), ListenerError>;
+    async fn comment(&self, _user_id: &Uuid, _recipients: &Vec<Uuid>, _comment: &Comment) -> Result<(), ListenerError> {
+        Ok(())
+    }
+    async fn mention(&self, _user_id: &Uuid, _recipients: &Vec<Uuid>, _post: &Post) -> Result<(), ListenerError> {
+        Ok(())
+    }
+    /// Event of a celebrity, whose followers are not loaded
+    async fn broadcast(&self, _user_id: &Uuid, _event: &DomainEvent) -> Result<(), ListenerError> {
+        Ok(())
+    }
+    /// The followers count of the author crossed the celebrity threshold
+    async fn celebrity_changed(&self, _author_id: &Uuid, _followers: &Vec<Uuid>) -> Result<(), ListenerError> {
+        Ok(())
+    }
 }
 
+/// Hands follower events to every listener. Listeners run concurrently and the event is done once all of them are
 pub struct EventBus {
-    sender: tokio::sync::mpsc::Sender<FollowerEvent>,
+    listeners: Vec<Arc<dyn FollowerEventListener + Send + Sync>>,
 }
 
 impl EventBus {
-    pub fn new(capacity: usize, listeners: Vec<Arc<dyn FollowerEventListener + Send + Sync>>) -> Self {
-        let (tx, mut rx) = tokio::sync::mpsc::channel::<FollowerEvent>(capacity);
-        tokio::spawn(async move {
-            while let Some(event) = rx.recv().await {                
-                let futures = listeners.iter().map(|l| {
-                    match &event.domain_event {
-                        DomainEvent::PostCreated { user_id, post} => l.create(&user_id, &event.followers, &post),                
-                        DomainEvent::PostUpdated { user_id, post} => l.update(&user_id, &event.followers, &post),
-                        DomainEvent::PostDeleted { user_id, post_id} => l.delete(&user_id, &event.followers, &post_id)
-                    }
-                });
-                futures::future::join_all(futures).await;                
+    pub fn new(listeners: Vec<Arc<dyn FollowerEventListener + Send + Sync>>) -> Self {
+        Self { listeners }
+    }
+
+    /// Returns the first error of the listeners, the ones which succeeded are not rolled back
+    pub async fn publish(&self, event: FollowerEvent) -> Result<(), ListenerError> {
+        let futures = self.listeners.iter().map(|l| {
+            match &event.domain_event {
+                DomainEvent::PostCreated { user_id, post} => l.create(&user_id, &event.followers, &post),                
+                DomainEvent::PostUpdated { user_id, post} => l.update(&user_id, &event.followers, &post),
+                DomainEvent::PostDeleted { user_id, post_id} => l.delete(&user_id, &event.followers, &post_id),
+                DomainEvent::CommentCreated { user_id, comment, ..} => l.comment(&user_id, &event.followers, &comment),
+                DomainEvent::UsersMentioned { user_id, post} => l.mention(&user_id, &event.followers, &post)
             }
         });
-
-        Self { sender: tx }
+        futures::future::join_all(futures).await.into_iter().collect::<Result<(), ListenerError>>()?;
+        if event.fanned_out_on_read {
+            let user_id = event.domain_event.user_id();
+            let futures = self.listeners.iter().map(|l| l.broadcast(user_id, &event.domain_event));
+            futures::future::join_all(futures).await.into_iter().collect::<Result<(), ListenerError>>()?;
+        }
+        Ok(())
     }
 
-    pub async fn publish(&self, event: FollowerEvent) {
-        if let Err(e) = self.sender.send(event).await {
-            tracing::info!("Failed to publish event: {:?}", e);
-        }
+    pub async fn celebrity_changed(&self, author_id: &Uuid, followers: &Vec<Uuid>) -> Result<(), ListenerError> {
+        let futures = self.listeners.iter().map(|l| l.celebrity_changed(author_id, followers));
+        futures::future::join_all(futures).await.into_iter().collect()
     }
 }
@@ -1,33 +1,115 @@
-use std::sync::Arc;
+use std::{collections::hash_map::DefaultHasher, hash::{Hash, Hasher}, sync::Arc};
 use async_trait::async_trait;
-use deadpool_lapin::{Pool, lapin::{Consumer, ExchangeKind, options::{BasicAckOptions, BasicConsumeOptions, ExchangeDeclareOptions, QueueBindOptions, QueueDeclareOptions}, types::{AMQPValue, FieldTable, ShortString}}};
+use deadpool_lapin::{Pool, lapin::{Channel, Consumer, ExchangeKind, message::Delivery, options::{BasicAckOptions, BasicConsumeOptions, BasicNackOptions, BasicPublishOptions, BasicQosOptions, ConfirmSelectOptions, ExchangeDeclareOptions, QueueBindOptions, QueueDeclareOptions}, publisher_confirm::Confirmation, types::{AMQPValue, FieldTable}}};
+use tokio::sync::mpsc;
 use uuid::Uuid;
-use crate::modules::{friend::repository::{FriendRepository, FriendRepositoryError}, post::{event::DomainEvent, followers::follower_event_bus::{EventBus, FollowerEvent, FollowerEventListener}}};
+use thiserror::Error;
+use crate::modules::{common::ext::extensions::ResultExt, friend::repository::{FriendRepository, FriendRepositoryError}, post::{event::DomainEvent, followers::{follower_event_bus::{EventBus, FollowerEvent, FollowerEventListener, ListenerError}, service_provider::ConsumerConfig}, model::{Post, Visibility}, rabbitmq::PublishError, repository::{PostRepository, PostRepositoryError}}};
 use tokio_stream::StreamExt;
 
+pub const POST_EVENTS_QUEUE: &str = "post_events";
+/// Failed events wait here for the retry delay, then dead-letter back to `POST_EVENTS_QUEUE`
+pub const RETRY_QUEUE: &str = "post_events_retry";
+pub const DLX_EXCHANGE: &str = "dlx_exchange";
+pub const DLX_ROUTING_KEY: &str = "failed";
+pub const FAILED_QUEUE: &str = "failed_posts";
+/// Number of times the event was retried, set on copies sent to `RETRY_QUEUE`
+pub const RETRY_COUNT_HEADER: &str = "x-retry-count";
+/// Error of the last failed attempt
+pub const LAST_ERROR_HEADER: &str = "x-last-error";
+/// Time the consumer gave up on the event, set on copies sent to `FAILED_QUEUE`
+pub const DEAD_LETTERED_AT_HEADER: &str = "x-dead-lettered-at";
+
+#[derive(Error, Debug)]
+pub enum FollowersServiceError {
+    #[error("Friend repository error: {0}")]
+    Friends(#[from] FriendRepositoryError),
+
+    #[error("Post repository error: {0}")]
+    Posts(#[from] PostRepositoryError),
+
+    #[error("Listener error: {0}")]
+    Listener(#[from] ListenerError),
+}
+
 #[async_trait]
 pub trait FollowersService {
+    /// Consumes post events, reconnecting with backoff whenever the connection drops
     async fn run_consumer(&self) -> Result<(), Box<dyn std::error::Error>>;
+    /// Fans the event out to the audience of its author, returns once every listener has handled it
+    async fn handle(&self, event: DomainEvent) -> Result<(), FollowersServiceError>;
+    /// Drops the celebrities cached for followers of the author, whose followers count crossed the celebrity threshold.
+    /// Feeds then merge posts of the author on read or get them pushed, as the new posts of the author are
+    async fn celebrity_changed(&self, author_id: Uuid) -> Result<(), FollowersServiceError>;
 }
 
-pub struct FollowersServiceImpl<F> 
+pub struct FollowersServiceImpl<F, P> 
 where 
-    F: FriendRepository {            
+    F: FriendRepository,
+    P: PostRepository {            
     repository: F,    
+    post_repository: P,
     event_bus: EventBus,
     pool: Arc<Pool>,
-    exchange: String
+    exchange: String,
+    celebrity_threshold: u64,
+    config: ConsumerConfig,
+}
+
+/// Declares the durable exchanges and queues every routing key of `DomainEvent` is routed to.
+/// The publisher declares them too, so events published before any consumer ran are queued rather than returned
+pub async fn declare_post_events(channel: &Channel, exchange: &str) -> Result<(), deadpool_lapin::lapin::Error> {
+    channel.exchange_declare(
+        DLX_EXCHANGE, 
+        ExchangeKind::Direct, 
+        ExchangeDeclareOptions { durable: true, ..ExchangeDeclareOptions::default() }, 
+        FieldTable::default()
+    ).await?;
+    channel.exchange_declare(
+        exchange, 
+        ExchangeKind::Topic, 
+        ExchangeDeclareOptions { durable: true, ..ExchangeDeclareOptions::default() }, 
+        FieldTable::default()
+    ).await?;
+    channel.queue_declare(FAILED_QUEUE, QueueDeclareOptions { durable: true, ..QueueDeclareOptions::default() }, FieldTable::default()).await?;
+    channel.queue_bind(FAILED_QUEUE, DLX_EXCHANGE, DLX_ROUTING_KEY, QueueBindOptions::default(), FieldTable::default()).await?;
+    let mut args = FieldTable::default();
+    args.insert("x-dead-letter-exchange".into(), AMQPValue::LongString(DLX_EXCHANGE.into()));
+    args.insert("x-dead-letter-routing-key".into(), AMQPValue::LongString(DLX_ROUTING_KEY.into()));
+    channel.queue_declare(POST_EVENTS_QUEUE, QueueDeclareOptions { durable: true, ..QueueDeclareOptions::default() }, args).await?;
+    channel.queue_bind(POST_EVENTS_QUEUE, exchange, "post.*", QueueBindOptions::default(), FieldTable::default()).await?;
+    channel.queue_bind(POST_EVENTS_QUEUE, exchange, "comment.*", QueueBindOptions::default(), FieldTable::default()).await?;
+    Ok(())
 }
 
-impl <F> FollowersServiceImpl<F>
+/// Reads an integer header whatever integer type the client encoded it with
+pub(crate) fn int_header(delivery: &Delivery, name: &str) -> Option<i64> {
+    let (_, value) = delivery.properties.headers().as_ref()?.inner().iter().find(|(key, _)| key.as_str() == name)?;
+    match value {
+        AMQPValue::ShortShortInt(v) => Some(*v as i64),
+        AMQPValue::ShortShortUInt(v) => Some(*v as i64),
+        AMQPValue::ShortInt(v) => Some(*v as i64),
+        AMQPValue::ShortUInt(v) => Some(*v as i64),
+        AMQPValue::LongInt(v) => Some(*v as i64),
+        AMQPValue::LongUInt(v) => Some(*v as i64),
+        AMQPValue::LongLongInt(v) => Some(*v),
+        _ => None
+    }
+}
+
+impl <F, P> FollowersServiceImpl<F, P>
 where 
-    F: FriendRepository + Send + Sync {
-    pub fn new(repository: F, listeners: Vec<Arc<dyn FollowerEventListener + Send + Sync>>, pool: Arc<Pool>, exchange: String) -> Self {
+    F: FriendRepository + Send + Sync,
+    P: PostRepository + Send + Sync {
+    pub fn new(repository: F, post_repository: P, listeners: Vec<Arc<dyn FollowerEventListener + Send + Sync>>, pool: Arc<Pool>, exchange: String, celebrity_threshold: u64, config: ConsumerConfig) -> Self {
         FollowersServiceImpl { 
             repository,
-            event_bus: EventBus::new(100, listeners),
+            post_repository,
+            event_bus: EventBus::new(listeners),
             pool,
-            exchange
+            exchange,
+            celebrity_threshold,
+            config
         }
     }
 
@@ -35,55 +117,249 @@ where
         Ok(self.repository.get_followers_ids(user_id).await?)
     }
 
-    async fn init_consumer(&self) -> Result<Consumer, Box<dyn std::error::Error>> {
+    async fn fetch_audience(&self, user_id: Uuid, visibility: Visibility) -> Result<Vec<Uuid>, FriendRepositoryError> {
+        match visibility {
+            Visibility::Public => self.fetch_followers(user_id).await,
+            Visibility::Friends => Ok(self.repository.get_mutual_ids(user_id).await?),
+            Visibility::Private => Ok(vec!())
+        }
+    }
+
+    /// Splits followers into the post audience and the ones who must not see it
+    async fn fetch_restricted(&self, user_id: Uuid, post: &Post) -> Result<(Vec<Uuid>, Vec<Uuid>), FriendRepositoryError> {
+        let followers = self.fetch_followers(user_id).await?;
+        if post.is_public() {
+            return Ok((followers, vec!()));
+        }
+        let audience = self.fetch_audience(user_id, post.visibility).await?;
+        let excluded = followers.into_iter().filter(|id| !audience.contains(id)).collect();
+        Ok((audience, excluded))
+    }
+
+    /// Public posts of authors with at least `celebrity_threshold` followers are merged into feeds on read, so their followers are not loaded.
+    /// Decided by `users.followers_count`, the count feeds pick followed celebrities by. Deletes of a celebrity are not fanned out either,
+    /// ids of deleted posts left in feeds are dropped on read
+    async fn is_fanned_out_on_read(&self, event: &DomainEvent) -> Result<bool, FriendRepositoryError> {
+        let public = match event {
+            DomainEvent::PostCreated { post, .. } | DomainEvent::PostUpdated { post, .. } => post.is_public(),
+            DomainEvent::PostDeleted { .. } => true,
+            _ => false
+        };
+        Ok(public && self.repository.get_followers_count(*event.user_id()).await? >= self.celebrity_threshold as i64)
+    }
+
+    /// Current state of the post as its author sees it, `None` once it is deleted or hidden
+    async fn current_post(&self, user_id: Uuid, post_id: Uuid) -> Result<Option<Post>, PostRepositoryError> {
+        match self.post_repository.get(user_id, post_id).await {
+            Ok(post) => Ok(Some(post)),
+            Err(PostRepositoryError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e)
+        }
+    }
+
+    /// A retried event may arrive after newer events of its post, so the event is checked against the post as it is now.
+    /// Creates and updates carry the current post and are dropped once it is removed,
+    /// deletes are dropped once the post is back
+    async fn current_event(&self, event: DomainEvent) -> Result<Option<DomainEvent>, PostRepositoryError> {
+        Ok(match event {
+            DomainEvent::PostCreated { user_id, post } => self.current_post(post.author_user_id, post.id).await?
+                .map(|post| DomainEvent::PostCreated { user_id, post }),
+            DomainEvent::PostUpdated { user_id, post } => self.current_post(post.author_user_id, post.id).await?
+                .map(|post| DomainEvent::PostUpdated { user_id, post }),
+            DomainEvent::PostDeleted { user_id, post_id } => match self.current_post(user_id, post_id).await? {
+                Some(_) => None,
+                None => Some(DomainEvent::PostDeleted { user_id, post_id })
+            },
+            event => Some(event)
+        })
+    }
+
+    /// Notifies users mentioned in the post who were not notified yet. Mentions are claimed first,
+    /// so a failed notification is not repeated
+    async fn notify_mentions(&self, user_id: Uuid, post: &Post) {
+        match self.post_repository.claim_mentions(post.id).await {
+            Ok(mentioned) if !mentioned.is_empty() => {
+                self.event_bus.publish(FollowerEvent::new(DomainEvent::UsersMentioned { user_id, post: post.clone() }, mentioned))
+                    .await.warn("Failed to notify mentioned users".to_string());
+            },
+            Ok(_) => {},
+            Err(e) => tracing::warn!("Failed to claim mentions {:?}", e)
+        }
+    }
+
+    async fn init_consumer(&self) -> Result<(Channel, Consumer), Box<dyn std::error::Error + Send + Sync>> {
         let conn = self.pool.get().await?;
         let channel = conn.create_channel().await?;
-        channel.exchange_declare(
-            "dlx_exchange", 
-            ExchangeKind::Direct, 
-            ExchangeDeclareOptions::default(), 
-            FieldTable::default()
-        ).await?;
-        channel.exchange_declare(
-            &self.exchange, 
-            ExchangeKind::Topic, 
-            ExchangeDeclareOptions::default(), 
-            FieldTable::default()
-        ).await?;
-        channel.queue_declare("failed_posts", QueueDeclareOptions::default(), FieldTable::default()).await?;
-        channel.queue_bind("failed_posts", "dlx_exchange", "failed", QueueBindOptions::default(), FieldTable::default()).await?;
-        let mut args = FieldTable::default();
-        args.insert("x-dead-letter-exchange".into(), AMQPValue::LongString("dlx_exchange".into()));
-        args.insert("x-dead-letter-routing-key".into(), AMQPValue::LongString("failed".into()));
-        channel.queue_declare("post_events", QueueDeclareOptions::default(), args).await?;
-        channel.queue_bind("post_events", &self.exchange, "post.*", QueueBindOptions::default(), FieldTable::default()).await?;
-        Ok(channel.basic_consume("post_events", "worker", BasicConsumeOptions::default(), FieldTable::default()).await?)
-    }    
+        declare_post_events(&channel, &self.exchange).await?;
+        // Expired retries go through the default exchange, which routes by queue name
+        let mut retry_args = FieldTable::default();
+        retry_args.insert("x-message-ttl".into(), AMQPValue::LongLongInt(self.config.retry_delay.as_millis() as i64));
+        retry_args.insert("x-dead-letter-exchange".into(), AMQPValue::LongString("".into()));
+        retry_args.insert("x-dead-letter-routing-key".into(), AMQPValue::LongString(POST_EVENTS_QUEUE.into()));
+        channel.queue_declare(RETRY_QUEUE, QueueDeclareOptions { durable: true, ..QueueDeclareOptions::default() }, retry_args).await?;
+        // Copies sent to the retry and failed queues are confirmed before the original is acked
+        channel.confirm_select(ConfirmSelectOptions::default()).await?;
+        channel.basic_qos(self.config.prefetch, BasicQosOptions::default()).await?;
+        let consumer = channel.basic_consume(POST_EVENTS_QUEUE, "worker", BasicConsumeOptions::default(), FieldTable::default()).await?;
+        Ok((channel, consumer))
+    }
+
+    /// Publishes a copy of the delivery with the given headers and waits for the broker to confirm it
+    async fn republish(&self, channel: &Channel, exchange: &str, routing_key: &str, delivery: &Delivery, headers: FieldTable) -> Result<(), PublishError> {
+        let confirmation = channel.basic_publish(
+            exchange,
+            routing_key,
+            BasicPublishOptions { mandatory: true, ..BasicPublishOptions::default() },
+            &delivery.data,
+            delivery.properties.clone().with_headers(headers),
+        ).await?.await?;
+        match confirmation {
+            Confirmation::Ack(None) | Confirmation::NotRequested => Ok(()),
+            Confirmation::Ack(Some(returned)) | Confirmation::Nack(Some(returned)) =>
+                Err(PublishError::Returned(returned.reply_code, returned.reply_text.to_string())),
+            Confirmation::Nack(None) => Err(PublishError::Nacked)
+        }
+    }
+
+    /// Sends a copy of the failed delivery to the retry queue, or to the failed queue once retries are used up.
+    /// Either copy records the error, and the delivery is acked only once the broker confirmed the copy
+    async fn retry_or_reject(&self, channel: &Channel, delivery: &Delivery, error: String) -> Result<(), PublishError> {
+        let retries = int_header(delivery, RETRY_COUNT_HEADER).unwrap_or(0);
+        let mut headers = delivery.properties.headers().clone().unwrap_or_default();
+        headers.insert(LAST_ERROR_HEADER.into(), AMQPValue::LongString(error.clone().into()));
+        let res = if retries >= self.config.max_retries as i64 {
+            tracing::error!("Dead-lettering event after {} retries: {}", retries, error);
+            headers.insert(DEAD_LETTERED_AT_HEADER.into(), AMQPValue::Timestamp(chrono::Utc::now().timestamp() as u64));
+            self.republish(channel, DLX_EXCHANGE, DLX_ROUTING_KEY, delivery, headers).await
+        } else {
+            headers.insert(RETRY_COUNT_HEADER.into(), AMQPValue::LongLongInt(retries + 1));
+            self.republish(channel, "", RETRY_QUEUE, delivery, headers).await
+        };
+        match res {
+            Ok(()) => Ok(delivery.ack(BasicAckOptions::default()).await?),
+            Err(e) => {
+                // Without a confirmed copy the delivery goes back to the queue rather than being lost
+                delivery.nack(BasicNackOptions { requeue: true, ..BasicNackOptions::default() }).await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn process(&self, channel: &Channel, delivery: Delivery, event: DomainEvent) {
+        let res = match self.handle(event).await {
+            Ok(()) => delivery.ack(BasicAckOptions::default()).await.map_err(PublishError::from),
+            Err(e) => {
+                tracing::warn!("Failed to handle event {:?}", e);
+                self.retry_or_reject(channel, &delivery, e.to_string()).await
+            }
+        };
+        if let Err(e) = res {
+            tracing::warn!("Failed to settle delivery {:?}", e);
+        }
+    }
+
+    /// Worker which handles events of the authors routed to it one at a time, so events of an author keep their order
+    async fn run_worker(&self, channel: &Channel, mut deliveries: mpsc::Receiver<(Delivery, DomainEvent)>) {
+        while let Some((delivery, event)) = deliveries.recv().await {
+            self.process(channel, delivery, event).await;
+        }
+    }
+
+    /// Consumes until the connection drops
+    async fn consume(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let (channel, mut consumer) = self.init_consumer().await?;
+        tracing::info!("Consumer started with prefetch {} and {} workers", self.config.prefetch, self.config.workers);
+        let (senders, receivers): (Vec<_>, Vec<_>) = (0..self.config.workers)
+            .map(|_| mpsc::channel::<(Delivery, DomainEvent)>(self.config.prefetch as usize))
+            .unzip();
+        let dispatch = async move {
+            while let Some(delivery) = consumer.next().await {
+                let delivery = delivery?;
+                let event: DomainEvent = match serde_json::from_slice(&delivery.data) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        // A payload which cannot be parsed never will be, so it skips the retries
+                        tracing::error!("Dead-lettering malformed event {:?}", e);
+                        delivery.nack(BasicNackOptions { requeue: false, ..BasicNackOptions::default() }).await?;
+                        continue;
+                    }
+                };
+                let mut hasher = DefaultHasher::new();
+                event.user_id().hash(&mut hasher);
+                let worker = (hasher.finish() % senders.len() as u64) as usize;
+                if senders[worker].send((delivery, event)).await.is_err() {
+                    break;
+                }
+            }
+            // Dropping the senders lets the workers finish what they got
+            Ok::<(), deadpool_lapin::lapin::Error>(())
+        };
+        let workers = futures::future::join_all(receivers.into_iter().map(|deliveries| self.run_worker(&channel, deliveries)));
+        let (res, _) = tokio::join!(dispatch, workers);
+        Ok(res?)
+    }
 }
 
 #[async_trait]
-impl <F> FollowersService for FollowersServiceImpl<F>
+impl <F, P> FollowersService for FollowersServiceImpl<F, P>
 where 
-    F: FriendRepository + Send + Sync {
+    F: FriendRepository + Send + Sync,
+    P: PostRepository + Send + Sync {
     async fn run_consumer(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let mut consumer = self.init_consumer().await?;
-        tracing::info!("Consumer started...");
-        while let Some(delivery) = consumer.next().await {            
-            let delivery = delivery?;
-            let event: DomainEvent = serde_json::from_slice(&delivery.data)?;
-            tracing::info!("Incoming event: {:?}", event);
-            let user_id = event.user_id();
-            match self.fetch_followers(*user_id).await {
-                Ok(followers) => {
-                    self.event_bus.publish(FollowerEvent {                        
-                        domain_event: event,
-                        followers,
-                    }).await;
-                },
-                Err(e) => tracing::warn!("Failed to fetch followers {:?}", e)
-            };    
-            delivery.ack(BasicAckOptions::default()).await?;
+        let mut backoff = self.config.reconnect_delay;
+        loop {
+            let started = std::time::Instant::now();
+            match self.consume().await {
+                Ok(()) => tracing::warn!("Consumer stream ended, reconnecting"),
+                Err(e) => tracing::error!("RabbitMQ Consumer error: {:?}", e)
+            }
+            // A consumer which ran for a while had a healthy connection, so the backoff starts over
+            if started.elapsed() > self.config.max_reconnect_delay {
+                backoff = self.config.reconnect_delay;
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(self.config.max_reconnect_delay);
+        }
+    }
+
+    async fn celebrity_changed(&self, author_id: Uuid) -> Result<(), FollowersServiceError> {
+        let followers = self.fetch_followers(author_id).await?;
+        self.event_bus.celebrity_changed(&author_id, &followers).await?;
+        Ok(())
+    }
+
+    async fn handle(&self, event: DomainEvent) -> Result<(), FollowersServiceError> {
+        tracing::info!("Incoming event: {:?}", event);
+        if let DomainEvent::CommentCreated { user_id, post_author_id, .. } = &event {
+            let recipients = if user_id != post_author_id { vec!(*post_author_id) } else { vec!() };
+            self.event_bus.publish(FollowerEvent::new(event, recipients)).await?;
+            return Ok(());
+        }
+        let Some(event) = self.current_event(event).await? else {
+            return Ok(());
+        };
+        if let DomainEvent::PostCreated { user_id, post } | DomainEvent::PostUpdated { user_id, post } = &event {
+            self.notify_mentions(*user_id, post).await;
         }
+        let user_id = *event.user_id();
+        if self.is_fanned_out_on_read(&event).await? {
+            self.event_bus.publish(FollowerEvent { 
+                domain_event: event, 
+                followers: vec!(), 
+                fanned_out_on_read: true 
+            }).await?;
+            return Ok(());
+        }
+        let (followers, excluded) = match &event {
+            DomainEvent::PostCreated { post, .. } => (self.fetch_audience(user_id, post.visibility).await?, vec!()),
+            DomainEvent::PostUpdated { post, .. } => self.fetch_restricted(user_id, post).await?,
+            _ => (self.fetch_followers(user_id).await?, vec!())
+        };
+        if let DomainEvent::PostUpdated { post, .. } = &event && !excluded.is_empty() {
+            // Followers who lost access to the post get it removed from feeds
+            self.event_bus.publish(FollowerEvent::new(DomainEvent::PostDeleted { user_id, post_id: post.id }, excluded)).await?;
+        }
+        self.event_bus.publish(FollowerEvent::new(event, followers)).await?;
         Ok(())
     }
-}
\ No newline at end of file
+}
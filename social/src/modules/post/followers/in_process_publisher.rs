@@ -0,0 +1,21 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use crate::modules::post::{event::{DomainEvent, EventPublisher}, followers::followers_service::FollowersService};
+
+/// Hands events straight to the followers service instead of the broker, for the dev profile
+pub struct InProcessPublisher {
+    followers_service: Arc<dyn FollowersService + Send + Sync>,
+}
+
+impl InProcessPublisher {
+    pub fn new(followers_service: Arc<dyn FollowersService + Send + Sync>) -> Self {
+        InProcessPublisher { followers_service }
+    }
+}
+
+#[async_trait]
+impl EventPublisher for InProcessPublisher {
+    async fn publish(&self, event: &DomainEvent) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(self.followers_service.handle(event.clone()).await?)
+    }
+}
@@ -3,4 +3,5 @@ mod follower_event_bus;
 mod caching_listener;
 pub mod followers_service;
 pub mod async_handler;
-pub mod service_provider;
\ No newline at end of file
+pub mod service_provider;
+pub mod in_process_publisher;
\ No newline at end of file
@@ -1,20 +1,59 @@
-use std::sync::Arc;
+use std::{env, sync::Arc, time::Duration};
 use fred::prelude;
 use deadpool_postgres;
-use crate::modules::{common::ws::ws_manager::WebSocketManager, friend::repository::FriendRepositoryImpl, post::{followers::{async_notifier::AsyncNotifier, caching_listener::CachingPostListener, follower_event_bus::FollowerEventListener, followers_service::{FollowersService, FollowersServiceImpl}}, post_cache::PostCacheImpl}}; 
+use crate::modules::{common::ws::ws_manager::WebSocketManager, friend::{in_memory_repository::InMemoryFriendRepository, repository::FriendRepositoryImpl}, post::{followers::{async_notifier::AsyncNotifier, caching_listener::CachingPostListener, follower_event_bus::FollowerEventListener, followers_service::{FollowersService, FollowersServiceImpl}}, in_memory_post_cache::InMemoryPostCache, in_memory_repository::InMemoryPostRepository, post_cache::{FeedLimits, PostCacheImpl}, repository::PostRepositoryImpl}};
 
+#[derive(Clone, Copy, Debug)]
+pub struct ConsumerConfig {
+    /// Unacknowledged deliveries the broker may push ahead of processing
+    pub prefetch: u16,
+    /// Events are spread over workers by author, so events of one author are handled in order.
+    /// A retried event comes back after newer ones, which is why handlers check the current state of the post
+    pub workers: usize,
+    pub max_retries: u32,
+    pub retry_delay: Duration,
+    pub reconnect_delay: Duration,
+    pub max_reconnect_delay: Duration,
+}
 
-pub fn create_service(pool: Arc<deadpool_postgres::Pool>, redis: Arc<prelude::Pool>, rabbitmq: Arc<deadpool_lapin::Pool>, ws_manager: Arc<WebSocketManager>, exchange: String) 
+impl ConsumerConfig {
+    pub fn from_env() -> Self {
+        ConsumerConfig {
+            prefetch: env::var("RABBITMQ_CONSUMER_PREFETCH").ok().and_then(|v| v.parse().ok()).unwrap_or(32).max(1),
+            workers: env::var("RABBITMQ_CONSUMER_WORKERS").ok().and_then(|v| v.parse().ok()).unwrap_or(8).max(1),
+            max_retries: env::var("RABBITMQ_CONSUMER_MAX_RETRIES").ok().and_then(|v| v.parse().ok()).unwrap_or(5),
+            retry_delay: Duration::from_millis(env::var("RABBITMQ_CONSUMER_RETRY_DELAY_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(5000)),
+            reconnect_delay: Duration::from_millis(env::var("RABBITMQ_RECONNECT_DELAY_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(1000)),
+            max_reconnect_delay: Duration::from_millis(env::var("RABBITMQ_MAX_RECONNECT_DELAY_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(30000)),
+        }
+    }
+}
+
+pub fn create_service(pool: Arc<deadpool_postgres::Pool>, redis: Arc<prelude::Pool>, rabbitmq: Arc<deadpool_lapin::Pool>, ws_manager: Arc<WebSocketManager>, exchange: String, celebrity_threshold: u64, feed_limits: FeedLimits, config: ConsumerConfig) 
     -> Arc<dyn FollowersService + Send + Sync> {        
     let listeners: Vec<Arc<dyn FollowerEventListener + Send + Sync>> = vec!(        
-        Arc::new(CachingPostListener::new(PostCacheImpl::new(Arc::clone(&redis)))),
+        Arc::new(CachingPostListener::new(PostCacheImpl::new(Arc::clone(&redis), feed_limits, false))),
         Arc::new(AsyncNotifier::new(ws_manager))
     );
     let followers_service = FollowersServiceImpl::new(
-        FriendRepositoryImpl::new(pool),                
+        FriendRepositoryImpl::new(Arc::clone(&pool)),                
+        PostRepositoryImpl::new(pool),
         listeners,
         rabbitmq,
-        exchange
+        exchange,
+        celebrity_threshold,
+        config
     );    
     Arc::new(followers_service)
-}
\ No newline at end of file
+}
+
+/// Followers service of the dev profile over the in-memory stores shared with the post service.
+/// Events reach it through `handle`, the broker consumer is not started
+pub fn create_in_memory_service(friends: InMemoryFriendRepository, posts: InMemoryPostRepository, cache: InMemoryPostCache, rabbitmq: Arc<deadpool_lapin::Pool>, ws_manager: Arc<WebSocketManager>, exchange: String, celebrity_threshold: u64, config: ConsumerConfig) 
+    -> Arc<dyn FollowersService + Send + Sync> {
+    let listeners: Vec<Arc<dyn FollowerEventListener + Send + Sync>> = vec!(
+        Arc::new(CachingPostListener::new(cache)),
+        Arc::new(AsyncNotifier::new(ws_manager))
+    );
+    Arc::new(FollowersServiceImpl::new(friends, posts, listeners, rabbitmq, exchange, celebrity_threshold, config))
+}
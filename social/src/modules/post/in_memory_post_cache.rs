@@ -0,0 +1,337 @@
+use std::{cmp::Ordering, collections::HashMap, sync::{Arc, Mutex}, time::{Duration, Instant}};
+use uuid::Uuid;
+use fred::error::Error;
+use async_trait::async_trait;
+use crate::modules::post::{cursor::{FeedCursor, FeedPage}, model::Post};
+use crate::modules::post::post_cache::{AUTHOR_MARK_TTL_SECONDS, AUTHOR_TIMELINE_SIZE, AUTHOR_TIMELINE_TTL_SECONDS, AuthorCache, DEFAULT_FEED_SIZE, FEED_LOCK_TTL_SECONDS, FEED_MARK_TTL_SECONDS, FeedCache, FeedLimits, MarkCache, POST_CACHE_TTL_SECONDS, UserPostCache};
+
+/// A value with an optional deadline, like a Redis key with or without a TTL
+struct Expiring<T> {
+    value: T,
+    expires_at: Option<Instant>,
+}
+
+impl <T> Expiring<T> {
+    fn new(value: T, ttl_seconds: Option<i64>) -> Self {
+        Expiring { value, expires_at: ttl_seconds.map(deadline) }
+    }
+
+    fn is_live(&self) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > Instant::now())
+    }
+}
+
+fn deadline(ttl_seconds: i64) -> Instant {
+    Instant::now() + Duration::from_secs(ttl_seconds.max(0) as u64)
+}
+
+/// Returns the live value of the key, dropping it if it has expired
+fn live<T>(map: &mut HashMap<Uuid, Expiring<T>>, key: &Uuid) -> Option<&mut Expiring<T>> {
+    if map.get(key).is_some_and(|entry| !entry.is_live()) {
+        map.remove(key);
+    }
+    map.get_mut(key)
+}
+
+/// Sorted set with the ordering of a Redis zset: by score, ties by member
+#[derive(Default)]
+struct SortedSet {
+    scores: HashMap<String, f64>,
+}
+
+impl SortedSet {
+    fn add(&mut self, score: f64, member: String) {
+        self.scores.insert(member, score);
+    }
+
+    fn remove(&mut self, member: &str) {
+        self.scores.remove(member);
+    }
+
+    fn len(&self) -> u64 {
+        self.scores.len() as u64
+    }
+
+    /// Members from the highest rank down, as ZREVRANGE returns them
+    fn descending(&self) -> Vec<(f64, String)> {
+        let mut members: Vec<(f64, String)> = self.scores.iter().map(|(member, score)| (*score, member.clone())).collect();
+        members.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal).then_with(|| b.1.cmp(&a.1)));
+        members
+    }
+
+    /// Keeps the `size` highest ranked members, as ZREMRANGEBYRANK 0 -size-1 does
+    fn trim(&mut self, size: u64) {
+        for (_, member) in self.descending().into_iter().skip(size as usize) {
+            self.scores.remove(&member);
+        }
+    }
+
+    fn page(&self, page: FeedPage) -> Vec<String> {
+        let members = self.descending();
+        match page {
+            FeedPage::Offset { limit, offset } => members.into_iter()
+                .skip(offset.unwrap_or(0) as usize)
+                .take(limit.unwrap_or(DEFAULT_FEED_SIZE) as usize)
+                .map(|(_, member)| member)
+                .collect(),
+            FeedPage::After { limit, cursor } => {
+                // Same as the Redis cache: members at the cursor score and at or above its id were served
+                let score = cursor.score();
+                let cursor_id = cursor.post_id.to_string();
+                members.into_iter()
+                    .filter(|(member_score, member)| *member_score < score || (*member_score == score && *member < cursor_id))
+                    .take(limit.unwrap_or(DEFAULT_FEED_SIZE) as usize)
+                    .map(|(_, member)| member)
+                    .collect()
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct CacheState {
+    feeds: HashMap<Uuid, Expiring<SortedSet>>,
+    marks: HashMap<Uuid, Expiring<()>>,
+    locks: HashMap<Uuid, Expiring<String>>,
+    celebrities: HashMap<Uuid, Expiring<Vec<Uuid>>>,
+    posts: HashMap<Uuid, Expiring<Post>>,
+    authors: HashMap<Uuid, Expiring<SortedSet>>,
+    author_marks: HashMap<Uuid, Expiring<()>>,
+}
+
+/// Process-local `PostCache` with the semantics of `PostCacheImpl`: bounded sorted feeds,
+/// TTLs on marks, locks and bodies, and fan-out only into feeds which exist.
+/// Clones share the same state
+#[derive(Clone)]
+pub struct InMemoryPostCache {
+    state: Arc<Mutex<CacheState>>,
+    feed_limits: FeedLimits,
+}
+
+impl InMemoryPostCache {
+    pub fn new(feed_limits: FeedLimits) -> Self {
+        InMemoryPostCache {
+            state: Arc::new(Mutex::new(CacheState::default())),
+            feed_limits
+        }
+    }
+}
+
+fn score(post: &Post) -> f64 {
+    FeedCursor::of(post).score()
+}
+
+#[async_trait]
+impl FeedCache for InMemoryPostCache {
+
+    async fn process_save(&self, followers_ids: &Vec<Uuid>, post: &Post) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        for follower_id in followers_ids {
+            if let Some(feed) = live(&mut state.feeds, follower_id) {
+                feed.value.add(score(post), post.id.to_string());
+                feed.value.trim(self.feed_limits.size);
+            }
+        }
+        Ok(())
+    }
+
+    async fn process_delete(&self, followers_ids: &Vec<Uuid>, post_id: &Uuid) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        for follower_id in followers_ids {
+            if let Some(feed) = live(&mut state.feeds, follower_id) {
+                feed.value.remove(&post_id.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_user_feed(&self, user_id: Uuid, page: FeedPage) -> Result<Vec<String>, Error> {
+        let mut state = self.state.lock().unwrap();
+        Ok(live(&mut state.feeds, &user_id).map(|feed| feed.value.page(page)).unwrap_or_default())
+    }
+
+    async fn save_user_feed(&self, user_id: Uuid, posts: &Vec<Post>) -> Result<(), Error> {
+        if posts.is_empty() {
+            return Ok(());
+        }
+        let mut state = self.state.lock().unwrap();
+        if live(&mut state.feeds, &user_id).is_none() {
+            state.feeds.insert(user_id, Expiring::new(SortedSet::default(), None));
+        }
+        let feed = state.feeds.get_mut(&user_id).unwrap();
+        for post in posts {
+            feed.value.add(score(post), post.id.to_string());
+        }
+        feed.value.trim(self.feed_limits.size);
+        feed.expires_at = Some(deadline(self.feed_limits.ttl_seconds));
+        Ok(())
+    }
+
+    async fn remove_from_user_feed(&self, user_id: Uuid, post_ids: &Vec<String>) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(feed) = live(&mut state.feeds, &user_id) {
+            for post_id in post_ids {
+                feed.value.remove(post_id);
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete_user_feed(&self, user_id: Uuid) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        state.feeds.remove(&user_id);
+        state.marks.remove(&user_id);
+        Ok(())
+    }
+
+    async fn is_user_feed_full(&self, user_id: Uuid) -> Result<bool, Error> {
+        let mut state = self.state.lock().unwrap();
+        let size = live(&mut state.feeds, &user_id).map(|feed| feed.value.len()).unwrap_or(0);
+        Ok(size >= self.feed_limits.size)
+    }
+
+    async fn get_followed_celebrities(&self, user_id: Uuid) -> Result<Option<Vec<Uuid>>, Error> {
+        let mut state = self.state.lock().unwrap();
+        Ok(live(&mut state.celebrities, &user_id).map(|authors| authors.value.clone()))
+    }
+
+    async fn save_followed_celebrities(&self, user_id: Uuid, authors: &Vec<Uuid>) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        state.celebrities.insert(user_id, Expiring::new(authors.clone(), Some(FEED_MARK_TTL_SECONDS)));
+        Ok(())
+    }
+
+    async fn delete_followed_celebrities(&self, user_id: Uuid) -> Result<(), Error> {
+        self.state.lock().unwrap().celebrities.remove(&user_id);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserPostCache for InMemoryPostCache {
+
+    async fn get_posts_by_ids(&self, ids: Vec<String>) -> Result<Vec<Post>, Error> {
+        let mut state = self.state.lock().unwrap();
+        Ok(ids.iter()
+            .filter_map(|id| Uuid::parse_str(id).ok())
+            .filter_map(|post_id| live(&mut state.posts, &post_id).map(|post| post.value.clone()))
+            .collect())
+    }
+
+    async fn save_post(&self, post: &Post) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        state.posts.insert(post.id, Expiring::new(post.clone(), Some(POST_CACHE_TTL_SECONDS)));
+        Ok(())
+    }
+
+    async fn save_posts(&self, posts: &Vec<Post>) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        for post in posts {
+            state.posts.insert(post.id, Expiring::new(post.clone(), Some(POST_CACHE_TTL_SECONDS)));
+        }
+        Ok(())
+    }
+
+    async fn delete_post(&self, post_id: &Uuid) -> Result<(), Error> {
+        self.state.lock().unwrap().posts.remove(post_id);
+        Ok(())
+    }
+
+    async fn get_post(&self, post_id: &Uuid) -> Result<Option<Post>, Error> {
+        let mut state = self.state.lock().unwrap();
+        Ok(live(&mut state.posts, post_id).map(|post| post.value.clone()))
+    }
+}
+
+#[async_trait]
+impl MarkCache for InMemoryPostCache {
+
+    async fn mark_feed_exists(&self, user_id: Uuid) -> Result<(), Error> {
+        self.state.lock().unwrap().marks.insert(user_id, Expiring::new((), Some(FEED_MARK_TTL_SECONDS)));
+        Ok(())
+    }
+
+    async fn check_feed_exists(&self, user_id: Uuid) -> Result<bool, Error> {
+        let mut state = self.state.lock().unwrap();
+        Ok(live(&mut state.marks, &user_id).is_some())
+    }
+
+    async fn feed_mark_ttl(&self, user_id: Uuid) -> Result<Option<i64>, Error> {
+        let mut state = self.state.lock().unwrap();
+        Ok(live(&mut state.marks, &user_id).map(|mark| match mark.expires_at {
+            Some(expires_at) => expires_at.saturating_duration_since(Instant::now()).as_secs() as i64,
+            None => i64::MAX
+        }))
+    }
+
+    async fn lock_feed(&self, user_id: Uuid, token: &String) -> Result<bool, Error> {
+        let mut state = self.state.lock().unwrap();
+        if live(&mut state.locks, &user_id).is_some() {
+            return Ok(false);
+        }
+        state.locks.insert(user_id, Expiring::new(token.clone(), Some(FEED_LOCK_TTL_SECONDS)));
+        Ok(true)
+    }
+
+    async fn unlock_feed(&self, user_id: Uuid, token: &String) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        if live(&mut state.locks, &user_id).is_some_and(|lock| lock.value == *token) {
+            state.locks.remove(&user_id);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AuthorCache for InMemoryPostCache {
+
+    async fn save_author_post(&self, post: &Post) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        if live(&mut state.authors, &post.author_user_id).is_none() {
+            state.authors.insert(post.author_user_id, Expiring::new(SortedSet::default(), None));
+        }
+        let timeline = state.authors.get_mut(&post.author_user_id).unwrap();
+        timeline.value.add(score(post), post.id.to_string());
+        timeline.value.trim(AUTHOR_TIMELINE_SIZE);
+        timeline.expires_at = Some(deadline(AUTHOR_TIMELINE_TTL_SECONDS));
+        Ok(())
+    }
+
+    async fn delete_author_post(&self, author_id: &Uuid, post_id: &Uuid) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(timeline) = live(&mut state.authors, author_id) {
+            timeline.value.remove(&post_id.to_string());
+        }
+        Ok(())
+    }
+
+    async fn get_author_timeline(&self, author_id: Uuid, page: FeedPage) -> Result<Vec<String>, Error> {
+        let mut state = self.state.lock().unwrap();
+        Ok(live(&mut state.authors, &author_id).map(|timeline| timeline.value.page(page)).unwrap_or_default())
+    }
+
+    async fn save_author_timeline(&self, author_id: Uuid, posts: &Vec<Post>) -> Result<(), Error> {
+        let mut timeline = SortedSet::default();
+        for post in posts {
+            timeline.add(score(post), post.id.to_string());
+        }
+        timeline.trim(AUTHOR_TIMELINE_SIZE);
+        self.state.lock().unwrap().authors.insert(author_id, Expiring::new(timeline, Some(AUTHOR_TIMELINE_TTL_SECONDS)));
+        Ok(())
+    }
+
+    async fn author_timeline_size(&self, author_id: Uuid) -> Result<u64, Error> {
+        let mut state = self.state.lock().unwrap();
+        Ok(live(&mut state.authors, &author_id).map(|timeline| timeline.value.len()).unwrap_or(0))
+    }
+
+    async fn mark_author_timeline_exists(&self, author_id: Uuid) -> Result<(), Error> {
+        self.state.lock().unwrap().author_marks.insert(author_id, Expiring::new((), Some(AUTHOR_MARK_TTL_SECONDS)));
+        Ok(())
+    }
+
+    async fn check_author_timeline_exists(&self, author_id: Uuid) -> Result<bool, Error> {
+        let mut state = self.state.lock().unwrap();
+        Ok(live(&mut state.author_marks, &author_id).is_some())
+    }
+}
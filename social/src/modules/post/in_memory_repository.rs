@@ -0,0 +1,289 @@
+use std::{cmp::Ordering, collections::HashMap, sync::{Arc, RwLock}};
+use uuid::Uuid;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crate::modules::friend::in_memory_repository::InMemoryFriendRepository;
+use crate::modules::post::{cursor::FeedPage, links::{parse_mentions, parse_tags}, model::{Post, PostRevision, Visibility}};
+use crate::modules::post::repository::{PostRepository, PostRepositoryError};
+
+/// A `posts` row with its tag, mention and revision rows
+struct StoredPost {
+    post: Post,
+    updated_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
+    hidden_at: Option<DateTime<Utc>>,
+    tags: Vec<String>,
+    /// Mentioned users with the notification flag
+    mentions: Vec<(Uuid, bool)>,
+    revisions: Vec<PostRevision>,
+}
+
+impl StoredPost {
+    fn is_removed(&self) -> bool {
+        self.deleted_at.is_some() || self.hidden_at.is_some()
+    }
+
+    /// Same as `to_post`: the post counts as edited once `updated_at` moves past `created_at`
+    fn to_post(&self) -> Post {
+        Post {
+            edited_at: Some(self.updated_at).filter(|updated_at| *updated_at != self.post.created_at),
+            ..self.post.clone()
+        }
+    }
+
+    /// Mirrors `sync_links`: mentions kept from the previous version keep their notification state
+    fn sync_links(&mut self) {
+        self.tags = parse_tags(&self.post.text);
+        let previous = std::mem::take(&mut self.mentions);
+        self.mentions = parse_mentions(&self.post.text).into_iter()
+            .map(|user_id| (user_id, previous.iter().any(|(mentioned, notified)| *mentioned == user_id && *notified)))
+            .collect();
+    }
+}
+
+/// `PostRepository` over process-local rows, for the dev profile.
+/// Follows the SQL of `PostRepositoryImpl`, user blocks are not modelled.
+/// Clones share the same rows
+#[derive(Clone)]
+pub struct InMemoryPostRepository {
+    friends: InMemoryFriendRepository,
+    posts: Arc<RwLock<HashMap<Uuid, StoredPost>>>,
+}
+
+impl InMemoryPostRepository {
+    pub fn new(friends: InMemoryFriendRepository) -> Self {
+        InMemoryPostRepository {
+            friends,
+            posts: Arc::new(RwLock::new(HashMap::new()))
+        }
+    }
+
+    /// Same as the `NOT_REMOVED` predicate
+    fn is_removed(posts: &HashMap<Uuid, StoredPost>, stored: &StoredPost) -> bool {
+        stored.is_removed() || stored.post.repost_of
+            .and_then(|original_id| posts.get(&original_id))
+            .is_some_and(|original| original.is_removed())
+    }
+
+    /// Same as the `visible_to` predicate
+    fn is_visible(&self, post: &Post, viewer_id: Uuid) -> bool {
+        post.author_user_id == viewer_id
+            || post.visibility == Visibility::Public
+            || (post.visibility == Visibility::Friends
+                && self.friends.is_linked(post.author_user_id, viewer_id)
+                && self.friends.is_linked(viewer_id, post.author_user_id))
+    }
+
+    fn insert(&self, id: Uuid, user_id: Uuid, text: String, visibility: Visibility, repost_of: Option<Uuid>, original_author_id: Option<Uuid>) -> Post {
+        let now = Utc::now();
+        let mut stored = StoredPost {
+            post: Post {
+                id,
+                text,
+                author_user_id: user_id,
+                created_at: now,
+                edited_at: None,
+                comments_count: 0,
+                repost_of,
+                original_author_user_id: original_author_id,
+                visibility
+            },
+            updated_at: now,
+            deleted_at: None,
+            hidden_at: None,
+            tags: vec!(),
+            mentions: vec!(),
+            revisions: vec!()
+        };
+        stored.sync_links();
+        let post = stored.to_post();
+        self.posts.write().unwrap().insert(post.id, stored);
+        post
+    }
+
+    /// Visible, not removed posts matching the filter, paged in the `(created_at, id)` descending order
+    fn page<F>(&self, viewer_id: Uuid, page: FeedPage, filter: F) -> Vec<Post>
+    where
+        F: Fn(&StoredPost) -> bool {
+        let posts = self.posts.read().unwrap();
+        let mut found: Vec<Post> = posts.values()
+            .filter(|stored| filter(stored) && !Self::is_removed(&posts, stored) && self.is_visible(&stored.post, viewer_id))
+            .map(StoredPost::to_post)
+            .collect();
+        found.sort_by(|a, b| match b.created_at.cmp(&a.created_at) {
+            Ordering::Equal => b.id.cmp(&a.id),
+            ordering => ordering
+        });
+        match page {
+            FeedPage::Offset { limit, offset } => found.into_iter()
+                .skip(offset.unwrap_or(0) as usize)
+                .take(limit.map(|v| v as usize).unwrap_or(usize::MAX))
+                .collect(),
+            FeedPage::After { limit, cursor } => found.into_iter()
+                .filter(|post| (post.created_at, post.id) < (cursor.timestamp, cursor.post_id))
+                .take(limit.map(|v| v as usize).unwrap_or(usize::MAX))
+                .collect()
+        }
+    }
+}
+
+#[async_trait]
+impl PostRepository for InMemoryPostRepository {
+
+    async fn create(&self, user_id: Uuid, text: &String, visibility: Visibility, post_id: Option<Uuid>) -> Result<Post, PostRepositoryError> {
+        if let Some(id) = post_id {
+            let posts = self.posts.read().unwrap();
+            if let Some(existing) = posts.get(&id) {
+                return match existing.post.author_user_id == user_id {
+                    true => Ok(existing.to_post()),
+                    false => Err(PostRepositoryError::IllegalState(format!("Post {} belongs to another user", id)))
+                };
+            }
+        }
+        Ok(self.insert(post_id.unwrap_or_else(Uuid::new_v4), user_id, text.clone(), visibility, None, None))
+    }
+
+    async fn repost(&self, user_id: Uuid, post_id: Uuid, text: &Option<String>) -> Result<Post, PostRepositoryError> {
+        let (repost_of, original_author_id) = {
+            let posts = self.posts.read().unwrap();
+            let original = posts.get(&post_id)
+                .filter(|stored| !stored.is_removed() && stored.post.is_public())
+                .ok_or(PostRepositoryError::NotFound(format!("Post {}", post_id)))?;
+            (
+                original.post.repost_of.unwrap_or(post_id),
+                original.post.original_author_user_id.unwrap_or(original.post.author_user_id)
+            )
+        };
+        if original_author_id == user_id {
+            return Err(PostRepositoryError::IllegalState("Cannot repost own post".to_string()));
+        }
+        Ok(self.insert(Uuid::new_v4(), user_id, text.clone().unwrap_or_default(), Visibility::Public, Some(repost_of), Some(original_author_id)))
+    }
+
+    async fn update(&self, user_id: Uuid, post_id: Uuid, text: &String, visibility: Option<Visibility>) -> Result<Post, PostRepositoryError> {
+        let mut posts = self.posts.write().unwrap();
+        let stored = posts.get_mut(&post_id)
+            .filter(|stored| stored.post.author_user_id == user_id && !stored.is_removed())
+            .ok_or(PostRepositoryError::NotFound(format!("Post {}", post_id)))?;
+        // The version being overwritten is kept as a revision
+        stored.revisions.push(PostRevision {
+            text: stored.post.text.clone(),
+            visibility: stored.post.visibility,
+            timestamp: stored.updated_at
+        });
+        stored.post.text = text.clone();
+        stored.post.visibility = visibility.unwrap_or(stored.post.visibility);
+        stored.updated_at = Utc::now();
+        stored.sync_links();
+        Ok(Post { edited_at: Some(stored.updated_at), ..stored.post.clone() })
+    }
+
+    async fn delete(&self, user_id: Uuid, post_id: Uuid) -> Result<(), PostRepositoryError> {
+        let mut posts = self.posts.write().unwrap();
+        match posts.get_mut(&post_id) {
+            Some(stored) if stored.post.author_user_id == user_id && stored.deleted_at.is_none() => {
+                stored.deleted_at = Some(Utc::now());
+                stored.post.comments_count = 0;
+                Ok(())
+            },
+            _ => Err(PostRepositoryError::Internal("Not updated".to_string()))
+        }
+    }
+
+    async fn restore(&self, user_id: Uuid, post_id: Uuid, window_days: i32) -> Result<Post, PostRepositoryError> {
+        let window_start = Utc::now() - chrono::Duration::days(window_days as i64);
+        let mut posts = self.posts.write().unwrap();
+        let stored = posts.get_mut(&post_id)
+            .filter(|stored| stored.post.author_user_id == user_id && stored.hidden_at.is_none())
+            .filter(|stored| stored.deleted_at.is_some_and(|deleted_at| deleted_at > window_start))
+            .ok_or(PostRepositoryError::NotFound(format!("Deleted post {}", post_id)))?;
+        stored.deleted_at = None;
+        Ok(stored.to_post())
+    }
+
+    async fn set_hidden(&self, post_id: Uuid, hidden: bool) -> Result<Post, PostRepositoryError> {
+        let mut posts = self.posts.write().unwrap();
+        let stored = posts.get_mut(&post_id)
+            .filter(|stored| stored.deleted_at.is_none() && stored.hidden_at.is_some() != hidden)
+            .ok_or(PostRepositoryError::NotFound(format!("Post {}", post_id)))?;
+        stored.hidden_at = if hidden { Some(Utc::now()) } else { None };
+        Ok(stored.to_post())
+    }
+
+    async fn get(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Post, PostRepositoryError> {
+        let posts = self.posts.read().unwrap();
+        posts.get(&post_id)
+            .filter(|stored| !Self::is_removed(&posts, stored) && self.is_visible(&stored.post, viewer_id))
+            .map(StoredPost::to_post)
+            .ok_or(PostRepositoryError::NotFound(format!("Post {}", post_id)))
+    }
+
+    async fn get_many(&self, viewer_id: Uuid, post_ids: &Vec<Uuid>) -> Result<Vec<Post>, PostRepositoryError> {
+        let posts = self.posts.read().unwrap();
+        Ok(post_ids.iter()
+            .filter_map(|post_id| posts.get(post_id))
+            .filter(|stored| !Self::is_removed(&posts, stored) && self.is_visible(&stored.post, viewer_id))
+            .map(StoredPost::to_post)
+            .collect())
+    }
+
+    async fn reposts(&self, post_id: Uuid) -> Result<Vec<Post>, PostRepositoryError> {
+        Ok(self.posts.read().unwrap().values()
+            .filter(|stored| stored.post.repost_of == Some(post_id) && stored.deleted_at.is_none())
+            .map(StoredPost::to_post)
+            .collect())
+    }
+
+    async fn revisions(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Vec<PostRevision>, PostRepositoryError> {
+        let posts = self.posts.read().unwrap();
+        let stored = posts.get(&post_id)
+            .filter(|stored| !Self::is_removed(&posts, stored) && stored.post.author_user_id == viewer_id)
+            .ok_or(PostRepositoryError::NotFound(format!("Post {}", post_id)))?;
+        let mut revisions = stored.revisions.clone();
+        revisions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        Ok(revisions)
+    }
+
+    async fn feed(&self, user_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostRepositoryError> {
+        let friend_ids = self.friends.friend_ids(user_id);
+        Ok(self.page(user_id, page, |stored| friend_ids.contains(&stored.post.author_user_id)))
+    }
+
+    async fn tag_feed(&self, viewer_id: Uuid, tag: &String, page: FeedPage) -> Result<Vec<Post>, PostRepositoryError> {
+        Ok(self.page(viewer_id, page, |stored| stored.tags.contains(tag)))
+    }
+
+    async fn author_feed(&self, viewer_id: Uuid, author_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostRepositoryError> {
+        Ok(self.page(viewer_id, page, |stored| stored.post.author_user_id == author_id))
+    }
+
+    async fn claim_mentions(&self, post_id: Uuid) -> Result<Vec<Uuid>, PostRepositoryError> {
+        let mut posts = self.posts.write().unwrap();
+        let removed = match posts.get(&post_id) {
+            Some(stored) => Self::is_removed(&posts, stored),
+            None => return Ok(vec!())
+        };
+        if removed {
+            return Ok(vec!());
+        }
+        let stored = posts.get_mut(&post_id).unwrap();
+        let post = stored.post.clone();
+        let mut claimed = vec!();
+        // Mentions the user may not see stay pending, so they are delivered if the post is opened up later.
+        // Mentions between blocked users stay pending as well
+        for (user_id, notified) in stored.mentions.iter_mut() {
+            if !*notified && *user_id != post.author_user_id && self.is_visible(&post, *user_id)
+                && !self.friends.is_blocked_between(post.author_user_id, *user_id) {
+                *notified = true;
+                claimed.push(*user_id);
+            }
+        }
+        Ok(claimed)
+    }
+
+    async fn followed_celebrities(&self, user_id: Uuid, threshold: i64) -> Result<Vec<Uuid>, PostRepositoryError> {
+        Ok(self.friends.linked_to(user_id).into_iter()
+            .filter(|author_id| self.friends.friend_ids(*author_id).len() as i64 >= threshold)
+            .collect())
+    }
+}
@@ -0,0 +1,69 @@
+use uuid::Uuid;
+use deadpool_postgres::Transaction;
+
+const MAX_TAG_LENGTH: usize = 64;
+const MENTION_LENGTH: usize = 36;
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Normalizes a hashtag to the form it is indexed by: lowercase, without the leading `#`
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag: String = tag.trim_start_matches('#').to_lowercase();
+    if tag.is_empty() || tag.chars().count() > MAX_TAG_LENGTH || !tag.chars().all(is_tag_char) {
+        return None;
+    }
+    Some(tag)
+}
+
+/// Distinct `#hashtags` of the text in order of appearance
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = vec!();
+    for (idx, _) in text.match_indices('#') {
+        let tag: String = text[idx + 1..].chars().take_while(|c| is_tag_char(*c)).collect();
+        if let Some(tag) = normalize_tag(&tag) && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Distinct users mentioned as `@<user_id>` in the text
+pub fn parse_mentions(text: &str) -> Vec<Uuid> {
+    let mut mentions: Vec<Uuid> = vec!();
+    for (idx, _) in text.match_indices('@') {
+        let candidate: String = text[idx + 1..].chars()
+            .take_while(|c| c.is_ascii_hexdigit() || *c == '-')
+            .take(MENTION_LENGTH)
+            .collect();
+        if let Ok(user_id) = Uuid::parse_str(&candidate) && !mentions.contains(&user_id) {
+            mentions.push(user_id);
+        }
+    }
+    mentions
+}
+
+/// Replaces tag and mention links of the post with the ones found in its text.
+/// Mentions kept from the previous version keep their notification state.
+pub async fn sync_links(tx: &Transaction<'_>, post_id: Uuid, text: &str) -> Result<(), tokio_postgres::Error> {
+    let tags = parse_tags(text);
+    let mentions = parse_mentions(text);
+    tx.execute(
+        "DELETE FROM post_tags WHERE post_id = $1 AND NOT (tag = ANY($2))",
+        &[&post_id, &tags]
+    ).await?;
+    tx.execute(
+        "INSERT INTO post_tags (post_id, tag) SELECT $1, UNNEST($2::VARCHAR[]) ON CONFLICT DO NOTHING",
+        &[&post_id, &tags]
+    ).await?;
+    tx.execute(
+        "DELETE FROM post_mentions WHERE post_id = $1 AND NOT (user_id = ANY($2))",
+        &[&post_id, &mentions]
+    ).await?;
+    tx.execute(
+        "INSERT INTO post_mentions (post_id, user_id) SELECT $1, id FROM users WHERE id = ANY($2) ON CONFLICT DO NOTHING",
+        &[&post_id, &mentions]
+    ).await?;
+    Ok(())
+}
@@ -0,0 +1,269 @@
+use std::{collections::{BTreeMap, HashMap}, sync::{Arc, Mutex}, time::{Duration, Instant}};
+use uuid::Uuid;
+use fred::error::Error;
+use async_trait::async_trait;
+use crate::modules::common::metrics::post_cache_metrics::PostCacheMetrics;
+use crate::modules::post::{cursor::FeedPage, model::Post, post_cache::{AuthorCache, FeedCache, MarkCache, UserPostCache}};
+
+struct LocalEntry {
+    post: Post,
+    expires_at: Instant,
+    tick: u64,
+}
+
+/// Entries with the recency order, the lowest tick is the least recently used
+#[derive(Default)]
+struct LruState {
+    entries: HashMap<Uuid, LocalEntry>,
+    order: BTreeMap<u64, Uuid>,
+    tick: u64,
+}
+
+impl LruState {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn remove(&mut self, post_id: &Uuid) {
+        if let Some(entry) = self.entries.remove(post_id) {
+            self.order.remove(&entry.tick);
+        }
+    }
+}
+
+/// Bounded in-process store of hot post bodies, shared by the caches of one instance.
+/// Entries live for a short TTL, which bounds staleness when an invalidation event is lost
+pub struct LocalPostStore {
+    state: Mutex<LruState>,
+    capacity: usize,
+    ttl: Duration,
+    metrics: Arc<PostCacheMetrics>,
+}
+
+impl LocalPostStore {
+    pub fn new(capacity: usize, ttl: Duration, metrics: Arc<PostCacheMetrics>) -> Self {
+        LocalPostStore {
+            state: Mutex::new(LruState::default()),
+            capacity,
+            ttl,
+            metrics
+        }
+    }
+
+    pub fn get(&self, post_id: &Uuid) -> Option<Post> {
+        let mut state = self.state.lock().unwrap();
+        let tick = state.next_tick();
+        let entry = state.entries.get_mut(post_id)?;
+        if entry.expires_at <= Instant::now() {
+            state.remove(post_id);
+            return None;
+        }
+        let old_tick = entry.tick;
+        entry.tick = tick;
+        let post = entry.post.clone();
+        state.order.remove(&old_tick);
+        state.order.insert(tick, *post_id);
+        Some(post)
+    }
+
+    pub fn put(&self, post: &Post) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.remove(&post.id);
+        if state.entries.len() >= self.capacity
+            && let Some((_, oldest)) = state.order.pop_first() {
+            state.entries.remove(&oldest);
+        }
+        let tick = state.next_tick();
+        state.order.insert(tick, post.id);
+        state.entries.insert(post.id, LocalEntry { post: post.clone(), expires_at: Instant::now() + self.ttl, tick });
+    }
+
+    pub fn remove(&self, post_id: &Uuid) {
+        self.state.lock().unwrap().remove(post_id);
+    }
+}
+
+/// Serves post bodies from the in-process store before going to the wrapped cache
+pub struct LocalPostCache<C> {
+    cache: C,
+    store: Arc<LocalPostStore>,
+}
+
+impl <C> LocalPostCache<C> {
+    pub fn new(cache: C, store: Arc<LocalPostStore>) -> Self {
+        LocalPostCache { cache, store }
+    }
+}
+
+#[async_trait]
+impl <C> UserPostCache for LocalPostCache<C>
+where
+    C: UserPostCache + Send + Sync {
+
+    async fn get_posts_by_ids(&self, ids: Vec<String>) -> Result<Vec<Post>, Error> {
+        let mut posts = vec!();
+        let mut missing = vec!();
+        for id in ids {
+            match Uuid::parse_str(&id).ok().and_then(|post_id| self.store.get(&post_id)) {
+                Some(post) => posts.push(post),
+                None => missing.push(id)
+            }
+        }
+        self.store.metrics.record_local(posts.len() as u64, missing.len() as u64);
+        if missing.is_empty() {
+            return Ok(posts);
+        }
+        let requested = missing.len() as u64;
+        let loaded = self.cache.get_posts_by_ids(missing).await?;
+        self.store.metrics.record_redis(loaded.len() as u64, requested - loaded.len() as u64);
+        for post in &loaded {
+            self.store.put(post);
+        }
+        posts.extend(loaded);
+        Ok(posts)
+    }
+
+    async fn save_post(&self, post: &Post) -> Result<(), Error> {
+        self.store.put(post);
+        self.cache.save_post(post).await
+    }
+
+    async fn save_posts(&self, posts: &Vec<Post>) -> Result<(), Error> {
+        for post in posts {
+            self.store.put(post);
+        }
+        self.cache.save_posts(posts).await
+    }
+
+    async fn delete_post(&self, post_id: &Uuid) -> Result<(), Error> {
+        self.store.remove(post_id);
+        self.cache.delete_post(post_id).await
+    }
+
+    async fn get_post(&self, post_id: &Uuid) -> Result<Option<Post>, Error> {
+        if let Some(post) = self.store.get(post_id) {
+            self.store.metrics.record_local(1, 0);
+            return Ok(Some(post));
+        }
+        self.store.metrics.record_local(0, 1);
+        let post = self.cache.get_post(post_id).await?;
+        match &post {
+            Some(post) => {
+                self.store.metrics.record_redis(1, 0);
+                self.store.put(post);
+            },
+            None => self.store.metrics.record_redis(0, 1)
+        }
+        Ok(post)
+    }
+}
+
+#[async_trait]
+impl <C> FeedCache for LocalPostCache<C>
+where
+    C: FeedCache + Send + Sync {
+
+    async fn process_save(&self, followers_ids: &Vec<Uuid>, post: &Post) -> Result<(), Error> {
+        self.cache.process_save(followers_ids, post).await
+    }
+
+    async fn process_delete(&self, followers_ids: &Vec<Uuid>, post_id: &Uuid) -> Result<(), Error> {
+        self.cache.process_delete(followers_ids, post_id).await
+    }
+
+    async fn get_user_feed(&self, user_id: Uuid, page: FeedPage) -> Result<Vec<String>, Error> {
+        self.cache.get_user_feed(user_id, page).await
+    }
+
+    async fn save_user_feed(&self, user_id: Uuid, posts: &Vec<Post>) -> Result<(), Error> {
+        self.cache.save_user_feed(user_id, posts).await
+    }
+
+    async fn remove_from_user_feed(&self, user_id: Uuid, post_ids: &Vec<String>) -> Result<(), Error> {
+        self.cache.remove_from_user_feed(user_id, post_ids).await
+    }
+
+    async fn delete_user_feed(&self, user_id: Uuid) -> Result<(), Error> {
+        self.cache.delete_user_feed(user_id).await
+    }
+
+    async fn is_user_feed_full(&self, user_id: Uuid) -> Result<bool, Error> {
+        self.cache.is_user_feed_full(user_id).await
+    }
+
+    async fn get_followed_celebrities(&self, user_id: Uuid) -> Result<Option<Vec<Uuid>>, Error> {
+        self.cache.get_followed_celebrities(user_id).await
+    }
+
+    async fn save_followed_celebrities(&self, user_id: Uuid, authors: &Vec<Uuid>) -> Result<(), Error> {
+        self.cache.save_followed_celebrities(user_id, authors).await
+    }
+
+    async fn delete_followed_celebrities(&self, user_id: Uuid) -> Result<(), Error> {
+        self.cache.delete_followed_celebrities(user_id).await
+    }
+}
+
+#[async_trait]
+impl <C> MarkCache for LocalPostCache<C>
+where
+    C: MarkCache + Send + Sync {
+
+    async fn mark_feed_exists(&self, user_id: Uuid) -> Result<(), Error> {
+        self.cache.mark_feed_exists(user_id).await
+    }
+
+    async fn check_feed_exists(&self, user_id: Uuid) -> Result<bool, Error> {
+        self.cache.check_feed_exists(user_id).await
+    }
+
+    async fn feed_mark_ttl(&self, user_id: Uuid) -> Result<Option<i64>, Error> {
+        self.cache.feed_mark_ttl(user_id).await
+    }
+
+    async fn lock_feed(&self, user_id: Uuid, token: &String) -> Result<bool, Error> {
+        self.cache.lock_feed(user_id, token).await
+    }
+
+    async fn unlock_feed(&self, user_id: Uuid, token: &String) -> Result<(), Error> {
+        self.cache.unlock_feed(user_id, token).await
+    }
+}
+
+#[async_trait]
+impl <C> AuthorCache for LocalPostCache<C>
+where
+    C: AuthorCache + Send + Sync {
+
+    async fn save_author_post(&self, post: &Post) -> Result<(), Error> {
+        self.cache.save_author_post(post).await
+    }
+
+    async fn delete_author_post(&self, author_id: &Uuid, post_id: &Uuid) -> Result<(), Error> {
+        self.cache.delete_author_post(author_id, post_id).await
+    }
+
+    async fn get_author_timeline(&self, author_id: Uuid, page: FeedPage) -> Result<Vec<String>, Error> {
+        self.cache.get_author_timeline(author_id, page).await
+    }
+
+    async fn save_author_timeline(&self, author_id: Uuid, posts: &Vec<Post>) -> Result<(), Error> {
+        self.cache.save_author_timeline(author_id, posts).await
+    }
+
+    async fn author_timeline_size(&self, author_id: Uuid) -> Result<u64, Error> {
+        self.cache.author_timeline_size(author_id).await
+    }
+
+    async fn mark_author_timeline_exists(&self, author_id: Uuid) -> Result<(), Error> {
+        self.cache.mark_author_timeline_exists(author_id).await
+    }
+
+    async fn check_author_timeline_exists(&self, author_id: Uuid) -> Result<bool, Error> {
+        self.cache.check_author_timeline_exists(author_id).await
+    }
+}
@@ -1,11 +1,28 @@
 pub mod controller;
 mod post_service;
-mod post_cache;
+pub mod post_cache;
+pub mod local_post_cache;
+pub mod cache_invalidator;
 mod repository;
+pub mod in_memory_repository;
+pub mod in_memory_post_cache;
 mod model;
+mod cursor;
+mod links;
 mod cached_post_service;
+mod moderating_post_service;
 pub mod service_provider;
-mod rabbitmq;
+pub mod rabbitmq;
 mod event;
 mod publishing_service;
-pub mod followers;
\ No newline at end of file
+pub mod followers;
+pub mod reaction;
+pub mod comment;
+pub mod draft;
+pub mod moderation;
+pub mod warmup;
+pub mod dlq;
+pub mod outbox;
+
+#[cfg(test)]
+mod tests;
@@ -1,10 +1,59 @@
 use uuid::Uuid;
 use serde::{Serialize, Deserialize};
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    #[default]
+    Public,
+    Friends,
+    Private
+}
+
+impl Visibility {
+    pub const ALL: [Visibility; 3] = [Visibility::Public, Visibility::Friends, Visibility::Private];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Visibility::Public => "public",
+            Visibility::Friends => "friends",
+            Visibility::Private => "private",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Visibility> {
+        Visibility::ALL.into_iter().find(|v| v.as_str() == value)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug,Clone)]
 pub struct Post {
     pub id: Uuid,            
     pub text: String,
     pub author_user_id: Uuid,
+    #[serde(alias = "timestamp")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    pub edited_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub comments_count: i64,
+    #[serde(default)]
+    pub repost_of: Option<Uuid>,
+    #[serde(default)]
+    pub original_author_user_id: Option<Uuid>,
+    #[serde(default)]
+    pub visibility: Visibility
+}
+
+impl Post {
+    pub fn is_public(&self) -> bool {
+        self.visibility == Visibility::Public
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PostRevision {
+    pub text: String,
+    pub visibility: Visibility,
     pub timestamp: chrono::DateTime<chrono::Utc>
 }
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+use uuid::Uuid;
+use crate::modules::post::{cursor::FeedPage, model::{Post, PostRevision, Visibility}, moderation::filter::ContentFilter, service_provider::{PostService, PostServiceError}};
+use async_trait::async_trait; 
+
+pub struct ModeratingPostService <S>
+where
+    S: PostService {
+    service: S,
+    filters: Vec<Arc<dyn ContentFilter + Send + Sync>>,
+}
+
+impl <S> ModeratingPostService<S>
+where 
+    S: PostService + Send + Sync {
+    pub fn new(service: S, filters: Vec<Arc<dyn ContentFilter + Send + Sync>>) -> Self {
+        ModeratingPostService { 
+            service,
+            filters
+        }
+    }
+
+    /// Runs the filters in order, the first rejection wins. Edits skip the filters limiting new posts
+    async fn moderate(&self, user_id: Uuid, text: &str, edit: bool) -> Result<(), PostServiceError> {
+        for filter in self.filters.iter().filter(|filter| !edit || filter.checks_edits()) {
+            if let Some(reason) = filter.check(user_id, text).await {
+                tracing::info!("Post of user {} rejected: {}", user_id, reason);
+                return Err(PostServiceError::Rejected(reason));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl <S> PostService for ModeratingPostService<S>
+where
+    S: PostService + Send + Sync {
+    async fn create(&self, user_id: Uuid, text: &String, visibility: Visibility, post_id: Option<Uuid>) -> Result<Post, PostServiceError> {
+        self.moderate(user_id, text, false).await?;
+        Ok(self.service.create(user_id, text, visibility, post_id).await?)
+    }
+
+    async fn repost(&self, user_id: Uuid, post_id: Uuid, text: &Option<String>) -> Result<Post, PostServiceError> {
+        // A plain share has no text of its own to moderate
+        if let Some(text) = text.as_deref().filter(|text| !text.is_empty()) {
+            self.moderate(user_id, text, false).await?;
+        }
+        Ok(self.service.repost(user_id, post_id, text).await?)
+    }
+
+    async fn update(&self, user_id: Uuid, post_id: Uuid, text: &String, visibility: Option<Visibility>) -> Result<Post, PostServiceError> {
+        self.moderate(user_id, text, true).await?;
+        Ok(self.service.update(user_id, post_id, text, visibility).await?)
+    }
+
+    async fn delete(&self, user_id: Uuid, post_id: Uuid) -> Result<(), PostServiceError> {
+        Ok(self.service.delete(user_id, post_id).await?)
+    }
+
+    async fn restore(&self, user_id: Uuid, post_id: Uuid) -> Result<Post, PostServiceError> {
+        Ok(self.service.restore(user_id, post_id).await?)
+    }
+
+    async fn hide(&self, post_id: Uuid) -> Result<Post, PostServiceError> {
+        Ok(self.service.hide(post_id).await?)
+    }
+
+    async fn unhide(&self, post_id: Uuid) -> Result<Post, PostServiceError> {
+        Ok(self.service.unhide(post_id).await?)
+    }
+
+    async fn get(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Post, PostServiceError> {
+        Ok(self.service.get(viewer_id, post_id).await?)
+    }
+
+    async fn get_many(&self, viewer_id: Uuid, post_ids: &Vec<Uuid>) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.service.get_many(viewer_id, post_ids).await?)
+    }
+
+    async fn reposts(&self, post_id: Uuid) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.service.reposts(post_id).await?)
+    }
+
+    async fn revisions(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Vec<PostRevision>, PostServiceError> {
+        Ok(self.service.revisions(viewer_id, post_id).await?)
+    }
+
+    async fn feed(&self, user_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.service.feed(user_id, page).await?)
+    }
+
+    async fn tag_feed(&self, viewer_id: Uuid, tag: &String, page: FeedPage) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.service.tag_feed(viewer_id, tag, page).await?)
+    }
+
+    async fn author_feed(&self, viewer_id: Uuid, author_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.service.author_feed(viewer_id, author_id, page).await?)
+    }
+
+    async fn followed_celebrities(&self, user_id: Uuid) -> Result<Vec<Uuid>, PostServiceError> {
+        Ok(self.service.followed_celebrities(user_id).await?)
+    }
+}
@@ -0,0 +1,144 @@
+use openapi::apis::moderation::{Moderation, PostIdReportPostResponse, ModerationReportsGetResponse, ModerationPostIdHidePostResponse, ModerationPostIdUnhidePostResponse};
+use axum_extra::headers::Host;
+use axum_extra::extract::CookieJar;
+use axum::http::Method;
+use async_trait::async_trait;
+use openapi::models::{self};
+use crate::modules::post::moderation::{model, service_provider::ModerationServiceError};
+use crate::modules::auth::auth;
+use crate::Application;
+use uuid::Uuid;
+
+#[async_trait]
+impl Moderation for Application {
+    type Claims = auth::Claims;
+
+    async fn post_id_report_post(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostIdReportPostPathParams,
+        body: &Option<models::PostIdReportPostRequest>,
+    ) -> Result<PostIdReportPostResponse, ()> {
+        let post_id = match Uuid::parse_str(&path_params.id) {
+            Ok(id) => id,
+            Err(_) => return Ok(PostIdReportPostResponse::Status400)
+        };
+        let body = match body {
+            Some(body) => body,
+            None => return Ok(PostIdReportPostResponse::Status400)
+        };
+        match self.state.moderation_service.report(claims.user_id, post_id, &body.reason).await {
+            Ok(()) => Ok(PostIdReportPostResponse::Status200),
+            Err(ModerationServiceError::NotFound(_)) => Ok(PostIdReportPostResponse::Status404),
+            Err(e) => {
+                tracing::error!("Report post error: {:?}", e);
+                Ok(PostIdReportPostResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn moderation_reports_get(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        query_params: &models::ModerationReportsGetQueryParams,
+    ) -> Result<ModerationReportsGetResponse, ()> {
+        match self.state.moderation_service.reports(claims.user_id, query_params.limit, query_params.offset).await {
+            Ok(reports) => Ok(ModerationReportsGetResponse::Status200(reports.into_iter().map(to_report_dto).collect())),
+            Err(ModerationServiceError::Forbidden(_)) => Ok(ModerationReportsGetResponse::Status403),
+            Err(e) => {
+                tracing::error!("List reports error: {:?}", e);
+                Ok(ModerationReportsGetResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn moderation_post_id_hide_post(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::ModerationPostIdHidePostPathParams,
+    ) -> Result<ModerationPostIdHidePostResponse, ()> {
+        let post_id = match Uuid::parse_str(&path_params.id) {
+            Ok(id) => id,
+            Err(_) => return Ok(ModerationPostIdHidePostResponse::Status400)
+        };
+        match self.state.moderation_service.hide(claims.user_id, post_id).await {
+            Ok(_) => Ok(ModerationPostIdHidePostResponse::Status200),
+            Err(ModerationServiceError::Forbidden(_)) => Ok(ModerationPostIdHidePostResponse::Status403),
+            Err(ModerationServiceError::NotFound(_)) => Ok(ModerationPostIdHidePostResponse::Status404),
+            Err(e) => {
+                tracing::error!("Hide post error: {:?}", e);
+                Ok(ModerationPostIdHidePostResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn moderation_post_id_unhide_post(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::ModerationPostIdUnhidePostPathParams,
+    ) -> Result<ModerationPostIdUnhidePostResponse, ()> {
+        let post_id = match Uuid::parse_str(&path_params.id) {
+            Ok(id) => id,
+            Err(_) => return Ok(ModerationPostIdUnhidePostResponse::Status400)
+        };
+        match self.state.moderation_service.unhide(claims.user_id, post_id).await {
+            Ok(_) => Ok(ModerationPostIdUnhidePostResponse::Status200),
+            Err(ModerationServiceError::Forbidden(_)) => Ok(ModerationPostIdUnhidePostResponse::Status403),
+            Err(ModerationServiceError::NotFound(_)) => Ok(ModerationPostIdUnhidePostResponse::Status404),
+            Err(e) => {
+                tracing::error!("Unhide post error: {:?}", e);
+                Ok(ModerationPostIdUnhidePostResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+}
+
+fn to_report_dto(report: model::PostReport) -> models::PostReport {
+    models::PostReport {
+        id: report.id.to_string(),
+        post_id: report.post_id.to_string(),
+        reporter_user_id: report.reporter_user_id.to_string(),
+        reason: report.reason,
+        created_at: report.timestamp
+    }
+}
@@ -0,0 +1,117 @@
+use std::{env, sync::Arc};
+use fred::prelude::*;
+use uuid::Uuid;
+use async_trait::async_trait;
+use crate::modules::common::ext::extensions::ResultExt;
+
+const LINK_PREFIXES: [&str; 3] = ["http://", "https://", "www."];
+
+/// A moderation stage applied to the text of created and updated posts
+#[async_trait]
+pub trait ContentFilter {
+    /// Returns the reason the text is rejected for, `None` lets it through
+    async fn check(&self, user_id: Uuid, text: &str) -> Option<String>;
+
+    /// Whether the filter also applies to edits of existing posts, otherwise it only sees new posts
+    fn checks_edits(&self) -> bool {
+        true
+    }
+}
+
+pub struct FilterConfig {
+    pub banned_words: Vec<String>,
+    pub max_links: usize,
+    pub max_posts_per_window: i64,
+    pub window_seconds: i64,
+}
+
+impl FilterConfig {
+    pub fn from_env() -> Self {
+        FilterConfig {
+            banned_words: env::var("MODERATION_BANNED_WORDS")
+                .map(|words| words.split(',').map(|word| word.trim().to_lowercase()).filter(|word| !word.is_empty()).collect())
+                .unwrap_or_default(),
+            max_links: env::var("MODERATION_MAX_LINKS").ok().and_then(|v| v.parse().ok()).unwrap_or(3),
+            max_posts_per_window: env::var("MODERATION_MAX_POSTS_PER_WINDOW").ok().and_then(|v| v.parse().ok()).unwrap_or(10),
+            window_seconds: env::var("MODERATION_WINDOW_SECONDS").ok().and_then(|v| v.parse().ok()).unwrap_or(60),
+        }
+    }
+}
+
+pub fn create_filters(config: FilterConfig, redis: Arc<Pool>) -> Vec<Arc<dyn ContentFilter + Send + Sync>> {
+    vec!(
+        Arc::new(BannedWordsFilter { words: config.banned_words }),
+        Arc::new(LinkSpamFilter { max_links: config.max_links }),
+        Arc::new(RateLimitFilter { pool: redis, max_posts: config.max_posts_per_window, window_seconds: config.window_seconds }),
+    )
+}
+
+/// Filters which need no Redis, the rate limit is left out
+pub fn create_local_filters(config: FilterConfig) -> Vec<Arc<dyn ContentFilter + Send + Sync>> {
+    vec!(
+        Arc::new(BannedWordsFilter { words: config.banned_words }),
+        Arc::new(LinkSpamFilter { max_links: config.max_links }),
+    )
+}
+
+pub struct BannedWordsFilter {
+    words: Vec<String>,
+}
+
+#[async_trait]
+impl ContentFilter for BannedWordsFilter {
+    async fn check(&self, _: Uuid, text: &str) -> Option<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .map(str::to_lowercase)
+            .find(|word| self.words.contains(word))
+            .map(|word| format!("Banned word: {}", word))
+    }
+}
+
+pub struct LinkSpamFilter {
+    max_links: usize,
+}
+
+#[async_trait]
+impl ContentFilter for LinkSpamFilter {
+    async fn check(&self, _: Uuid, text: &str) -> Option<String> {
+        let links = text.split_whitespace()
+            .filter(|word| LINK_PREFIXES.iter().any(|prefix| word.to_lowercase().starts_with(prefix)))
+            .count();
+        (links > self.max_links).then(|| format!("Too many links: {}", links))
+    }
+}
+
+/// Counts new posts of the user in a fixed window, Redis failures let the post through
+pub struct RateLimitFilter {
+    pool: Arc<Pool>,
+    max_posts: i64,
+    window_seconds: i64,
+}
+
+impl RateLimitFilter {
+    fn get_key(&self, user_id: Uuid) -> String {
+        format!("highload/moderation/rate/{}", user_id)
+    }
+
+    async fn count(&self, user_id: Uuid) -> Result<i64, Error> {
+        let key = self.get_key(user_id);
+        let pipeline = self.pool.next().pipeline();
+        let _: () = pipeline.incr(&key).await?;
+        let _: () = pipeline.expire(&key, self.window_seconds, Some(ExpireOptions::NX)).await?;
+        let (count, _): (i64, i64) = pipeline.all().await?;
+        Ok(count)
+    }
+}
+
+#[async_trait]
+impl ContentFilter for RateLimitFilter {
+    async fn check(&self, user_id: Uuid, _: &str) -> Option<String> {
+        let count = self.count(user_id).await.warn(format!("Failed to count posts of user {}", user_id))?;
+        (count > self.max_posts).then(|| format!("Too many posts in {} seconds", self.window_seconds))
+    }
+
+    fn checks_edits(&self) -> bool {
+        false
+    }
+}
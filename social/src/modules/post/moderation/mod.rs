@@ -0,0 +1,6 @@
+pub mod controller;
+pub mod model;
+pub mod filter;
+mod repository;
+mod moderation_service;
+pub mod service_provider;
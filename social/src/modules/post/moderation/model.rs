@@ -0,0 +1,11 @@
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PostReport {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub reporter_user_id: Uuid,
+    pub reason: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>
+}
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use uuid::Uuid;
+use async_trait::async_trait;
+use crate::modules::post::{model::Post, repository::PostRepositoryError, service_provider::{PostService, PostServiceError}};
+use crate::modules::post::moderation::{model::PostReport, repository::ModerationRepository, service_provider::{ModerationService, ModerationServiceError}};
+
+pub struct ModerationServiceImpl<R>
+where
+    R: ModerationRepository {
+    repository: R,
+    post_service: Arc<dyn PostService + Send + Sync>,
+    admin_ids: Vec<Uuid>,
+}
+
+impl <R> ModerationServiceImpl<R>
+where
+    R: ModerationRepository + Send + Sync {
+    pub fn new(repository: R, post_service: Arc<dyn PostService + Send + Sync>, admin_ids: Vec<Uuid>) -> Self {
+        ModerationServiceImpl {
+            repository,
+            post_service,
+            admin_ids
+        }
+    }
+
+    fn check_admin(&self, user_id: Uuid) -> Result<(), ModerationServiceError> {
+        if self.admin_ids.contains(&user_id) {
+            Ok(())
+        } else {
+            Err(ModerationServiceError::Forbidden(format!("User {} is not a moderator", user_id)))
+        }
+    }
+}
+
+fn to_moderation_error(e: PostServiceError) -> ModerationServiceError {
+    match e {
+        PostServiceError::Database(PostRepositoryError::NotFound(message)) => ModerationServiceError::NotFound(message),
+        e => ModerationServiceError::Post(e.to_string())
+    }
+}
+
+#[async_trait]
+impl <R> ModerationService for ModerationServiceImpl<R>
+where
+    R: ModerationRepository + Send + Sync {
+
+    async fn report(&self, user_id: Uuid, post_id: Uuid, reason: &String) -> Result<(), ModerationServiceError> {
+        // Only posts the reporter can see may be reported
+        self.post_service.get(user_id, post_id).await.map_err(to_moderation_error)?;
+        Ok(self.repository.report(user_id, post_id, reason).await?)
+    }
+
+    async fn reports(&self, admin_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<PostReport>, ModerationServiceError> {
+        self.check_admin(admin_id)?;
+        Ok(self.repository.pending(limit, offset).await?)
+    }
+
+    async fn hide(&self, admin_id: Uuid, post_id: Uuid) -> Result<Post, ModerationServiceError> {
+        self.check_admin(admin_id)?;
+        // Hiding goes through the post service chain, which evicts caches and feeds
+        let post = self.post_service.hide(post_id).await.map_err(to_moderation_error)?;
+        tracing::info!("Post {} hidden by {}", post_id, admin_id);
+        self.repository.resolve(post_id).await?;
+        Ok(post)
+    }
+
+    async fn unhide(&self, admin_id: Uuid, post_id: Uuid) -> Result<Post, ModerationServiceError> {
+        self.check_admin(admin_id)?;
+        let post = self.post_service.unhide(post_id).await.map_err(to_moderation_error)?;
+        tracing::info!("Post {} restored by {}", post_id, admin_id);
+        Ok(post)
+    }
+}
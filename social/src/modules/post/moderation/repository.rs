@@ -0,0 +1,76 @@
+use uuid::Uuid;
+use deadpool_postgres::Pool;
+use tokio_postgres::Row;
+use thiserror::Error;
+use std::sync::Arc;
+use async_trait::async_trait;
+use mockall::automock;
+use crate::modules::post::moderation::model::PostReport;
+
+#[derive(Error, Debug)]
+pub enum ModerationRepositoryError {
+    #[error("Database error: {0}")]
+    Database(#[from] tokio_postgres::Error),
+
+    #[error("Pool error: {0}")]
+    Pool(#[from] deadpool_postgres::PoolError),
+}
+
+#[automock]
+#[async_trait]
+pub trait ModerationRepository {
+    async fn report(&self, user_id: Uuid, post_id: Uuid, reason: &String) -> Result<(), ModerationRepositoryError>;
+    async fn pending(&self, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<PostReport>, ModerationRepositoryError>;
+    async fn resolve(&self, post_id: Uuid) -> Result<(), ModerationRepositoryError>;
+}
+
+pub struct ModerationRepositoryImpl {
+    pool: Arc<Pool>
+}
+
+impl ModerationRepositoryImpl {
+    pub fn new(pool: Arc<Pool>) -> Self {
+        ModerationRepositoryImpl { pool }
+    }
+}
+
+fn to_report(row: &Row) -> PostReport {
+    PostReport {
+        id: row.get("id"),
+        post_id: row.get("post_id"),
+        reporter_user_id: row.get("reporter_id"),
+        reason: row.get("reason"),
+        timestamp: row.get("created_at")
+    }
+}
+
+#[async_trait]
+impl ModerationRepository for ModerationRepositoryImpl {
+
+    async fn report(&self, user_id: Uuid, post_id: Uuid, reason: &String) -> Result<(), ModerationRepositoryError> {
+        // A repeated report of the same user replaces the earlier one and puts it back in the queue
+        self.pool.get().await?.execute(
+            "INSERT INTO post_reports (post_id, reporter_id, reason) VALUES ($1, $2, $3)
+                ON CONFLICT (post_id, reporter_id) DO UPDATE SET reason=EXCLUDED.reason, status='pending', created_at=NOW()",
+            &[&post_id, &user_id, reason]
+        ).await?;
+        Ok(())
+    }
+
+    async fn pending(&self, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<PostReport>, ModerationRepositoryError> {
+        let res = self.pool.get().await?.query(
+            "SELECT id, post_id, reporter_id, reason, created_at FROM post_reports
+                WHERE status='pending' ORDER BY created_at LIMIT $1 OFFSET $2",
+            &[&limit.map(|v| v as i64), &offset.map(|v| v as i64)]
+        ).await?;
+        Ok(res.iter().map(to_report).collect())
+    }
+
+    async fn resolve(&self, post_id: Uuid) -> Result<(), ModerationRepositoryError> {
+        self.pool.get().await?.execute(
+            "UPDATE post_reports SET status='resolved' WHERE post_id=$1 AND status='pending'",
+            &[&post_id]
+        ).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+use deadpool_postgres;
+use thiserror::Error;
+use uuid::Uuid;
+use async_trait::async_trait;
+use crate::modules::post::{model::Post, service_provider::PostService};
+use crate::modules::post::moderation::{model::PostReport, moderation_service::ModerationServiceImpl, repository::{ModerationRepositoryError, ModerationRepositoryImpl}};
+
+#[derive(Error, Debug)]
+pub enum ModerationServiceError {
+    #[error("Database error: {0}")]
+    Database(#[from] ModerationRepositoryError),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Post error: {0}")]
+    Post(String),
+}
+
+#[async_trait]
+pub trait ModerationService {
+    async fn report(&self, user_id: Uuid, post_id: Uuid, reason: &String) -> Result<(), ModerationServiceError>;
+    async fn reports(&self, admin_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<PostReport>, ModerationServiceError>;
+    async fn hide(&self, admin_id: Uuid, post_id: Uuid) -> Result<Post, ModerationServiceError>;
+    async fn unhide(&self, admin_id: Uuid, post_id: Uuid) -> Result<Post, ModerationServiceError>;
+}
+
+pub fn create_service(pool: Arc<deadpool_postgres::Pool>, post_service: Arc<dyn PostService + Send + Sync>, admin_ids: Vec<Uuid>) -> Arc<dyn ModerationService + Send + Sync> {
+    Arc::new(
+        ModerationServiceImpl::new(
+            ModerationRepositoryImpl::new(pool),
+            post_service,
+            admin_ids
+        )
+    )
+}
@@ -0,0 +1,3 @@
+pub mod repository;
+mod outbox_relay;
+pub mod service_provider;
@@ -0,0 +1,107 @@
+use std::{sync::Arc, time::{Duration, Instant}};
+use async_trait::async_trait;
+use crate::modules::post::{event::{DomainEvent, EventPublisher}, rabbitmq::PublishError};
+use crate::modules::post::outbox::{repository::{OutboxEntry, OutboxRepository}, service_provider::{OutboxConfig, OutboxRelay}};
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(600);
+
+enum RelayError {
+    /// Only this entry failed, e.g. it could not be routed
+    Entry(String),
+    /// The broker is unreachable or failing
+    Broker(String),
+}
+
+pub struct OutboxRelayImpl<R>
+where
+    R: OutboxRepository {
+    repository: R,
+    publisher: Arc<dyn EventPublisher + Send + Sync>,
+    config: OutboxConfig,
+}
+
+impl <R> OutboxRelayImpl<R>
+where
+    R: OutboxRepository + Send + Sync {
+    pub fn new(repository: R, publisher: Arc<dyn EventPublisher + Send + Sync>, config: OutboxConfig) -> Self {
+        OutboxRelayImpl { repository, publisher, config }
+    }
+
+    /// Exponential backoff by the number of failed attempts
+    fn retry_in_seconds(&self, attempts: i32) -> i32 {
+        2_i32.saturating_pow(attempts.clamp(0, 30) as u32).min(self.config.max_retry_seconds)
+    }
+
+    async fn publish(&self, entry: &OutboxEntry) -> Result<(), RelayError> {
+        let event: DomainEvent = serde_json::from_str(&entry.payload).map_err(|e| RelayError::Entry(e.to_string()))?;
+        self.publisher.publish(&event).await.map_err(|e| {
+            if e.downcast_ref::<PublishError>().is_some_and(PublishError::is_broker_failure) {
+                RelayError::Broker(e.to_string())
+            } else {
+                RelayError::Entry(e.to_string())
+            }
+        })
+    }
+
+    /// Retries the failed entry later, or gives up on it once it used up its attempts
+    async fn fail(&self, entry: &OutboxEntry, error: &String) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if entry.attempts + 1 >= self.config.max_attempts {
+            tracing::error!("Giving up on outbox entry {} after {} attempts: {}", entry.id, entry.attempts + 1, error);
+            self.repository.mark_dead(entry.id, error).await?;
+        } else {
+            tracing::warn!("Failed to relay outbox entry {}: {}", entry.id, error);
+            self.repository.mark_failed(entry.id, error, self.retry_in_seconds(entry.attempts)).await?;
+        }
+        Ok(())
+    }
+
+    /// Delivers one claimed batch, returns the number of entries sent.
+    /// A batch holds at most one entry per post. A failed entry is retried on its own,
+    /// a broker failure stops the batch and hands the rest of it back right away
+    async fn relay_batch(&self) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let entries = self.repository.claim(self.config.batch_size, self.config.lease_seconds).await?;
+        let mut sent = vec!();
+        let mut unsent = vec!();
+        for (i, entry) in entries.iter().enumerate() {
+            match self.publish(entry).await {
+                Ok(()) => sent.push(entry.id),
+                Err(RelayError::Entry(e)) => self.fail(entry, &e).await?,
+                Err(RelayError::Broker(e)) => {
+                    self.fail(entry, &e).await?;
+                    unsent = entries[i + 1..].iter().map(|entry| entry.id).collect();
+                    break;
+                }
+            }
+        }
+        self.repository.mark_sent(&sent).await?;
+        self.repository.release(&unsent).await?;
+        Ok(sent.len())
+    }
+}
+
+#[async_trait]
+impl <R> OutboxRelay for OutboxRelayImpl<R>
+where
+    R: OutboxRepository + Send + Sync {
+    async fn run(&self) -> () {
+        tracing::info!("Outbox relay started...");
+        let mut last_purge = Instant::now();
+        loop {
+            if last_purge.elapsed() >= PURGE_INTERVAL {
+                last_purge = Instant::now();
+                match self.repository.purge_sent(self.config.retention_hours).await {
+                    Ok(purged) if purged > 0 => tracing::info!("Purged {} sent outbox entries", purged),
+                    Ok(_) => {},
+                    Err(e) => tracing::warn!("Failed to purge outbox {:?}", e)
+                }
+            }
+            match self.relay_batch().await {
+                // A full batch means more entries are likely waiting
+                Ok(sent) if sent as i64 == self.config.batch_size => continue,
+                Ok(_) => {},
+                Err(e) => tracing::warn!("Outbox relay error {:?}", e)
+            }
+            tokio::time::sleep(self.config.poll_interval).await;
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use deadpool_postgres::{Pool, Transaction};
+use thiserror::Error;
+use std::sync::Arc;
+use async_trait::async_trait;
+use mockall::automock;
+use crate::modules::post::event::DomainEvent;
+
+#[derive(Error, Debug)]
+pub enum OutboxRepositoryError {
+    #[error("Database error: {0}")]
+    Database(#[from] tokio_postgres::Error),
+
+    #[error("Pool error: {0}")]
+    Pool(#[from] deadpool_postgres::PoolError),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone)]
+pub struct OutboxEntry {
+    pub id: i64,
+    pub payload: String,
+    pub attempts: i32,
+}
+
+/// Writes the event to the outbox within the transaction of the change it describes
+pub async fn enqueue(tx: &Transaction<'_>, event: &DomainEvent) -> Result<(), OutboxRepositoryError> {
+    tx.execute(
+        "INSERT INTO post_outbox (routing_key, aggregate_id, payload) VALUES ($1, $2, $3::TEXT::JSONB)",
+        &[&event.routing_key(), event.post_id(), &serde_json::to_string(event)?]
+    ).await?;
+    Ok(())
+}
+
+#[automock]
+#[async_trait]
+pub trait OutboxRepository {
+    /// Leases up to `limit` due entries in insertion order, the lease expires after `lease_seconds`
+    /// so entries of a relay which died mid-batch are picked up again.
+    /// Only the oldest pending entry of a post is due, so events of one post are never in flight together
+    async fn claim(&self, limit: i64, lease_seconds: i32) -> Result<Vec<OutboxEntry>, OutboxRepositoryError>;
+    async fn mark_sent(&self, ids: &Vec<i64>) -> Result<(), OutboxRepositoryError>;
+    async fn mark_failed(&self, id: i64, error: &String, retry_in_seconds: i32) -> Result<(), OutboxRepositoryError>;
+    /// Ends the lease of entries left unsent, so they are due again right away
+    async fn release(&self, ids: &Vec<i64>) -> Result<(), OutboxRepositoryError>;
+    /// Gives up on the entry, it is kept for inspection and no longer holds back later events of its post
+    async fn mark_dead(&self, id: i64, error: &String) -> Result<(), OutboxRepositoryError>;
+    /// Deletes entries sent more than `retention_hours` ago, returns the number deleted
+    async fn purge_sent(&self, retention_hours: i32) -> Result<u64, OutboxRepositoryError>;
+}
+
+pub struct OutboxRepositoryImpl {
+    pool: Arc<Pool>
+}
+
+impl OutboxRepositoryImpl {
+    pub fn new(pool: Arc<Pool>) -> Self {
+        OutboxRepositoryImpl { pool }
+    }
+}
+
+#[async_trait]
+impl OutboxRepository for OutboxRepositoryImpl {
+
+    async fn claim(&self, limit: i64, lease_seconds: i32) -> Result<Vec<OutboxEntry>, OutboxRepositoryError> {
+        // SKIP LOCKED lets several relays claim disjoint batches. An entry behind an older pending one of the same post
+        // waits even while that one is leased or backing off, so neither another relay nor a retry reorders them
+        let res = self.pool.get().await?.query(
+            "UPDATE post_outbox SET next_attempt_at = NOW() + make_interval(secs => $2) 
+                WHERE id IN (
+                    SELECT o.id FROM post_outbox o WHERE o.sent_at IS NULL AND o.dead_at IS NULL AND o.next_attempt_at <= NOW() 
+                        AND NOT EXISTS (
+                            SELECT 1 FROM post_outbox b WHERE b.aggregate_id = o.aggregate_id AND b.id < o.id 
+                                AND b.sent_at IS NULL AND b.dead_at IS NULL
+                        )
+                        ORDER BY o.id LIMIT $1 FOR UPDATE SKIP LOCKED
+                ) 
+                RETURNING id, payload::TEXT AS payload, attempts",
+            &[&limit, &(lease_seconds as f64)]
+        ).await?;
+        let mut entries: Vec<OutboxEntry> = res.iter()
+            .map(|row| OutboxEntry {
+                id: row.get("id"),
+                payload: row.get("payload"),
+                attempts: row.get("attempts")
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.id);
+        Ok(entries)
+    }
+
+    async fn mark_sent(&self, ids: &Vec<i64>) -> Result<(), OutboxRepositoryError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.pool.get().await?.execute(
+            "UPDATE post_outbox SET sent_at = NOW(), last_error = NULL WHERE id = ANY($1)",
+            &[ids]
+        ).await?;
+        Ok(())
+    }
+
+    async fn mark_failed(&self, id: i64, error: &String, retry_in_seconds: i32) -> Result<(), OutboxRepositoryError> {
+        self.pool.get().await?.execute(
+            "UPDATE post_outbox SET attempts = attempts + 1, last_error = $2, next_attempt_at = NOW() + make_interval(secs => $3) 
+                WHERE id = $1",
+            &[&id, error, &(retry_in_seconds as f64)]
+        ).await?;
+        Ok(())
+    }
+
+    async fn release(&self, ids: &Vec<i64>) -> Result<(), OutboxRepositoryError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.pool.get().await?.execute(
+            "UPDATE post_outbox SET next_attempt_at = NOW() WHERE id = ANY($1) AND sent_at IS NULL",
+            &[ids]
+        ).await?;
+        Ok(())
+    }
+
+    async fn mark_dead(&self, id: i64, error: &String) -> Result<(), OutboxRepositoryError> {
+        self.pool.get().await?.execute(
+            "UPDATE post_outbox SET attempts = attempts + 1, last_error = $2, dead_at = NOW() WHERE id = $1",
+            &[&id, error]
+        ).await?;
+        Ok(())
+    }
+
+    async fn purge_sent(&self, retention_hours: i32) -> Result<u64, OutboxRepositoryError> {
+        Ok(self.pool.get().await?.execute(
+            "DELETE FROM post_outbox WHERE sent_at < NOW() - make_interval(hours => $1)",
+            &[&retention_hours]
+        ).await?)
+    }
+}
@@ -0,0 +1,46 @@
+use std::{env, sync::Arc, time::Duration};
+use deadpool_postgres;
+use async_trait::async_trait;
+use crate::modules::post::{outbox::{outbox_relay::OutboxRelayImpl, repository::OutboxRepositoryImpl}, rabbitmq::RabbitPublisher};
+
+#[derive(Clone, Copy, Debug)]
+pub struct OutboxConfig {
+    pub batch_size: i64,
+    /// Pause before the next poll once the outbox is drained
+    pub poll_interval: Duration,
+    /// How long a claimed batch stays with one relay before others may retry it
+    pub lease_seconds: i32,
+    pub max_retry_seconds: i32,
+    /// Failed attempts after which an entry is dead and stops holding back later events of its post
+    pub max_attempts: i32,
+    pub retention_hours: i32,
+}
+
+impl OutboxConfig {
+    pub fn from_env() -> Self {
+        OutboxConfig {
+            batch_size: env::var("OUTBOX_BATCH_SIZE").ok().and_then(|v| v.parse().ok()).unwrap_or(100).max(1),
+            poll_interval: Duration::from_millis(env::var("OUTBOX_POLL_INTERVAL_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(200)),
+            lease_seconds: env::var("OUTBOX_LEASE_SECONDS").ok().and_then(|v| v.parse().ok()).unwrap_or(30),
+            max_retry_seconds: env::var("OUTBOX_MAX_RETRY_SECONDS").ok().and_then(|v| v.parse().ok()).unwrap_or(300),
+            max_attempts: env::var("OUTBOX_MAX_ATTEMPTS").ok().and_then(|v| v.parse().ok()).unwrap_or(20).max(1),
+            retention_hours: env::var("OUTBOX_RETENTION_HOURS").ok().and_then(|v| v.parse().ok()).unwrap_or(24),
+        }
+    }
+}
+
+#[async_trait]
+pub trait OutboxRelay {
+    /// Delivers outbox entries to the exchange until the process stops
+    async fn run(&self) -> ();
+}
+
+pub fn create_relay(pool: Arc<deadpool_postgres::Pool>, publisher: Arc<RabbitPublisher>, config: OutboxConfig) -> Arc<dyn OutboxRelay + Send + Sync> {
+    Arc::new(
+        OutboxRelayImpl::new(
+            OutboxRepositoryImpl::new(pool),
+            publisher,
+            config
+        )
+    )
+}
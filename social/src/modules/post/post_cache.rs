@@ -1,23 +1,75 @@
 use fred::prelude::Pool;
 use uuid::Uuid;
-use crate::modules::post::{model::Post};
+use crate::modules::post::{cursor::{FeedCursor, FeedPage}, model::Post};
 use fred::prelude::{SortedSetsInterface};
 use fred::prelude::*;
 use fred::error::Error;
-use std::sync::Arc;
+use std::{env, pin::Pin, sync::Arc};
+use fred::types::scan::{ScanResult, ScanType, Scanner};
+use futures::{Stream, TryStreamExt};
+use crate::modules::common::ext::redis_ext::mget_by_slot;
 use async_trait::async_trait; 
 use mockall::automock;
 
-const DEFAULT_FEED_SIZE: u64 = 1000;
-const POST_CACHE_TTL_SECONDS: i64 = 86400;
+pub const DEFAULT_FEED_SIZE: u64 = 1000;
+pub const AUTHOR_TIMELINE_SIZE: u64 = 1000;
+pub const POST_CACHE_TTL_SECONDS: i64 = 86400;
+pub const FEED_MARK_TTL_SECONDS: i64 = 3600;
+pub const FEED_LOCK_TTL_SECONDS: i64 = 10;
+pub const AUTHOR_MARK_TTL_SECONDS: i64 = 3600;
+// A timeline outlives its mark, so a marked timeline is never read empty, and goes away once nobody reads it
+pub const AUTHOR_TIMELINE_TTL_SECONDS: i64 = 86400;
+// Author timelines from before keys were hash-tagged, they were written without expiration
+const LEGACY_AUTHOR_KEYS_PATTERN: &str = "highload/post/author/ids/*";
+const SCAN_PAGE_SIZE: u32 = 1000;
+const RELEASE_LOCK_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+// Feeds of users who have not read them lately are gone, so fan-out does not bring them back
+const FEED_PUSH_SCRIPT: &str = r#"
+if redis.call('EXISTS', KEYS[1]) == 1 then
+    redis.call('ZADD', KEYS[1], ARGV[1], ARGV[2])
+    redis.call('ZREMRANGEBYRANK', KEYS[1], 0, -tonumber(ARGV[3]) - 1)
+end
+return 0
+"#;
+
+/// Bounds of the cached feed zsets
+#[derive(Debug, Clone, Copy)]
+pub struct FeedLimits {
+    pub size: u64,
+    pub ttl_seconds: i64,
+}
+
+impl FeedLimits {
+    pub fn from_env() -> Self {
+        FeedLimits {
+            size: env::var("FEED_CACHE_SIZE").ok().and_then(|v| v.parse().ok()).unwrap_or(DEFAULT_FEED_SIZE),
+            // A feed must outlive its mark, otherwise a marked feed could be read empty
+            ttl_seconds: env::var("FEED_CACHE_TTL_SECONDS").ok().and_then(|v| v.parse().ok()).unwrap_or(86400).max(FEED_MARK_TTL_SECONDS),
+        }
+    }
+}
 
 #[automock]
 #[async_trait]
 pub trait FeedCache {
     async fn process_save(&self, followers_ids: &Vec<Uuid>, post: &Post) -> Result<(), Error>;
     async fn process_delete(&self, followers_ids: &Vec<Uuid>, post_id: &Uuid) -> Result<(), Error>;    
-    async fn get_user_feed(&self, user_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<String>, Error>;
+    async fn get_user_feed(&self, user_id: Uuid, page: FeedPage) -> Result<Vec<String>, Error>;
     async fn save_user_feed(&self, user_id: Uuid, posts: &Vec<Post>) -> Result<(), Error>;
+    async fn remove_from_user_feed(&self, user_id: Uuid, post_ids: &Vec<String>) -> Result<(), Error>;
+    /// Drops the feed together with its mark
+    async fn delete_user_feed(&self, user_id: Uuid) -> Result<(), Error>;
+    async fn is_user_feed_full(&self, user_id: Uuid) -> Result<bool, Error>;
+    async fn get_followed_celebrities(&self, user_id: Uuid) -> Result<Option<Vec<Uuid>>, Error>;
+    async fn save_followed_celebrities(&self, user_id: Uuid, authors: &Vec<Uuid>) -> Result<(), Error>;
+    /// Drops the list after the user followed or unfollowed someone, or a followed author crossed the celebrity threshold.
+    /// The next feed read loads it again
+    async fn delete_followed_celebrities(&self, user_id: Uuid) -> Result<(), Error>;
 }
 
 #[automock]
@@ -35,33 +87,112 @@ pub trait UserPostCache {
 pub trait MarkCache {
     async fn mark_feed_exists(&self, user_id: Uuid) -> Result<(), Error>;
     async fn check_feed_exists(&self, user_id: Uuid) -> Result<bool, Error>;
+    /// Seconds left until the feed mark expires, `None` if there is no mark
+    async fn feed_mark_ttl(&self, user_id: Uuid) -> Result<Option<i64>, Error>;
+    async fn lock_feed(&self, user_id: Uuid, token: &String) -> Result<bool, Error>;
+    async fn unlock_feed(&self, user_id: Uuid, token: &String) -> Result<(), Error>;
 }
 
+#[automock]
 #[async_trait]
-pub trait PostCache: FeedCache + UserPostCache + MarkCache {}
+pub trait AuthorCache {
+    async fn save_author_post(&self, post: &Post) -> Result<(), Error>;
+    async fn delete_author_post(&self, author_id: &Uuid, post_id: &Uuid) -> Result<(), Error>;
+    async fn get_author_timeline(&self, author_id: Uuid, page: FeedPage) -> Result<Vec<String>, Error>;
+    async fn save_author_timeline(&self, author_id: Uuid, posts: &Vec<Post>) -> Result<(), Error>;
+    async fn author_timeline_size(&self, author_id: Uuid) -> Result<u64, Error>;
+    async fn mark_author_timeline_exists(&self, author_id: Uuid) -> Result<(), Error>;
+    async fn check_author_timeline_exists(&self, author_id: Uuid) -> Result<bool, Error>;
+}
 
-impl<T> PostCache for T where T: FeedCache + UserPostCache + MarkCache {}
+#[async_trait]
+pub trait PostCache: FeedCache + UserPostCache + MarkCache + AuthorCache {}
+
+impl<T> PostCache for T where T: FeedCache + UserPostCache + MarkCache + AuthorCache {}
 
 pub struct PostCacheImpl {
-    pool: Arc<Pool>
+    pool: Arc<Pool>,
+    feed_limits: FeedLimits,
+    replica_reads: bool,
 } 
 
 impl PostCacheImpl {
-    pub fn new(pool: Arc<Pool>) -> Self {
-        PostCacheImpl { pool }
+    /// With `replica_reads` post bodies are read from replicas. Feeds, marks and locks always go to the primary,
+    /// a feed rebuilt a moment ago must not be read back empty
+    pub fn new(pool: Arc<Pool>, feed_limits: FeedLimits, replica_reads: bool) -> Self {
+        PostCacheImpl { pool, feed_limits, replica_reads }
     }
 }
 
-
+// Keys of one user or author share a hash tag, so they live in one cluster slot
 impl PostCacheImpl {
+    /// Deletes author timelines left under the keys used before hash tags. Other keys of that layout
+    /// were written with expiration and are gone on their own. On a cluster every primary is scanned in turn
+    pub async fn delete_legacy_keys(&self) -> Result<u64, Error> {
+        let client = self.pool.next();
+        let mut pages: Pin<Box<dyn Stream<Item = Result<ScanResult, Error>> + Send>> = if client.is_clustered() {
+            Box::pin(client.scan_cluster(LEGACY_AUTHOR_KEYS_PATTERN, Some(SCAN_PAGE_SIZE), Some(ScanType::ZSet)))
+        } else {
+            Box::pin(client.scan(LEGACY_AUTHOR_KEYS_PATTERN, Some(SCAN_PAGE_SIZE), Some(ScanType::ZSet)))
+        };
+        let mut deleted = 0u64;
+        while let Some(mut page) = pages.try_next().await? {
+            if let Some(keys) = page.take_results() && !keys.is_empty() {
+                // Keys of a page belong to different slots, so they are deleted one by one in a pipeline
+                let pipeline = client.pipeline();
+                for key in &keys {
+                    let _: () = pipeline.del(key).await?;
+                }
+                let counts: Vec<u64> = pipeline.all().await?;
+                deleted += counts.into_iter().sum::<u64>();
+            }
+            let _ = page.next();
+        }
+        Ok(deleted)
+    }
+
     fn get_feed_key(&self, user_id: &Uuid) -> String {
-        format!("highload/post/feed/ids/{}", user_id)
+        format!("highload/post/feed/{{{}}}/ids", user_id)
     }
     fn get_post_key(&self, post_id: &String) -> String {
-        format!("highload/post:{}", post_id)
+        format!("highload/post:{{{}}}", post_id)
     }
     fn get_mark_key(&self, user_id: &Uuid) -> String {
-        format!("highload/post/feed/exists/{}", user_id)
+        format!("highload/post/feed/{{{}}}/exists", user_id)
+    }
+    fn get_lock_key(&self, user_id: &Uuid) -> String {
+        format!("highload/post/feed/{{{}}}/lock", user_id)
+    }
+    fn get_celebrities_key(&self, user_id: &Uuid) -> String {
+        format!("highload/post/feed/{{{}}}/celebrities", user_id)
+    }
+    fn get_author_key(&self, author_id: &Uuid) -> String {
+        format!("highload/post/author/{{{}}}/ids", author_id)
+    }
+    fn get_author_mark_key(&self, author_id: &Uuid) -> String {
+        format!("highload/post/author/{{{}}}/exists", author_id)
+    }
+
+    async fn get_page(&self, key: String, page: FeedPage) -> Result<Vec<String>, Error> {
+        match page {
+            FeedPage::Offset { limit, offset } => {
+                let start = offset.unwrap_or(0) as i64;
+                let stop = start + (limit.unwrap_or(DEFAULT_FEED_SIZE) as i64) - 1;
+                self.pool.next().zrevrange::<Vec<String>, _>(key, start, stop, false).await
+            },
+            FeedPage::After { limit, cursor } => {
+                let client = self.pool.next();
+                let score = cursor.score();
+                // Members sharing the cursor score come in reverse lexicographic order,
+                // so the ones at or above the cursor id were already served.
+                let ties: Vec<String> = client.zrangebyscore(&key, score, score, false, None).await?;
+                let cursor_id = cursor.post_id.to_string();
+                let skip = ties.iter().filter(|id| **id >= cursor_id).count() as i64;
+                client.zrevrangebyscore::<Vec<String>, _, _, _>(
+                    &key, score, "-inf", false, Some((skip, limit.unwrap_or(DEFAULT_FEED_SIZE) as i64))
+                ).await
+            }
+        }
     }
 }
 
@@ -69,12 +200,14 @@ impl PostCacheImpl {
 impl FeedCache for PostCacheImpl {
 
     async fn process_save(&self, followers_ids: &Vec<Uuid>, post: &Post) -> Result<(), Error> {      
+        let score = FeedCursor::of(post).score().to_string();
+        let post_id = post.id.to_string();
+        let size = self.feed_limits.size.to_string();
         let pipeline = self.pool.next().pipeline();        
         for follower_id in followers_ids {            
-            let _ = pipeline.zadd::<(), _, _>(
-                self.get_feed_key(follower_id), None, None, false, false, (post.timestamp.timestamp() as f64, post.id.to_string())
-            ).await;
-            let _: () = pipeline.set(self.get_mark_key(follower_id), "1", Some(Expiration::EX(3600)), None, false).await?;
+            let _: () = pipeline.eval(
+                FEED_PUSH_SCRIPT, vec!(self.get_feed_key(follower_id)), vec!(score.as_str(), post_id.as_str(), size.as_str())
+            ).await?;
         }
         pipeline.last().await
     }
@@ -89,29 +222,64 @@ impl FeedCache for PostCacheImpl {
         pipeline.last().await
     }
 
-    async fn get_user_feed(&self, user_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<String>, Error> {        
-        let start = offset.unwrap_or(0) as i64;
-        let stop = start + (limit.unwrap_or(DEFAULT_FEED_SIZE) as i64) - 1;
-        self.pool.next().zrevrange::<Vec<String>, _>(self.get_feed_key(&user_id), start, stop, false).await
+    async fn get_user_feed(&self, user_id: Uuid, page: FeedPage) -> Result<Vec<String>, Error> {        
+        self.get_page(self.get_feed_key(&user_id), page).await
     }
 
     async fn save_user_feed(&self, user_id: Uuid, posts: &Vec<Post>) -> Result<(), Error> {
         let entries: Vec<(f64, String)> = posts
             .iter()
-            .map(|p| (p.timestamp.timestamp() as f64, p.id.to_string()))
+            .map(|p| (FeedCursor::of(p).score(), p.id.to_string()))
             .collect();
         if entries.is_empty() {
             return Ok(());
         }        
-        self.pool.next().zadd(
-            self.get_feed_key(&user_id),
-            None, 
+        let key = self.get_feed_key(&user_id);
+        let pipeline = self.pool.next().pipeline();
+        let _: () = pipeline.zadd(&key, None, None, false, false, entries).await?;
+        let _: () = pipeline.zremrangebyrank(&key, 0, -(self.feed_limits.size as i64) - 1).await?;
+        let _: () = pipeline.expire(&key, self.feed_limits.ttl_seconds, None).await?;
+        pipeline.last().await
+    }  
+
+    async fn remove_from_user_feed(&self, user_id: Uuid, post_ids: &Vec<String>) -> Result<(), Error> {
+        if post_ids.is_empty() {
+            return Ok(());
+        }
+        self.pool.next().zrem(self.get_feed_key(&user_id), post_ids.clone()).await
+    }
+
+    async fn delete_user_feed(&self, user_id: Uuid) -> Result<(), Error> {
+        self.pool.next().del(vec!(self.get_feed_key(&user_id), self.get_mark_key(&user_id))).await
+    }
+
+    async fn is_user_feed_full(&self, user_id: Uuid) -> Result<bool, Error> {
+        let size: u64 = self.pool.next().zcard(self.get_feed_key(&user_id)).await?;
+        Ok(size >= self.feed_limits.size)
+    }
+
+    async fn get_followed_celebrities(&self, user_id: Uuid) -> Result<Option<Vec<Uuid>>, Error> {
+        let maybe_json: Option<String> = self.pool.next().get(self.get_celebrities_key(&user_id)).await?;
+        maybe_json
+            .map(|json| serde_json::from_str(&json).map_err(|e| Error::new(ErrorKind::Parse, e.to_string())))
+            .transpose()
+    }
+
+    async fn save_followed_celebrities(&self, user_id: Uuid, authors: &Vec<Uuid>) -> Result<(), Error> {
+        // Follows and unfollows drop the list right away, the expiration only catches
+        // authors who became celebrities or stopped being ones since it was loaded
+        self.pool.next().set(
+            self.get_celebrities_key(&user_id),
+            serde_json::to_string(authors).map_err(|e| Error::new(ErrorKind::Parse, e.to_string()))?,
+            Some(Expiration::EX(FEED_MARK_TTL_SECONDS)),
             None,
-            false,
-            false,
-            entries
+            false
         ).await
-    }  
+    }
+
+    async fn delete_followed_celebrities(&self, user_id: Uuid) -> Result<(), Error> {
+        self.pool.next().del(self.get_celebrities_key(&user_id)).await
+    }
 }
 
 #[async_trait]
@@ -148,7 +316,12 @@ impl UserPostCache for PostCacheImpl {
 
     async fn get_post(&self, post_id: &Uuid) -> Result<Option<Post>, Error> {            
         let item_key = self.get_post_key(&post_id.to_string());  
-        let maybe_json: Option<String> = self.pool.next().get(item_key).await?;                      
+        let client = self.pool.next();
+        let maybe_json: Option<String> = if self.replica_reads {
+            client.replicas().get(item_key).await?
+        } else {
+            client.get(item_key).await?
+        };                      
         match maybe_json {
             Some(json) => {
                 let post = serde_json::from_str(&json)
@@ -168,7 +341,7 @@ impl UserPostCache for PostCacheImpl {
             return Ok(vec!())
         }         
         let keys: Vec<String> = ids.iter().map(|id| self.get_post_key(id)).collect();
-        let json_posts: Vec<Option<String>> = self.pool.next().mget(keys).await?;
+        let json_posts = mget_by_slot(self.pool.next(), keys, self.replica_reads).await?;
         
         Ok(json_posts.into_iter()
             .flatten()
@@ -184,7 +357,7 @@ impl MarkCache for PostCacheImpl {
         self.pool.next().set(
             &self.get_mark_key(&user_id), 
             "1", 
-            Some(Expiration::EX(3600)), 
+            Some(Expiration::EX(FEED_MARK_TTL_SECONDS)), 
             None, 
             false
         ).await  
@@ -193,4 +366,86 @@ impl MarkCache for PostCacheImpl {
     async fn check_feed_exists(&self, user_id: Uuid) -> Result<bool, Error> {        
         self.pool.next().exists::<i64, _>(&self.get_mark_key(&user_id)).await.map(|count| count > 0)
     }     
-}
\ No newline at end of file
+
+    async fn feed_mark_ttl(&self, user_id: Uuid) -> Result<Option<i64>, Error> {
+        let ttl: i64 = self.pool.next().ttl(&self.get_mark_key(&user_id)).await?;
+        // -2 stands for a missing key, -1 for a key without expiration
+        Ok(match ttl {
+            -2 => None,
+            -1 => Some(i64::MAX),
+            ttl => Some(ttl)
+        })
+    }
+
+    async fn lock_feed(&self, user_id: Uuid, token: &String) -> Result<bool, Error> {
+        let locked: Option<String> = self.pool.next().set(
+            &self.get_lock_key(&user_id),
+            token.as_str(),
+            Some(Expiration::EX(FEED_LOCK_TTL_SECONDS)),
+            Some(SetOptions::NX),
+            false
+        ).await?;
+        Ok(locked.is_some())
+    }
+
+    async fn unlock_feed(&self, user_id: Uuid, token: &String) -> Result<(), Error> {
+        // The lock may have expired and been taken by another instance, only the owner releases it
+        let _: i64 = self.pool.next().eval(RELEASE_LOCK_SCRIPT, vec!(self.get_lock_key(&user_id)), vec!(token.as_str())).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AuthorCache for PostCacheImpl {
+
+    async fn save_author_post(&self, post: &Post) -> Result<(), Error> {
+        let key = self.get_author_key(&post.author_user_id);
+        let pipeline = self.pool.next().pipeline();
+        let _: () = pipeline.zadd(&key, None, None, false, false, (FeedCursor::of(post).score(), post.id.to_string())).await?;
+        let _: () = pipeline.zremrangebyrank(&key, 0, -(AUTHOR_TIMELINE_SIZE as i64) - 1).await?;
+        let _: () = pipeline.expire(&key, AUTHOR_TIMELINE_TTL_SECONDS, None).await?;
+        pipeline.last().await
+    }
+
+    async fn delete_author_post(&self, author_id: &Uuid, post_id: &Uuid) -> Result<(), Error> {
+        self.pool.next().zrem(self.get_author_key(author_id), post_id.to_string()).await
+    }
+
+    async fn get_author_timeline(&self, author_id: Uuid, page: FeedPage) -> Result<Vec<String>, Error> {
+        self.get_page(self.get_author_key(&author_id), page).await
+    }
+
+    async fn save_author_timeline(&self, author_id: Uuid, posts: &Vec<Post>) -> Result<(), Error> {
+        let key = self.get_author_key(&author_id);
+        let entries: Vec<(f64, String)> = posts
+            .iter()
+            .map(|p| (FeedCursor::of(p).score(), p.id.to_string()))
+            .collect();
+        // The timeline is rebuilt from scratch, so entries missed while it was not tracked go away
+        let pipeline = self.pool.next().pipeline();
+        let _: () = pipeline.del(&key).await?;
+        if !entries.is_empty() {
+            let _: () = pipeline.zadd(&key, None, None, false, false, entries).await?;
+            let _: () = pipeline.expire(&key, AUTHOR_TIMELINE_TTL_SECONDS, None).await?;
+        }
+        pipeline.last().await
+    }
+
+    async fn author_timeline_size(&self, author_id: Uuid) -> Result<u64, Error> {
+        self.pool.next().zcard(self.get_author_key(&author_id)).await
+    }
+
+    async fn mark_author_timeline_exists(&self, author_id: Uuid) -> Result<(), Error> {
+        self.pool.next().set(
+            &self.get_author_mark_key(&author_id),
+            "1",
+            Some(Expiration::EX(AUTHOR_MARK_TTL_SECONDS)),
+            None,
+            false
+        ).await
+    }
+
+    async fn check_author_timeline_exists(&self, author_id: Uuid) -> Result<bool, Error> {
+        self.pool.next().exists::<i64, _>(&self.get_author_mark_key(&author_id)).await.map(|count| count > 0)
+    }
+}
@@ -1,24 +1,29 @@
 use uuid::Uuid;
-use crate::modules::post::{model::Post, repository::PostRepository, service_provider::{PostService, PostServiceError}};
+use crate::modules::post::{cursor::FeedPage, model::{Post, PostRevision, Visibility}, repository::PostRepository, service_provider::{PostService, PostServiceError}};
 use async_trait::async_trait; 
 
 pub struct PostServiceImpl<R> 
 where
     R: PostRepository {
-    repository: R
+    repository: R,
+    celebrity_threshold: u64,
+    /// Days a deleted post stays restorable
+    restore_window_days: i32,
 } 
 
 impl <R> PostServiceImpl<R>
 where 
     R: PostRepository {    
-    pub fn new(repository: R) -> Self {
+    pub fn new(repository: R, celebrity_threshold: u64, restore_window_days: i32) -> Self {
         PostServiceImpl { 
-            repository
+            repository,
+            celebrity_threshold,
+            restore_window_days
         }
     }
 
-    async fn fetch_from_db(&self, user_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<Post>, PostServiceError> {
-        let feed = self.repository.feed(user_id, limit, offset).await?;        
+    async fn fetch_from_db(&self, user_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostServiceError> {
+        let feed = self.repository.feed(user_id, page).await?;        
         Ok(feed)
     }            
 }
@@ -27,23 +32,63 @@ where
 impl <R> PostService for PostServiceImpl<R> 
 where
     R: PostRepository + Send + Sync {    
-    async fn create(&self, user_id: Uuid, text: &String) -> Result<Post, PostServiceError> {                
-        Ok(self.repository.create(user_id, text).await?)
+    async fn create(&self, user_id: Uuid, text: &String, visibility: Visibility, post_id: Option<Uuid>) -> Result<Post, PostServiceError> {                
+        Ok(self.repository.create(user_id, text, visibility, post_id).await?)
     }
 
-    async fn update(&self, user_id: Uuid, post_id: Uuid, text: &String) -> Result<Post, PostServiceError> {
-        Ok(self.repository.update(user_id, post_id, text).await?)
+    async fn repost(&self, user_id: Uuid, post_id: Uuid, text: &Option<String>) -> Result<Post, PostServiceError> {
+        Ok(self.repository.repost(user_id, post_id, text).await?)
+    }
+
+    async fn update(&self, user_id: Uuid, post_id: Uuid, text: &String, visibility: Option<Visibility>) -> Result<Post, PostServiceError> {
+        Ok(self.repository.update(user_id, post_id, text, visibility).await?)
     }
 
     async fn delete(&self, user_id: Uuid, post_id: Uuid) -> Result<(), PostServiceError> {        
         Ok(self.repository.delete(user_id, post_id).await?)
     }
+
+    async fn restore(&self, user_id: Uuid, post_id: Uuid) -> Result<Post, PostServiceError> {
+        Ok(self.repository.restore(user_id, post_id, self.restore_window_days).await?)
+    }
+  
+    async fn hide(&self, post_id: Uuid) -> Result<Post, PostServiceError> {
+        Ok(self.repository.set_hidden(post_id, true).await?)
+    }
+
+    async fn unhide(&self, post_id: Uuid) -> Result<Post, PostServiceError> {
+        Ok(self.repository.set_hidden(post_id, false).await?)
+    }
   
-    async fn get(&self, post_id: Uuid) -> Result<Post, PostServiceError> {         
-        Ok(self.repository.get(post_id).await?)
+    async fn get(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Post, PostServiceError> {         
+        Ok(self.repository.get(viewer_id, post_id).await?)
+    }
+
+    async fn get_many(&self, viewer_id: Uuid, post_ids: &Vec<Uuid>) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.repository.get_many(viewer_id, post_ids).await?)
+    }
+
+    async fn reposts(&self, post_id: Uuid) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.repository.reposts(post_id).await?)
+    }
+
+    async fn revisions(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Vec<PostRevision>, PostServiceError> {
+        Ok(self.repository.revisions(viewer_id, post_id).await?)
+    }
+
+    async fn feed(&self, user_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostServiceError> {                  
+        Ok(self.fetch_from_db(user_id, page).await?)      
+    }
+
+    async fn tag_feed(&self, viewer_id: Uuid, tag: &String, page: FeedPage) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.repository.tag_feed(viewer_id, tag, page).await?)
+    }
+
+    async fn author_feed(&self, viewer_id: Uuid, author_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.repository.author_feed(viewer_id, author_id, page).await?)
     }
 
-    async fn feed(&self, user_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<Post>, PostServiceError> {                  
-        Ok(self.fetch_from_db(user_id, limit, offset).await?)      
+    async fn followed_celebrities(&self, user_id: Uuid) -> Result<Vec<Uuid>, PostServiceError> {
+        Ok(self.repository.followed_celebrities(user_id, self.celebrity_threshold as i64).await?)
     }
-}    
+}
\ No newline at end of file
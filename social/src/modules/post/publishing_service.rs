@@ -1,24 +1,25 @@
 use uuid::Uuid;
-use crate::modules::post::event::DomainEvent;
-use crate::modules::post::model::Post;
-use crate::modules::post::rabbitmq::RabbitPublisher;
+use crate::modules::post::event::{DomainEvent, EventPublisher};
+use crate::modules::post::cursor::FeedPage;
+use crate::modules::post::model::{Post, PostRevision, Visibility};
 use crate::modules::post::service_provider::{PostService, PostServiceError};
 use async_trait::async_trait;
 use std::sync::Arc; 
 
+/// Publishes events after the write succeeds, for repositories without an outbox
 pub struct PublishingServiceImpl <S> 
 where 
     S: PostService {
-    rabbit_publisher: Arc<RabbitPublisher>,
+    publisher: Arc<dyn EventPublisher + Send + Sync>,
     service: S,
 }
 
 impl <S> PublishingServiceImpl<S>
 where 
     S: PostService + Send + Sync {
-    pub fn new(service: S, rabbit_publisher: Arc<RabbitPublisher>) -> Self {
+    pub fn new(service: S, publisher: Arc<dyn EventPublisher + Send + Sync>) -> Self {
         PublishingServiceImpl { 
-            rabbit_publisher,
+            publisher,
             service
         }
     }
@@ -28,10 +29,10 @@ where
 impl <S> PostService for PublishingServiceImpl<S>
 where 
     S: PostService + Send + Sync {
-    async fn create(&self, user_id: Uuid, text: &String) -> Result<Post, PostServiceError> {        
-        let post = self.service.create(user_id, text).await?; 
+    async fn create(&self, user_id: Uuid, text: &String, visibility: Visibility, post_id: Option<Uuid>) -> Result<Post, PostServiceError> {        
+        let post = self.service.create(user_id, text, visibility, post_id).await?; 
         tracing::info!("Create post at PublishingService");
-        let _ = self.rabbit_publisher.publish(
+        let _ = self.publisher.publish(
             &DomainEvent::PostCreated {
                 user_id,
                 post: post.clone(),                
@@ -40,9 +41,20 @@ where
         Ok(post)
     }
 
-    async fn update(&self, user_id: Uuid, post_id: Uuid, text: &String) -> Result<Post, PostServiceError> {                        
-        let post = self.service.update(user_id, post_id, text).await?;   
-        let _ = self.rabbit_publisher.publish(
+    async fn repost(&self, user_id: Uuid, post_id: Uuid, text: &Option<String>) -> Result<Post, PostServiceError> {
+        let post = self.service.repost(user_id, post_id, text).await?;
+        let _ = self.publisher.publish(
+            &DomainEvent::PostCreated {
+                user_id,
+                post: post.clone(),
+            }
+        ).await?;
+        Ok(post)
+    }
+
+    async fn update(&self, user_id: Uuid, post_id: Uuid, text: &String, visibility: Option<Visibility>) -> Result<Post, PostServiceError> {                        
+        let post = self.service.update(user_id, post_id, text, visibility).await?;   
+        let _ = self.publisher.publish(
             &DomainEvent::PostUpdated {
                 user_id,
                 post: post.clone(),                
@@ -52,21 +64,114 @@ where
     }
 
     async fn delete(&self, user_id: Uuid, post_id: Uuid) -> Result<(), PostServiceError> {
+        let reposts = self.service.reposts(post_id).await?;
         let _ = self.service.delete(user_id, post_id).await?;
-        let _ = self.rabbit_publisher.publish(
+        let _ = self.publisher.publish(
             &DomainEvent::PostDeleted {
                 user_id,
                 post_id
             }
         ).await?;     
+        for repost in reposts {
+            let _ = self.publisher.publish(
+                &DomainEvent::PostDeleted {
+                    user_id: repost.author_user_id,
+                    post_id: repost.id
+                }
+            ).await?;
+        }
         Ok(())
     }
 
-    async fn get(&self, post_id: Uuid) -> Result<Post, PostServiceError> {
-        Ok(self.service.get(post_id).await?)
+    async fn restore(&self, user_id: Uuid, post_id: Uuid) -> Result<Post, PostServiceError> {
+        let post = self.service.restore(user_id, post_id).await?;
+        let _ = self.publisher.publish(
+            &DomainEvent::PostCreated {
+                user_id,
+                post: post.clone(),
+            }
+        ).await?;
+        // Reposts were hidden together with the original and come back with it
+        for repost in self.service.reposts(post_id).await? {
+            let _ = self.publisher.publish(
+                &DomainEvent::PostCreated {
+                    user_id: repost.author_user_id,
+                    post: repost,
+                }
+            ).await?;
+        }
+        Ok(post)
     }
 
-    async fn feed(&self, user_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<Post>, PostServiceError> {
-        Ok(self.service.feed(user_id, limit, offset).await?)
+    async fn hide(&self, post_id: Uuid) -> Result<Post, PostServiceError> {
+        let reposts = self.service.reposts(post_id).await?;
+        let post = self.service.hide(post_id).await?;
+        // Listeners drop hidden posts from feeds and timelines the same way as deleted ones
+        let _ = self.publisher.publish(
+            &DomainEvent::PostDeleted {
+                user_id: post.author_user_id,
+                post_id
+            }
+        ).await?;
+        for repost in reposts {
+            let _ = self.publisher.publish(
+                &DomainEvent::PostDeleted {
+                    user_id: repost.author_user_id,
+                    post_id: repost.id
+                }
+            ).await?;
+        }
+        Ok(post)
     }
-}
+
+    async fn unhide(&self, post_id: Uuid) -> Result<Post, PostServiceError> {
+        let post = self.service.unhide(post_id).await?;
+        let _ = self.publisher.publish(
+            &DomainEvent::PostCreated {
+                user_id: post.author_user_id,
+                post: post.clone(),
+            }
+        ).await?;
+        for repost in self.service.reposts(post_id).await? {
+            let _ = self.publisher.publish(
+                &DomainEvent::PostCreated {
+                    user_id: repost.author_user_id,
+                    post: repost,
+                }
+            ).await?;
+        }
+        Ok(post)
+    }
+
+    async fn get(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Post, PostServiceError> {
+        Ok(self.service.get(viewer_id, post_id).await?)
+    }
+
+    async fn get_many(&self, viewer_id: Uuid, post_ids: &Vec<Uuid>) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.service.get_many(viewer_id, post_ids).await?)
+    }
+
+    async fn reposts(&self, post_id: Uuid) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.service.reposts(post_id).await?)
+    }
+
+    async fn revisions(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Vec<PostRevision>, PostServiceError> {
+        Ok(self.service.revisions(viewer_id, post_id).await?)
+    }
+
+    async fn feed(&self, user_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.service.feed(user_id, page).await?)
+    }
+
+    async fn tag_feed(&self, viewer_id: Uuid, tag: &String, page: FeedPage) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.service.tag_feed(viewer_id, tag, page).await?)
+    }
+
+    async fn author_feed(&self, viewer_id: Uuid, author_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostServiceError> {
+        Ok(self.service.author_feed(viewer_id, author_id, page).await?)
+    }
+
+    async fn followed_celebrities(&self, user_id: Uuid) -> Result<Vec<Uuid>, PostServiceError> {
+        Ok(self.service.followed_celebrities(user_id).await?)
+    }
+}
\ No newline at end of file
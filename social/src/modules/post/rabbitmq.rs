@@ -1,37 +1,162 @@
-use std::sync::Arc;
-use deadpool_lapin::{Pool, lapin::{BasicProperties, options::BasicPublishOptions}};
+use std::{env, sync::Arc, time::{Duration, Instant}};
+use async_trait::async_trait;
+use deadpool_lapin::{Pool, lapin::{BasicProperties, Channel, options::{BasicPublishOptions, ConfirmSelectOptions}, publisher_confirm::Confirmation}};
+use thiserror::Error;
+use tokio::sync::{Mutex, Semaphore};
+use uuid::Uuid;
+use crate::modules::common::metrics::publisher_metrics::{PublishOutcome, PublisherMetrics};
+use crate::modules::post::{event::{DomainEvent, EventPublisher}, followers::followers_service::declare_post_events};
 
-use crate::modules::post::event::DomainEvent;
+/// AMQP delivery mode of messages which survive a broker restart
+const PERSISTENT: u8 = 2;
 
+#[derive(Error, Debug)]
+pub enum PublishError {
+    #[error("Pool error: {0}")]
+    Pool(#[from] deadpool_lapin::PoolError),
+
+    #[error("AMQP error: {0}")]
+    Amqp(#[from] deadpool_lapin::lapin::Error),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("No confirm within {0:?}")]
+    Timeout(Duration),
+
+    #[error("Rejected by the broker")]
+    Nacked,
+
+    #[error("Unroutable: {0} {1}")]
+    Returned(u16, String),
+}
+
+impl PublishError {
+    /// The broker or the connection to it failed, so later events would most likely fail as well
+    pub fn is_broker_failure(&self) -> bool {
+        matches!(self, PublishError::Pool(_) | PublishError::Amqp(_) | PublishError::Timeout(_) | PublishError::Nacked)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PublisherConfig {
+    /// Upper bound of open confirm channels, also bounds concurrent publishes
+    pub channels: usize,
+    pub confirm_timeout: Duration,
+}
+
+impl PublisherConfig {
+    pub fn from_env() -> Self {
+        PublisherConfig {
+            channels: env::var("RABBITMQ_PUBLISHER_CHANNELS").ok().and_then(|v| v.parse().ok()).unwrap_or(8).max(1),
+            confirm_timeout: Duration::from_millis(env::var("RABBITMQ_CONFIRM_TIMEOUT_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(5000)),
+        }
+    }
+}
+
+/// Publishes to the exchange over long-lived channels in confirm mode.
+/// A publish completes once the broker has confirmed the message, which is persistent and mandatory.
+/// Every routing key is bound to a queue the publisher declares itself, so none is expected to be unroutable:
+/// a returned message means a broken topology and fails, the outbox gives up on it after its attempts
 pub struct RabbitPublisher {
     pool: Arc<Pool>,
     exchange: String,
+    config: PublisherConfig,
+    idle_channels: Mutex<Vec<Channel>>,
+    permits: Semaphore,
+    metrics: Arc<PublisherMetrics>,
 }
 
 impl RabbitPublisher {
-    pub fn new(pool: Arc<Pool>, exchange: String) -> Self {        
-        Self { pool, exchange }
+    pub fn new(pool: Arc<Pool>, exchange: String, config: PublisherConfig, metrics: Arc<PublisherMetrics>) -> Self {
+        Self {
+            pool,
+            exchange,
+            config,
+            idle_channels: Mutex::new(vec!()),
+            permits: Semaphore::new(config.channels),
+            metrics
+        }
     }
 
-    pub async fn publish(&self, event: &DomainEvent) -> Result<(), Box<dyn std::error::Error>> {
-        tracing::info!("Publishing event...");
+    /// Takes an idle channel which is still open or opens a new one
+    async fn acquire(&self) -> Result<Channel, PublishError> {
+        while let Some(channel) = self.idle_channels.lock().await.pop() {
+            if channel.status().connected() {
+                return Ok(channel);
+            }
+        }
         let conn = self.pool.get().await?;
         let channel = conn.create_channel().await?;
-        let payload = serde_json::to_vec(event)?;        
-        let routing_key = match event {
-            DomainEvent::PostCreated { .. } => "post.created",
-            DomainEvent::PostUpdated { .. } => "post.updated",
-            DomainEvent::PostDeleted { .. } => "post.deleted",
-        };
-        tracing::info!("Publishing event: {:?} by key: {:?}", event, routing_key);
-        channel.basic_publish(
+        channel.confirm_select(ConfirmSelectOptions::default()).await?;
+        // Publishing to a missing exchange would close the channel, so the exchange and its queues are declared up front
+        declare_post_events(&channel, &self.exchange).await?;
+        tracing::info!("Opened publisher channel {}", channel.id());
+        Ok(channel)
+    }
+
+    async fn release(&self, channel: Channel) {
+        self.idle_channels.lock().await.push(channel);
+    }
+
+    async fn publish_confirmed(&self, channel: &Channel, event: &DomainEvent) -> Result<(), PublishError> {
+        let payload = serde_json::to_vec(event)?;
+        let confirm = channel.basic_publish(
             &self.exchange,
-            routing_key,
-            BasicPublishOptions::default(),
+            event.routing_key(),
+            BasicPublishOptions { mandatory: true, ..BasicPublishOptions::default() },
             &payload,
-            BasicProperties::default(),
+            BasicProperties::default()
+                .with_delivery_mode(PERSISTENT)
+                // Identifies the message in the dead-letter queue, retried copies keep it
+                .with_message_id(Uuid::new_v4().to_string().into())
+                .with_content_type("application/json".into()),
         ).await?;
-        tracing::info!("Event published: {:?} by key: {:?}", event, routing_key);
-        Ok(())
+        let confirmation = tokio::time::timeout(self.config.confirm_timeout, confirm).await
+            .map_err(|_| PublishError::Timeout(self.config.confirm_timeout))??;
+        match confirmation {
+            // The broker confirms returned messages as well, the return comes with the ack
+            Confirmation::Ack(Some(returned)) | Confirmation::Nack(Some(returned)) =>
+                Err(PublishError::Returned(returned.reply_code, returned.reply_text.to_string())),
+            Confirmation::Nack(None) => Err(PublishError::Nacked),
+            Confirmation::Ack(None) | Confirmation::NotRequested => Ok(())
+        }
     }
-}
\ No newline at end of file
+
+    fn outcome(res: &Result<(), PublishError>) -> PublishOutcome {
+        match res {
+            Ok(()) => PublishOutcome::Acked,
+            Err(PublishError::Nacked) => PublishOutcome::Nacked,
+            Err(PublishError::Returned(..)) => PublishOutcome::Returned,
+            Err(PublishError::Timeout(_)) => PublishOutcome::Timeout,
+            Err(_) => PublishOutcome::Error
+        }
+    }
+}
+
+#[async_trait]
+impl EventPublisher for RabbitPublisher {
+    async fn publish(&self, event: &DomainEvent) -> Result<(), Box<dyn std::error::Error>> {
+        let _permit = self.permits.acquire().await?;
+        let started = Instant::now();
+        let res = match self.acquire().await {
+            Ok(channel) => {
+                let res = self.publish_confirmed(&channel, event).await;
+                // After a timeout or a channel error the confirm sequence of the channel is unknown
+                if matches!(res, Ok(()) | Err(PublishError::Nacked | PublishError::Returned(..))) {
+                    self.release(channel).await;
+                } else if channel.status().connected() {
+                    let _ = channel.close(200, "Discarded by publisher").await;
+                }
+                res
+            },
+            Err(e) => Err(e)
+        };
+        self.metrics.record(Self::outcome(&res), started.elapsed());
+        match &res {
+            Ok(()) => tracing::info!("Event published: {:?} by key: {:?}", event, event.routing_key()),
+            Err(e) => tracing::warn!("Failed to publish event by key {:?}: {}", event.routing_key(), e)
+        }
+        Ok(res?)
+    }
+}
@@ -0,0 +1,155 @@
+use openapi::apis::reaction::{Reaction, PostIdReactionPutResponse, PostIdReactionDeleteResponse, PostIdReactionsGetResponse};
+use axum_extra::headers::Host;
+use axum_extra::extract::CookieJar;
+use axum::http::Method;
+use async_trait::async_trait; 
+use openapi::models::{self};
+use crate::modules::post::reaction::{model::{self, PostReaction, ReactionSummary}, repository::ReactionRepositoryError, service_provider::ReactionServiceError};
+use crate::modules::auth::auth;
+use crate::Application;
+use uuid::Uuid;
+
+#[async_trait]
+impl Reaction for Application {
+    type Claims = auth::Claims;
+
+    async fn post_id_reaction_put(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostIdReactionPutPathParams,
+        body: &Option<models::PostIdReactionPutRequest>,
+    ) -> Result<PostIdReactionPutResponse, ()> {
+        let post_id = match Uuid::parse_str(&path_params.id) {
+            Ok(id) => id,
+            Err(_) => return Ok(PostIdReactionPutResponse::Status400)
+        };
+        let reaction = match body {
+            Some(body) => from_reaction_dto(&body.reaction),
+            None => return Ok(PostIdReactionPutResponse::Status400)
+        };
+        match self.state.reaction_service.react(claims.user_id, post_id, reaction).await {
+            Ok(()) => Ok(PostIdReactionPutResponse::Status200),
+            Err(ReactionServiceError::Database(ReactionRepositoryError::NotFound(_))) => Ok(PostIdReactionPutResponse::Status404),
+            Err(e) => {
+                tracing::error!("React to post error: {:?}", e);
+                Ok(PostIdReactionPutResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn post_id_reaction_delete(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostIdReactionDeletePathParams,
+    ) -> Result<PostIdReactionDeleteResponse, ()> {
+        let post_id = match Uuid::parse_str(&path_params.id) {
+            Ok(id) => id,
+            Err(_) => return Ok(PostIdReactionDeleteResponse::Status400)
+        };
+        match self.state.reaction_service.unreact(claims.user_id, post_id).await {
+            Ok(()) => Ok(PostIdReactionDeleteResponse::Status200),
+            Err(e) => {
+                tracing::error!("Remove post reaction error: {:?}", e);
+                Ok(PostIdReactionDeleteResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn post_id_reactions_get(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::PostIdReactionsGetPathParams,
+        query_params: &models::PostIdReactionsGetQueryParams,
+    ) -> Result<PostIdReactionsGetResponse, ()> {
+        let post_id = match Uuid::parse_str(&path_params.id) {
+            Ok(id) => id,
+            Err(_) => return Ok(PostIdReactionsGetResponse::Status400)
+        };
+        match self.state.reaction_service.list(claims.user_id, post_id, query_params.limit, query_params.offset).await {
+            Ok(reactions) => Ok(PostIdReactionsGetResponse::Status200(to_post_reaction_dtos(reactions))),
+            Err(ReactionServiceError::Database(ReactionRepositoryError::NotFound(_))) => Ok(PostIdReactionsGetResponse::Status400),
+            Err(e) => {
+                tracing::error!("List post reactions error: {:?}", e);
+                Ok(PostIdReactionsGetResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+}
+
+fn from_reaction_dto(reaction: &models::ReactionType) -> model::Reaction {
+    match reaction {
+        models::ReactionType::Like => model::Reaction::Like,
+        models::ReactionType::Love => model::Reaction::Love,
+        models::ReactionType::Laugh => model::Reaction::Laugh,
+        models::ReactionType::Wow => model::Reaction::Wow,
+        models::ReactionType::Sad => model::Reaction::Sad,
+        models::ReactionType::Angry => model::Reaction::Angry,
+    }
+}
+
+fn to_reaction_dto(reaction: model::Reaction) -> models::ReactionType {
+    match reaction {
+        model::Reaction::Like => models::ReactionType::Like,
+        model::Reaction::Love => models::ReactionType::Love,
+        model::Reaction::Laugh => models::ReactionType::Laugh,
+        model::Reaction::Wow => models::ReactionType::Wow,
+        model::Reaction::Sad => models::ReactionType::Sad,
+        model::Reaction::Angry => models::ReactionType::Angry,
+    }
+}
+
+pub fn to_reaction_count_dtos(summary: &ReactionSummary) -> Vec<models::ReactionCount> {
+    model::Reaction::ALL.into_iter()
+        .filter_map(|reaction| summary.counts.get(&reaction)
+            .filter(|count| **count > 0)
+            .map(|count| models::ReactionCount {
+                reaction: to_reaction_dto(reaction),
+                count: *count
+            }))
+        .collect()
+}
+
+pub fn to_my_reaction_dto(summary: &ReactionSummary) -> Option<models::ReactionType> {
+    summary.my_reaction.map(to_reaction_dto)
+}
+
+fn to_post_reaction_dto(reaction: PostReaction) -> models::PostReaction {
+    models::PostReaction {
+        user_id: reaction.user_id.to_string(),
+        reaction: to_reaction_dto(reaction.reaction)
+    }
+}
+
+fn to_post_reaction_dtos(reactions: Vec<PostReaction>) -> Vec<models::PostReaction> {
+    reactions.into_iter().map(to_post_reaction_dto).collect()
+}
@@ -0,0 +1,75 @@
+use std::{collections::HashMap, sync::{Arc, RwLock}};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use async_trait::async_trait;
+use crate::modules::post::{in_memory_repository::InMemoryPostRepository, repository::PostRepository};
+use crate::modules::post::reaction::{model::{PostReaction, Reaction, ReactionChange}, repository::{ReactionRepository, ReactionRepositoryError}};
+
+/// `ReactionRepository` over process-local `post_reactions` rows keyed by `(post_id, user_id)`, for the dev profile.
+/// Posts are looked up in the in-memory post repository and counts are computed from the rows
+#[derive(Clone)]
+pub struct InMemoryReactionRepository {
+    posts: InMemoryPostRepository,
+    reactions: Arc<RwLock<HashMap<(Uuid, Uuid), (Reaction, DateTime<Utc>)>>>,
+}
+
+impl InMemoryReactionRepository {
+    pub fn new(posts: InMemoryPostRepository) -> Self {
+        InMemoryReactionRepository {
+            posts,
+            reactions: Arc::new(RwLock::new(HashMap::new()))
+        }
+    }
+}
+
+#[async_trait]
+impl ReactionRepository for InMemoryReactionRepository {
+
+    async fn set(&self, user_id: Uuid, post_id: Uuid, reaction: Reaction) -> Result<ReactionChange, ReactionRepositoryError> {
+        let author_user_id = self.posts.get(user_id, post_id).await
+            .map_err(|_| ReactionRepositoryError::NotFound(format!("Post {}", post_id)))?
+            .author_user_id;
+        let mut reactions = self.reactions.write().unwrap();
+        let previous = reactions.get(&(post_id, user_id)).map(|(previous, _)| *previous);
+        if previous != Some(reaction) {
+            reactions.insert((post_id, user_id), (reaction, Utc::now()));
+        }
+        Ok(ReactionChange { author_user_id, previous })
+    }
+
+    async fn remove(&self, user_id: Uuid, post_id: Uuid) -> Result<Option<Reaction>, ReactionRepositoryError> {
+        Ok(self.reactions.write().unwrap().remove(&(post_id, user_id)).map(|(reaction, _)| reaction))
+    }
+
+    async fn counts(&self, post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, HashMap<Reaction, i64>>, ReactionRepositoryError> {
+        let mut counts: HashMap<Uuid, HashMap<Reaction, i64>> = HashMap::new();
+        for ((post_id, _), (reaction, _)) in self.reactions.read().unwrap().iter() {
+            if post_ids.contains(post_id) {
+                *counts.entry(*post_id).or_default().entry(*reaction).or_default() += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn user_reactions(&self, user_id: Uuid, post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, Reaction>, ReactionRepositoryError> {
+        let reactions = self.reactions.read().unwrap();
+        Ok(post_ids.iter()
+            .filter_map(|post_id| reactions.get(&(*post_id, user_id)).map(|(reaction, _)| (*post_id, *reaction)))
+            .collect())
+    }
+
+    async fn list(&self, viewer_id: Uuid, post_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<PostReaction>, ReactionRepositoryError> {
+        if self.posts.get(viewer_id, post_id).await.is_err() {
+            return Err(ReactionRepositoryError::NotFound(format!("Post {}", post_id)));
+        }
+        let mut found: Vec<PostReaction> = self.reactions.read().unwrap().iter()
+            .filter(|((reacted_post_id, _), _)| *reacted_post_id == post_id)
+            .map(|((_, user_id), (reaction, timestamp))| PostReaction { user_id: *user_id, reaction: *reaction, timestamp: *timestamp })
+            .collect();
+        found.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        Ok(found.into_iter()
+            .skip(offset.unwrap_or(0) as usize)
+            .take(limit.map(|v| v as usize).unwrap_or(usize::MAX))
+            .collect())
+    }
+}
@@ -0,0 +1,7 @@
+pub mod controller;
+pub mod model;
+mod repository;
+mod in_memory_repository;
+mod reaction_cache;
+mod reaction_service;
+pub mod service_provider;
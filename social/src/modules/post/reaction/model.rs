@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Reaction {
+    Like,
+    Love,
+    Laugh,
+    Wow,
+    Sad,
+    Angry
+}
+
+impl Reaction {
+    pub const ALL: [Reaction; 6] = [Reaction::Like, Reaction::Love, Reaction::Laugh, Reaction::Wow, Reaction::Sad, Reaction::Angry];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Reaction::Like => "like",
+            Reaction::Love => "love",
+            Reaction::Laugh => "laugh",
+            Reaction::Wow => "wow",
+            Reaction::Sad => "sad",
+            Reaction::Angry => "angry",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Reaction> {
+        Reaction::ALL.into_iter().find(|r| r.as_str() == value)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PostReaction {
+    pub user_id: Uuid,
+    pub reaction: Reaction,
+    pub timestamp: chrono::DateTime<chrono::Utc>
+}
+
+#[derive(Debug, Clone)]
+pub struct ReactionChange {
+    pub author_user_id: Uuid,
+    pub previous: Option<Reaction>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReactionSummary {
+    pub counts: HashMap<Reaction, i64>,
+    pub my_reaction: Option<Reaction>,
+}
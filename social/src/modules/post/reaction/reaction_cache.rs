@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use fred::prelude::Pool;
+use uuid::Uuid;
+use fred::prelude::*;
+use fred::error::Error;
+use std::sync::Arc;
+use async_trait::async_trait; 
+use mockall::automock;
+use crate::modules::post::reaction::model::Reaction;
+
+const REACTION_CACHE_TTL_SECONDS: i64 = 86400;
+const NO_REACTION: &str = "none";
+const APPLY_CHANGE_SCRIPT: &str = r#"
+if redis.call('EXISTS', KEYS[1]) == 1 then
+    if ARGV[1] ~= '' then redis.call('HINCRBY', KEYS[1], ARGV[1], -1) end
+    if ARGV[2] ~= '' then redis.call('HINCRBY', KEYS[1], ARGV[2], 1) end
+end
+return 0
+"#;
+
+#[automock]
+#[async_trait]
+pub trait ReactionCache {
+    async fn get_counts(&self, post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, HashMap<Reaction, i64>>, Error>;
+    async fn save_counts(&self, counts: &HashMap<Uuid, HashMap<Reaction, i64>>) -> Result<(), Error>;
+    async fn apply_change(&self, post_id: &Uuid, previous: Option<Reaction>, current: Option<Reaction>) -> Result<(), Error>;
+    async fn get_user_reactions(&self, user_id: &Uuid, post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, Option<Reaction>>, Error>;
+    async fn save_user_reactions(&self, user_id: &Uuid, reactions: &HashMap<Uuid, Option<Reaction>>) -> Result<(), Error>;
+}
+
+pub struct ReactionCacheImpl {
+    pool: Arc<Pool>
+}
+
+impl ReactionCacheImpl {
+    pub fn new(pool: Arc<Pool>) -> Self {
+        ReactionCacheImpl { pool }
+    }
+
+    fn get_counts_key(&self, post_id: &Uuid) -> String {
+        format!("highload/post/reactions/{}", post_id)
+    }
+
+    /// Reactions of one user share a hash tag, so reading them for a page of posts is a single-slot MGET
+    fn get_user_reaction_key(&self, user_id: &Uuid, post_id: &Uuid) -> String {
+        format!("highload/post/reactions/user/{{{}}}/{}", user_id, post_id)
+    }
+}
+
+#[async_trait]
+impl ReactionCache for ReactionCacheImpl {
+
+    async fn get_counts(&self, post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, HashMap<Reaction, i64>>, Error> {
+        if post_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let pipeline = self.pool.next().pipeline();
+        for post_id in post_ids {
+            let _: () = pipeline.hgetall(self.get_counts_key(post_id)).await?;
+        }
+        let results: Vec<HashMap<String, i64>> = pipeline.all().await?;
+        // Counters are always stored for every reaction kind, so an empty hash means a miss
+        Ok(post_ids.iter()
+            .zip(results)
+            .filter(|(_, fields)| !fields.is_empty())
+            .map(|(post_id, fields)| {
+                let counts = fields.into_iter()
+                    .filter_map(|(reaction, count)| Reaction::parse(&reaction).map(|r| (r, count)))
+                    .collect();
+                (*post_id, counts)
+            })
+            .collect())
+    }
+
+    async fn save_counts(&self, counts: &HashMap<Uuid, HashMap<Reaction, i64>>) -> Result<(), Error> {
+        if counts.is_empty() {
+            return Ok(());
+        }
+        let pipeline = self.pool.next().pipeline();
+        for (post_id, post_counts) in counts {
+            let key = self.get_counts_key(post_id);
+            let fields: Vec<(&str, i64)> = Reaction::ALL.iter()
+                .map(|r| (r.as_str(), *post_counts.get(r).unwrap_or(&0)))
+                .collect();
+            let _: () = pipeline.hset(&key, fields).await?;
+            let _: () = pipeline.expire(&key, REACTION_CACHE_TTL_SECONDS, None).await?;
+        }
+        pipeline.last().await
+    }
+
+    async fn apply_change(&self, post_id: &Uuid, previous: Option<Reaction>, current: Option<Reaction>) -> Result<(), Error> {
+        let args: Vec<&str> = vec!(
+            previous.map(|r| r.as_str()).unwrap_or(""),
+            current.map(|r| r.as_str()).unwrap_or("")
+        );
+        let _: i64 = self.pool.next().eval(APPLY_CHANGE_SCRIPT, vec!(self.get_counts_key(post_id)), args).await?;
+        Ok(())
+    }
+
+    async fn get_user_reactions(&self, user_id: &Uuid, post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, Option<Reaction>>, Error> {
+        if post_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let keys: Vec<String> = post_ids.iter().map(|post_id| self.get_user_reaction_key(user_id, post_id)).collect();
+        let values: Vec<Option<String>> = self.pool.next().mget(keys).await?;
+        Ok(post_ids.iter()
+            .zip(values)
+            .filter_map(|(post_id, value)| value.map(|v| (*post_id, Reaction::parse(&v))))
+            .collect())
+    }
+
+    async fn save_user_reactions(&self, user_id: &Uuid, reactions: &HashMap<Uuid, Option<Reaction>>) -> Result<(), Error> {
+        if reactions.is_empty() {
+            return Ok(());
+        }
+        let pipeline = self.pool.next().pipeline();
+        for (post_id, reaction) in reactions {
+            let _: () = pipeline.set(
+                self.get_user_reaction_key(user_id, post_id),
+                reaction.map(|r| r.as_str()).unwrap_or(NO_REACTION),
+                Some(Expiration::EX(REACTION_CACHE_TTL_SECONDS)),
+                None,
+                false
+            ).await?;
+        }
+        pipeline.last().await
+    }
+}
+
+/// Cache of the dev profile which holds nothing, every read misses and reactions are served by the database
+pub struct NoReactionCache;
+
+#[async_trait]
+impl ReactionCache for NoReactionCache {
+
+    async fn get_counts(&self, _post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, HashMap<Reaction, i64>>, Error> {
+        Ok(HashMap::new())
+    }
+
+    async fn save_counts(&self, _counts: &HashMap<Uuid, HashMap<Reaction, i64>>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn apply_change(&self, _post_id: &Uuid, _previous: Option<Reaction>, _current: Option<Reaction>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn get_user_reactions(&self, _user_id: &Uuid, _post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, Option<Reaction>>, Error> {
+        Ok(HashMap::new())
+    }
+
+    async fn save_user_reactions(&self, _user_id: &Uuid, _reactions: &HashMap<Uuid, Option<Reaction>>) -> Result<(), Error> {
+        Ok(())
+    }
+}
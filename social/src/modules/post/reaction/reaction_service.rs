@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+use serde::Serialize;
+use async_trait::async_trait; 
+use crate::modules::common::{ext::extensions::ResultExt, ws::ws_manager::WebSocketManager};
+use crate::modules::post::reaction::{model::{PostReaction, Reaction, ReactionSummary}, reaction_cache::ReactionCache, repository::ReactionRepository, service_provider::{ReactionService, ReactionServiceError}};
+
+#[derive(Debug, Clone, Serialize)]
+enum ReactionEvent {
+    React
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ReactionNotification {
+    pub event: ReactionEvent,
+    pub post_id: Uuid,
+    pub user_id: Uuid,
+    pub reaction: Reaction
+}
+
+pub struct ReactionServiceImpl<R, C>
+where 
+    R: ReactionRepository,
+    C: ReactionCache {
+    repository: R,
+    cache: C,
+    ws_manager: Arc<WebSocketManager>
+}
+
+impl <R, C> ReactionServiceImpl<R, C>
+where 
+    R: ReactionRepository + Send + Sync,
+    C: ReactionCache + Send + Sync {
+    pub fn new(repository: R, cache: C, ws_manager: Arc<WebSocketManager>) -> Self {
+        ReactionServiceImpl { 
+            repository, 
+            cache, 
+            ws_manager 
+        }
+    }
+
+    async fn fetch_counts(&self, post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, HashMap<Reaction, i64>>, ReactionServiceError> {
+        let mut counts = self.cache.get_counts(post_ids).await
+            .warn("Reading reaction counts from cache failed".to_string())
+            .unwrap_or_default();
+        let missing: Vec<Uuid> = post_ids.iter().filter(|id| !counts.contains_key(id)).cloned().collect();
+        if !missing.is_empty() {
+            let mut loaded = self.repository.counts(&missing).await?;
+            for post_id in &missing {
+                loaded.entry(*post_id).or_default();
+            }
+            self.cache.save_counts(&loaded).await.warn("Saving reaction counts to cache failed".to_string());
+            counts.extend(loaded);
+        }
+        Ok(counts)
+    }
+
+    async fn fetch_user_reactions(&self, user_id: Uuid, post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, Option<Reaction>>, ReactionServiceError> {
+        let mut reactions = self.cache.get_user_reactions(&user_id, post_ids).await
+            .warn("Reading user reactions from cache failed".to_string())
+            .unwrap_or_default();
+        let missing: Vec<Uuid> = post_ids.iter().filter(|id| !reactions.contains_key(id)).cloned().collect();
+        if !missing.is_empty() {
+            let found = self.repository.user_reactions(user_id, &missing).await?;
+            let loaded: HashMap<Uuid, Option<Reaction>> = missing.iter()
+                .map(|post_id| (*post_id, found.get(post_id).cloned()))
+                .collect();
+            self.cache.save_user_reactions(&user_id, &loaded).await.warn("Saving user reactions to cache failed".to_string());
+            reactions.extend(loaded);
+        }
+        Ok(reactions)
+    }
+}
+
+#[async_trait]
+impl <R, C> ReactionService for ReactionServiceImpl<R, C>
+where 
+    R: ReactionRepository + Send + Sync,
+    C: ReactionCache + Send + Sync {
+
+    async fn react(&self, user_id: Uuid, post_id: Uuid, reaction: Reaction) -> Result<(), ReactionServiceError> {
+        let change = self.repository.set(user_id, post_id, reaction).await?;
+        if change.previous == Some(reaction) {
+            return Ok(());
+        }
+        self.cache.apply_change(&post_id, change.previous, Some(reaction)).await.warn("Updating reaction counts in cache failed".to_string());
+        self.cache.save_user_reactions(&user_id, &HashMap::from([(post_id, Some(reaction))])).await.warn("Saving user reaction to cache failed".to_string());
+        if change.author_user_id != user_id {
+            let _ = self.ws_manager.send_to_users(
+                &[change.author_user_id], 
+                &ReactionNotification {
+                    event: ReactionEvent::React,
+                    post_id,
+                    user_id,
+                    reaction
+                }
+            );
+        }
+        Ok(())
+    }
+
+    async fn unreact(&self, user_id: Uuid, post_id: Uuid) -> Result<(), ReactionServiceError> {
+        if let Some(previous) = self.repository.remove(user_id, post_id).await? {
+            self.cache.apply_change(&post_id, Some(previous), None).await.warn("Updating reaction counts in cache failed".to_string());
+        }
+        self.cache.save_user_reactions(&user_id, &HashMap::from([(post_id, None)])).await.warn("Saving user reaction to cache failed".to_string());
+        Ok(())
+    }
+
+    async fn list(&self, viewer_id: Uuid, post_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<PostReaction>, ReactionServiceError> {
+        Ok(self.repository.list(viewer_id, post_id, limit, offset).await?)
+    }
+
+    async fn summaries(&self, viewer_id: Uuid, post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, ReactionSummary>, ReactionServiceError> {
+        let mut counts = self.fetch_counts(post_ids).await?;
+        let mine = self.fetch_user_reactions(viewer_id, post_ids).await?;
+        Ok(post_ids.iter()
+            .map(|post_id| (*post_id, ReactionSummary {
+                counts: counts.remove(post_id).unwrap_or_default(),
+                my_reaction: mine.get(post_id).cloned().flatten()
+            }))
+            .collect())
+    }
+}
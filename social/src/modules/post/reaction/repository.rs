@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use uuid::Uuid;
+use deadpool_postgres::{Pool, Transaction};
+use thiserror::Error;
+use std::sync::Arc;
+use async_trait::async_trait; 
+use mockall::automock;
+use crate::modules::post::{reaction::model::{PostReaction, Reaction, ReactionChange}, repository::visible_to};
+
+#[derive(Error, Debug)]
+pub enum ReactionRepositoryError {
+    #[error("Database error: {0}")]
+    Database(#[from] tokio_postgres::Error),
+    
+    #[error("Pool error: {0}")]
+    Pool(#[from] deadpool_postgres::PoolError),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+}
+
+#[automock]
+#[async_trait]
+pub trait ReactionRepository {
+    async fn set(&self, user_id: Uuid, post_id: Uuid, reaction: Reaction) -> Result<ReactionChange, ReactionRepositoryError>;
+    async fn remove(&self, user_id: Uuid, post_id: Uuid) -> Result<Option<Reaction>, ReactionRepositoryError>;
+    async fn counts(&self, post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, HashMap<Reaction, i64>>, ReactionRepositoryError>;
+    async fn user_reactions(&self, user_id: Uuid, post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, Reaction>, ReactionRepositoryError>;
+    async fn list(&self, viewer_id: Uuid, post_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<PostReaction>, ReactionRepositoryError>;
+}
+
+pub struct ReactionRepositoryImpl {
+    pool: Arc<Pool>
+}
+
+impl ReactionRepositoryImpl {
+    pub fn new(pool: Arc<Pool>) -> Self {
+        ReactionRepositoryImpl { pool }
+    }
+}
+
+async fn adjust_count(tx: &Transaction<'_>, post_id: Uuid, reaction: Reaction, delta: i64) -> Result<(), ReactionRepositoryError> {
+    tx.execute(
+        "INSERT INTO post_reaction_counts (post_id, reaction, count) VALUES ($1, $2, $3) 
+            ON CONFLICT (post_id, reaction) DO UPDATE SET count = post_reaction_counts.count + EXCLUDED.count",
+        &[&post_id, &reaction.as_str(), &delta]
+    ).await?;
+    Ok(())
+}
+
+#[async_trait]
+impl ReactionRepository for ReactionRepositoryImpl {
+
+    async fn set(&self, user_id: Uuid, post_id: Uuid, reaction: Reaction) -> Result<ReactionChange, ReactionRepositoryError> {
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let query = format!(
+            "SELECT p.user_id FROM posts p WHERE p.id=$1 AND p.deleted_at IS NULL AND p.hidden_at IS NULL AND {}", 
+            visible_to("$2")
+        );
+        let author_user_id: Uuid = tx.query_opt(query.as_str(), &[&post_id, &user_id]).await?
+            .ok_or(ReactionRepositoryError::NotFound(format!("Post {}", post_id)))?
+            .get("user_id");
+        // Inserting first locks the row even when the user has not reacted yet,
+        // so of concurrent first reactions of the user only one counts as new
+        let inserted = tx.execute(
+            "INSERT INTO post_reactions (post_id, user_id, reaction) VALUES ($1, $2, $3) 
+                ON CONFLICT (post_id, user_id) DO NOTHING",
+            &[&post_id, &user_id, &reaction.as_str()]
+        ).await? > 0;
+        let previous = if inserted {
+            None
+        } else {
+            tx.query_opt(
+                "SELECT reaction FROM post_reactions WHERE post_id=$1 AND user_id=$2 FOR UPDATE", 
+                &[&post_id, &user_id]
+            ).await?
+                .and_then(|row| Reaction::parse(row.get("reaction")))
+        };
+        if previous != Some(reaction) {
+            if !inserted {
+                // Also inserts the row again if a concurrent removal deleted it
+                tx.execute(
+                    "INSERT INTO post_reactions (post_id, user_id, reaction) VALUES ($1, $2, $3) 
+                        ON CONFLICT (post_id, user_id) DO UPDATE SET reaction = EXCLUDED.reaction, created_at = NOW()",
+                    &[&post_id, &user_id, &reaction.as_str()]
+                ).await?;
+            }
+            if let Some(previous) = previous {
+                adjust_count(&tx, post_id, previous, -1).await?;
+            }
+            adjust_count(&tx, post_id, reaction, 1).await?;
+        }
+        tx.commit().await?;
+        Ok(ReactionChange { author_user_id, previous })
+    }
+
+    async fn remove(&self, user_id: Uuid, post_id: Uuid) -> Result<Option<Reaction>, ReactionRepositoryError> {
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let removed = tx.query_opt(
+            "DELETE FROM post_reactions WHERE post_id=$1 AND user_id=$2 RETURNING reaction", 
+            &[&post_id, &user_id]
+        ).await?
+            .and_then(|row| Reaction::parse(row.get("reaction")));
+        if let Some(reaction) = removed {
+            adjust_count(&tx, post_id, reaction, -1).await?;
+        }
+        tx.commit().await?;
+        Ok(removed)
+    }
+
+    async fn counts(&self, post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, HashMap<Reaction, i64>>, ReactionRepositoryError> {
+        let res = self.pool.get().await?.query(
+            "SELECT post_id, reaction, count FROM post_reaction_counts WHERE post_id = ANY($1) AND count > 0", 
+            &[post_ids]
+        ).await?;
+        let mut counts: HashMap<Uuid, HashMap<Reaction, i64>> = HashMap::new();
+        for row in res {
+            if let Some(reaction) = Reaction::parse(row.get("reaction")) {
+                counts.entry(row.get("post_id")).or_default().insert(reaction, row.get("count"));
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn user_reactions(&self, user_id: Uuid, post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, Reaction>, ReactionRepositoryError> {
+        let res = self.pool.get().await?.query(
+            "SELECT post_id, reaction FROM post_reactions WHERE user_id=$1 AND post_id = ANY($2)", 
+            &[&user_id, post_ids]
+        ).await?;
+        Ok(
+            res.iter().filter_map(|row| {
+                let post_id: Uuid = row.get("post_id");
+                Reaction::parse(row.get("reaction")).map(|reaction| (post_id, reaction))
+            }).collect()
+        )
+    }
+
+    async fn list(&self, viewer_id: Uuid, post_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<PostReaction>, ReactionRepositoryError> {
+        let client = self.pool.get().await?;
+        let query = format!("SELECT 1 FROM posts p WHERE p.id=$1 AND {}", visible_to("$2"));
+        if client.query_opt(query.as_str(), &[&post_id, &viewer_id]).await?.is_none() {
+            return Err(ReactionRepositoryError::NotFound(format!("Post {}", post_id)));
+        }
+        let res = client.query(
+            "SELECT user_id, reaction, created_at FROM post_reactions WHERE post_id=$1 
+                ORDER BY created_at DESC LIMIT $2 OFFSET $3", 
+            &[&post_id, &limit.map(|v| v as i64), &offset.map(|v| v as i64)]
+        ).await?;
+        Ok(
+            res.iter().filter_map(|row| {
+                let user_id: Uuid = row.get("user_id");
+                let timestamp: chrono::DateTime<chrono::Utc> = row.get("created_at");
+                Reaction::parse(row.get("reaction")).map(|reaction| PostReaction { user_id, reaction, timestamp })
+            }).collect()
+        )
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use fred::prelude;
+use deadpool_postgres;
+use thiserror::Error;
+use uuid::Uuid;
+use async_trait::async_trait;
+use crate::modules::common::ws::ws_manager::WebSocketManager;
+use crate::modules::post::in_memory_repository::InMemoryPostRepository;
+use crate::modules::post::reaction::{in_memory_repository::InMemoryReactionRepository, model::{PostReaction, Reaction, ReactionSummary}, reaction_cache::{NoReactionCache, ReactionCacheImpl}, reaction_service::ReactionServiceImpl, repository::{ReactionRepositoryError, ReactionRepositoryImpl}};
+
+#[derive(Error, Debug)]
+pub enum ReactionServiceError {
+    #[error("Database error: {0}")]
+    Database(#[from] ReactionRepositoryError),
+}
+
+#[async_trait]
+pub trait ReactionService {
+    async fn react(&self, user_id: Uuid, post_id: Uuid, reaction: Reaction) -> Result<(), ReactionServiceError>;
+    async fn unreact(&self, user_id: Uuid, post_id: Uuid) -> Result<(), ReactionServiceError>;
+    async fn list(&self, viewer_id: Uuid, post_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<PostReaction>, ReactionServiceError>;
+    async fn summaries(&self, viewer_id: Uuid, post_ids: &Vec<Uuid>) -> Result<HashMap<Uuid, ReactionSummary>, ReactionServiceError>;
+}
+
+pub fn create_service(pool: Arc<deadpool_postgres::Pool>, redis: Arc<prelude::Pool>, ws_manager: Arc<WebSocketManager>) -> Arc<dyn ReactionService + Send + Sync> {
+    Arc::new(
+        ReactionServiceImpl::new(
+            ReactionRepositoryImpl::new(pool),
+            ReactionCacheImpl::new(redis),
+            ws_manager
+        )
+    )
+}
+
+/// Reaction service of the dev profile over reactions kept in memory, without a cache
+pub fn create_in_memory_service(posts: InMemoryPostRepository, ws_manager: Arc<WebSocketManager>) -> Arc<dyn ReactionService + Send + Sync> {
+    Arc::new(
+        ReactionServiceImpl::new(
+            InMemoryReactionRepository::new(posts),
+            NoReactionCache,
+            ws_manager
+        )
+    )
+}
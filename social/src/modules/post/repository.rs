@@ -1,7 +1,8 @@
 use uuid::Uuid;
-use deadpool_postgres::Pool;
+use deadpool_postgres::{Pool, Transaction};
+use tokio_postgres::Row;
 use thiserror::Error;
-use crate::modules::post::model::Post;
+use crate::modules::post::{cursor::FeedPage, event::DomainEvent, links::sync_links, model::{Post, PostRevision, Visibility}, outbox::repository::{OutboxRepositoryError, enqueue}};
 use std::sync::Arc;
 use async_trait::async_trait; 
 use mockall::automock;
@@ -14,18 +15,37 @@ pub enum PostRepositoryError {
     #[error("Pool error: {0}")]
     Pool(#[from] deadpool_postgres::PoolError),
 
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Illegal state: {0}")]
+    IllegalState(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
+
+    #[error("Outbox error: {0}")]
+    Outbox(#[from] OutboxRepositoryError),
 }
 
 #[automock]
 #[async_trait]
 pub trait PostRepository {
-    async fn create(&self, user_id: Uuid, text: &String) -> Result<Post, PostRepositoryError>;
-    async fn update(&self, user_id: Uuid, post_id: Uuid, text: &String) -> Result<Post, PostRepositoryError>;
+    async fn create(&self, user_id: Uuid, text: &String, visibility: Visibility, post_id: Option<Uuid>) -> Result<Post, PostRepositoryError>;
+    async fn repost(&self, user_id: Uuid, post_id: Uuid, text: &Option<String>) -> Result<Post, PostRepositoryError>;
+    async fn update(&self, user_id: Uuid, post_id: Uuid, text: &String, visibility: Option<Visibility>) -> Result<Post, PostRepositoryError>;
     async fn delete(&self, user_id: Uuid, post_id: Uuid) -> Result<(), PostRepositoryError>;
-    async fn get(&self, post_id: Uuid) -> Result<Post, PostRepositoryError>;
-    async fn feed(&self, user_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<Post>, PostRepositoryError>;    
+    async fn restore(&self, user_id: Uuid, post_id: Uuid, window_days: i32) -> Result<Post, PostRepositoryError>;
+    async fn set_hidden(&self, post_id: Uuid, hidden: bool) -> Result<Post, PostRepositoryError>;
+    async fn get(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Post, PostRepositoryError>;
+    async fn get_many(&self, viewer_id: Uuid, post_ids: &Vec<Uuid>) -> Result<Vec<Post>, PostRepositoryError>;
+    async fn reposts(&self, post_id: Uuid) -> Result<Vec<Post>, PostRepositoryError>;
+    async fn revisions(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Vec<PostRevision>, PostRepositoryError>;
+    async fn feed(&self, user_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostRepositoryError>;    
+    async fn tag_feed(&self, viewer_id: Uuid, tag: &String, page: FeedPage) -> Result<Vec<Post>, PostRepositoryError>;
+    async fn author_feed(&self, viewer_id: Uuid, author_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostRepositoryError>;
+    async fn claim_mentions(&self, post_id: Uuid) -> Result<Vec<Uuid>, PostRepositoryError>;
+    async fn followed_celebrities(&self, user_id: Uuid, threshold: i64) -> Result<Vec<Uuid>, PostRepositoryError>;
 }
 
 pub struct PostRepositoryImpl {
@@ -38,66 +58,364 @@ impl PostRepositoryImpl {
     }
 }
 
+/// Expects `edited_at` selected as `NULLIF(updated_at, created_at)`: inserts set both columns
+/// to the same time, so the post counts as edited only after `update` moves `updated_at`.
+fn to_post(row: &Row) -> Post {
+    Post {
+        id: row.get("id"),
+        text: row.get("text"),
+        author_user_id: row.get("user_id"),
+        created_at: row.get("created_at"),
+        edited_at: row.get("edited_at"),
+        comments_count: row.get("comments_count"),
+        repost_of: row.get("repost_of"),
+        original_author_user_id: row.get("original_author_id"),
+        visibility: Visibility::parse(row.get("visibility")).unwrap_or_default()
+    }
+}
+
+/// SQL predicate matching posts `p` readable by the viewer bound to `viewer`:
+/// own posts, public ones and friends-only ones of mutual friends.
+pub(crate) fn visible_to(viewer: &str) -> String {
+    format!(
+        "(p.user_id = {viewer} OR p.visibility = 'public' OR (p.visibility = 'friends' 
+            AND EXISTS (SELECT 1 FROM friends m WHERE m.user_id = p.user_id AND m.friend_id = {viewer}) 
+            AND EXISTS (SELECT 1 FROM friends m WHERE m.user_id = {viewer} AND m.friend_id = p.user_id)))"
+    )
+}
+
+/// SQL predicate hiding soft deleted or moderated posts `p` and reposts of such originals.
+pub(crate) const NOT_REMOVED: &str = "p.deleted_at IS NULL AND p.hidden_at IS NULL 
+    AND NOT EXISTS (SELECT 1 FROM posts o WHERE o.id = p.repost_of AND (o.deleted_at IS NOT NULL OR o.hidden_at IS NOT NULL))";
+
+/// Live reposts of the post, their feed entries follow the original
+async fn reposts_of(tx: &Transaction<'_>, post_id: Uuid) -> Result<Vec<Post>, PostRepositoryError> {
+    let res = tx.query(
+        "SELECT id, text, user_id, created_at, NULLIF(updated_at, created_at) AS edited_at, comments_count, repost_of, original_author_id, visibility 
+            FROM posts WHERE repost_of=$1 AND deleted_at IS NULL", 
+        &[&post_id]
+    ).await?;
+    Ok(res.iter().map(to_post).collect())
+}
+
+/// Enqueues `PostDeleted` for the post and its reposts, listeners drop hidden posts the same way as deleted ones
+async fn enqueue_removed(tx: &Transaction<'_>, author_id: Uuid, post_id: Uuid) -> Result<(), PostRepositoryError> {
+    enqueue(tx, &DomainEvent::PostDeleted { user_id: author_id, post_id }).await?;
+    for repost in reposts_of(tx, post_id).await? {
+        enqueue(tx, &DomainEvent::PostDeleted { user_id: repost.author_user_id, post_id: repost.id }).await?;
+    }
+    Ok(())
+}
+
+/// Enqueues `PostCreated` for the post and its reposts, which come back together with it
+async fn enqueue_returned(tx: &Transaction<'_>, post: &Post) -> Result<(), PostRepositoryError> {
+    enqueue(tx, &DomainEvent::PostCreated { user_id: post.author_user_id, post: post.clone() }).await?;
+    for repost in reposts_of(tx, post.id).await? {
+        enqueue(tx, &DomainEvent::PostCreated { user_id: repost.author_user_id, post: repost }).await?;
+    }
+    Ok(())
+}
+
+/// Write methods enqueue their domain events to the outbox in the same transaction as the change
 #[async_trait]
 impl PostRepository for PostRepositoryImpl {    
 
-    async fn create(&self, user_id: Uuid, text: &String) -> Result<Post, PostRepositoryError> {
-        let res = self.pool.get().await?.query_one(
-            "INSERT INTO posts (user_id, text) VALUES ($1, $2) RETURNING id, created_at", 
-            &[&user_id, text]
+    async fn create(&self, user_id: Uuid, text: &String, visibility: Visibility, post_id: Option<Uuid>) -> Result<Post, PostRepositoryError> {
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let res = tx.query_opt(
+            "INSERT INTO posts (id, user_id, text, visibility) VALUES (COALESCE($4, gen_random_uuid()), $1, $2, $3) 
+                ON CONFLICT (id) DO NOTHING
+                RETURNING id, text, user_id, created_at, NULLIF(updated_at, created_at) AS edited_at, comments_count, repost_of, original_author_id, visibility", 
+            &[&user_id, text, &visibility.as_str(), &post_id]
         ).await?;
-        let id: Uuid = res.get("id");
-        let timestamp: chrono::DateTime<chrono::Utc> = res.get("created_at");                     
-        Ok(Post {id, text: text.to_string(), author_user_id: user_id, timestamp})
+        let Some(res) = res else {
+            // Created by an earlier call with the same id, its links and event are already there
+            let existing = tx.query_opt(
+                "SELECT id, text, user_id, created_at, NULLIF(updated_at, created_at) AS edited_at, comments_count, repost_of, original_author_id, visibility 
+                    FROM posts WHERE id=$1 AND user_id=$2",
+                &[&post_id, &user_id]
+            ).await?
+                .ok_or(PostRepositoryError::IllegalState(format!("Post {:?} belongs to another user", post_id)))?;
+            return Ok(to_post(&existing));
+        };
+        let post = to_post(&res);
+        sync_links(&tx, post.id, &post.text).await?;
+        enqueue(&tx, &DomainEvent::PostCreated { user_id, post: post.clone() }).await?;
+        tx.commit().await?;
+        Ok(post)
     }
 
-    async fn update(&self, user_id: Uuid, id: Uuid, text: &String) -> Result<Post, PostRepositoryError> {
-        let res = self.pool.get().await?.query_one(
-            "UPDATE posts SET text=$1,updated_at=NOW() WHERE user_id=$2 AND id=$3 RETURNING updated_at", 
-            &[&text, &user_id, &id]
+    async fn repost(&self, user_id: Uuid, post_id: Uuid, text: &Option<String>) -> Result<Post, PostRepositoryError> {
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let original = tx.query_opt(
+            "SELECT user_id, repost_of, original_author_id, visibility FROM posts WHERE id=$1 AND deleted_at IS NULL AND hidden_at IS NULL", 
+            &[&post_id]
+        ).await?
+            .ok_or(PostRepositoryError::NotFound(format!("Post {}", post_id)))?;
+        // Only public posts may be shared, otherwise the repost would widen their audience
+        if original.get::<_, &str>("visibility") != Visibility::Public.as_str() {
+            return Err(PostRepositoryError::NotFound(format!("Post {}", post_id)));
+        }
+        // Reposting a repost shares the post it refers to
+        let repost_of: Uuid = original.get::<_, Option<Uuid>>("repost_of").unwrap_or(post_id);
+        let original_author_id: Uuid = original.get::<_, Option<Uuid>>("original_author_id")
+            .unwrap_or(original.get("user_id"));
+        if original_author_id == user_id {
+            return Err(PostRepositoryError::IllegalState("Cannot repost own post".to_string()));
+        }
+        let res = tx.query_one(
+            "INSERT INTO posts (user_id, text, repost_of, original_author_id) VALUES ($1, $2, $3, $4) 
+                RETURNING id, text, user_id, created_at, NULLIF(updated_at, created_at) AS edited_at, comments_count, repost_of, original_author_id, visibility", 
+            &[&user_id, &text.clone().unwrap_or_default(), &repost_of, &original_author_id]
+        ).await?;
+        let post = to_post(&res);
+        sync_links(&tx, post.id, &post.text).await?;
+        enqueue(&tx, &DomainEvent::PostCreated { user_id, post: post.clone() }).await?;
+        tx.commit().await?;
+        Ok(post)
+    }
+
+    async fn update(&self, user_id: Uuid, id: Uuid, text: &String, visibility: Option<Visibility>) -> Result<Post, PostRepositoryError> {
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        // The version being overwritten is kept as a revision
+        let archived = tx.execute(
+            "INSERT INTO post_revisions (post_id, text, visibility, created_at) 
+                SELECT id, text, visibility, COALESCE(updated_at, created_at) FROM posts WHERE user_id=$1 AND id=$2 AND deleted_at IS NULL AND hidden_at IS NULL", 
+            &[&user_id, &id]
+        ).await?;
+        if archived == 0 {
+            return Err(PostRepositoryError::NotFound(format!("Post {}", id)));
+        }
+        let res = tx.query_one(
+            "UPDATE posts SET text=$1,visibility=COALESCE($4, visibility),updated_at=NOW() WHERE user_id=$2 AND id=$3 
+                RETURNING id, text, user_id, created_at, updated_at AS edited_at, comments_count, repost_of, original_author_id, visibility", 
+            &[&text, &user_id, &id, &visibility.map(|v| v.as_str())]
         ).await?;    
-        let timestamp: chrono::DateTime<chrono::Utc> = res.get("updated_at");                     
-        Ok(Post {id, text: text.to_string(), author_user_id: user_id, timestamp})        
+        let post = to_post(&res);
+        sync_links(&tx, post.id, &post.text).await?;
+        enqueue(&tx, &DomainEvent::PostUpdated { user_id, post: post.clone() }).await?;
+        tx.commit().await?;
+        Ok(post)
     }
 
     async fn delete(&self, user_id: Uuid, post_id: Uuid) -> Result<(), PostRepositoryError> {
-        let rows_affected = self.pool.get().await?.execute(
-            "DELETE FROM posts WHERE user_id=$1 AND id=$2", 
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        // Soft delete keeps the post restorable; its reposts are hidden while it stays deleted.
+        // Comments are removed for good, a restored post comes back without them
+        let rows_affected = tx.execute(
+            "UPDATE posts SET deleted_at=NOW(), comments_count=0 WHERE user_id=$1 AND id=$2 AND deleted_at IS NULL", 
             &[&user_id, &post_id]
         ).await?;    
-        if rows_affected > 0 {
-            Ok(())
+        if rows_affected == 0 {
+            return Err(PostRepositoryError::Internal("Not updated".to_string()));
+        }
+        tx.execute("DELETE FROM comments WHERE post_id=$1", &[&post_id]).await?;
+        enqueue_removed(&tx, user_id, post_id).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn restore(&self, user_id: Uuid, post_id: Uuid, window_days: i32) -> Result<Post, PostRepositoryError> {
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let res = tx.query_opt(
+            "UPDATE posts SET deleted_at=NULL 
+                WHERE user_id=$1 AND id=$2 AND hidden_at IS NULL AND deleted_at > NOW() - make_interval(days => $3) 
+                RETURNING id, text, user_id, created_at, NULLIF(updated_at, created_at) AS edited_at, comments_count, repost_of, original_author_id, visibility", 
+            &[&user_id, &post_id, &window_days]
+        ).await?
+            .ok_or(PostRepositoryError::NotFound(format!("Deleted post {}", post_id)))?;
+        let post = to_post(&res);
+        enqueue_returned(&tx, &post).await?;
+        tx.commit().await?;
+        Ok(post)
+    }
+
+    async fn set_hidden(&self, post_id: Uuid, hidden: bool) -> Result<Post, PostRepositoryError> {
+        let query = if hidden {
+            "UPDATE posts SET hidden_at=NOW() WHERE id=$1 AND deleted_at IS NULL AND hidden_at IS NULL 
+                RETURNING id, text, user_id, created_at, NULLIF(updated_at, created_at) AS edited_at, comments_count, repost_of, original_author_id, visibility"
+        } else {
+            "UPDATE posts SET hidden_at=NULL WHERE id=$1 AND deleted_at IS NULL AND hidden_at IS NOT NULL 
+                RETURNING id, text, user_id, created_at, NULLIF(updated_at, created_at) AS edited_at, comments_count, repost_of, original_author_id, visibility"
+        };
+        let mut client = self.pool.get().await?;
+        let tx = client.transaction().await?;
+        let res = tx.query_opt(query, &[&post_id]).await?
+            .ok_or(PostRepositoryError::NotFound(format!("Post {}", post_id)))?;
+        let post = to_post(&res);
+        if hidden {
+            enqueue_removed(&tx, post.author_user_id, post_id).await?;
         } else {
-            Err(PostRepositoryError::Internal("Not updated".to_string()))
+            enqueue_returned(&tx, &post).await?;
         }
+        tx.commit().await?;
+        Ok(post)
     }
 
-    async fn get(&self, post_id: Uuid) -> Result<Post, PostRepositoryError> {
-        let res = self.pool.get().await?.query_one(
-            "SELECT text,user_id, updated_at FROM posts WHERE id=$1", 
+    async fn get(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Post, PostRepositoryError> {
+        let query = format!(
+            "SELECT p.id, p.text, p.user_id, p.created_at, NULLIF(p.updated_at, p.created_at) AS edited_at, p.comments_count, p.repost_of, p.original_author_id, p.visibility 
+                FROM posts p WHERE p.id=$1 AND {NOT_REMOVED} AND {}", 
+            visible_to("$2")
+        );
+        let res = self.pool.get().await?.query_opt(query.as_str(), &[&post_id, &viewer_id]).await?
+            .ok_or(PostRepositoryError::NotFound(format!("Post {}", post_id)))?;
+        Ok(to_post(&res))
+    }
+
+    /// Loads posts by ids skipping removed ones and the ones the viewer may not see
+    async fn get_many(&self, viewer_id: Uuid, post_ids: &Vec<Uuid>) -> Result<Vec<Post>, PostRepositoryError> {
+        if post_ids.is_empty() {
+            return Ok(vec!());
+        }
+        let query = format!(
+            "SELECT p.id, p.text, p.user_id, p.created_at, NULLIF(p.updated_at, p.created_at) AS edited_at, p.comments_count, p.repost_of, p.original_author_id, p.visibility 
+                FROM posts p WHERE p.id = ANY($1) AND {NOT_REMOVED} AND {}",
+            visible_to("$2")
+        );
+        let res = self.pool.get().await?.query(query.as_str(), &[post_ids, &viewer_id]).await?;
+        Ok(res.iter().map(to_post).collect())
+    }
+
+    async fn reposts(&self, post_id: Uuid) -> Result<Vec<Post>, PostRepositoryError> {
+        let res = self.pool.get().await?.query(
+            "SELECT id, text, user_id, created_at, NULLIF(updated_at, created_at) AS edited_at, comments_count, repost_of, original_author_id, visibility 
+                FROM posts WHERE repost_of=$1 AND deleted_at IS NULL", 
             &[&post_id]
-        ).await?;    
-        let text: String = res.get("text");    
-        let author_user_id: Uuid = res.get("user_id");
-        let timestamp: chrono::DateTime<chrono::Utc> = res.get("updated_at");    
-        Ok(Post {id: post_id, text, author_user_id, timestamp})
+        ).await?;
+        Ok(res.iter().map(to_post).collect())
+    }
+
+    async fn revisions(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Vec<PostRevision>, PostRepositoryError> {
+        let client = self.pool.get().await?;
+        // Earlier versions may have had a narrower audience or text the author removed, so only the author reads them
+        let query = format!("SELECT 1 FROM posts p WHERE p.id=$1 AND p.user_id=$2 AND {NOT_REMOVED}");
+        if client.query_opt(query.as_str(), &[&post_id, &viewer_id]).await?.is_none() {
+            return Err(PostRepositoryError::NotFound(format!("Post {}", post_id)));
+        }
+        let res = client.query(
+            "SELECT text, visibility, created_at FROM post_revisions WHERE post_id=$1 ORDER BY created_at DESC", 
+            &[&post_id]
+        ).await?;
+        Ok(res.iter()
+            .map(|row| PostRevision {
+                text: row.get("text"),
+                visibility: Visibility::parse(row.get("visibility")).unwrap_or_default(),
+                timestamp: row.get("created_at")
+            })
+            .collect())
     }
 
-    async fn feed(&self, user_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<Post>, PostRepositoryError> {
+    async fn feed(&self, user_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostRepositoryError> {
+        let client = self.pool.get().await?;
+        let visible = visible_to("$1");
+        let res = match page {
+            FeedPage::Offset { limit, offset } => client.query(
+                format!(
+                    "SELECT p.id, p.text, p.user_id, p.created_at, NULLIF(p.updated_at, p.created_at) AS edited_at, p.comments_count, p.repost_of, p.original_author_id, p.visibility 
+                        FROM (SELECT friend_id AS f_id FROM friends WHERE user_id=$1) q 
+                        JOIN posts p ON q.f_id = p.user_id 
+                        WHERE {NOT_REMOVED} AND {visible}
+                        ORDER BY p.created_at DESC, p.id DESC LIMIT $2 OFFSET $3"
+                ).as_str(), 
+                &[&user_id, &limit.map(|v| v as i64), &offset.map(|v| v as i64)]
+            ).await?,
+            FeedPage::After { limit, cursor } => client.query(
+                format!(
+                    "SELECT p.id, p.text, p.user_id, p.created_at, NULLIF(p.updated_at, p.created_at) AS edited_at, p.comments_count, p.repost_of, p.original_author_id, p.visibility 
+                        FROM (SELECT friend_id AS f_id FROM friends WHERE user_id=$1) q 
+                        JOIN posts p ON q.f_id = p.user_id 
+                        WHERE {NOT_REMOVED} AND {visible} AND (p.created_at, p.id) < ($3, $4)
+                        ORDER BY p.created_at DESC, p.id DESC LIMIT $2"
+                ).as_str(), 
+                &[&user_id, &limit.map(|v| v as i64), &cursor.timestamp, &cursor.post_id]
+            ).await?
+        };
+        Ok(res.iter().map(to_post).collect())    
+    }
+
+    async fn tag_feed(&self, viewer_id: Uuid, tag: &String, page: FeedPage) -> Result<Vec<Post>, PostRepositoryError> {
+        let client = self.pool.get().await?;
+        let visible = visible_to("$1");
+        let res = match page {
+            FeedPage::Offset { limit, offset } => client.query(
+                format!(
+                    "SELECT p.id, p.text, p.user_id, p.created_at, NULLIF(p.updated_at, p.created_at) AS edited_at, p.comments_count, p.repost_of, p.original_author_id, p.visibility 
+                        FROM post_tags t JOIN posts p ON p.id = t.post_id 
+                        WHERE t.tag = $2 AND {NOT_REMOVED} AND {visible}
+                        ORDER BY p.created_at DESC, p.id DESC LIMIT $3 OFFSET $4"
+                ).as_str(), 
+                &[&viewer_id, tag, &limit.map(|v| v as i64), &offset.map(|v| v as i64)]
+            ).await?,
+            FeedPage::After { limit, cursor } => client.query(
+                format!(
+                    "SELECT p.id, p.text, p.user_id, p.created_at, NULLIF(p.updated_at, p.created_at) AS edited_at, p.comments_count, p.repost_of, p.original_author_id, p.visibility 
+                        FROM post_tags t JOIN posts p ON p.id = t.post_id 
+                        WHERE t.tag = $2 AND {NOT_REMOVED} AND {visible} AND (p.created_at, p.id) < ($4, $5)
+                        ORDER BY p.created_at DESC, p.id DESC LIMIT $3"
+                ).as_str(), 
+                &[&viewer_id, tag, &limit.map(|v| v as i64), &cursor.timestamp, &cursor.post_id]
+            ).await?
+        };
+        Ok(res.iter().map(to_post).collect())
+    }
+
+    async fn author_feed(&self, viewer_id: Uuid, author_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostRepositoryError> {
+        let client = self.pool.get().await?;
+        let visible = visible_to("$1");
+        let res = match page {
+            FeedPage::Offset { limit, offset } => client.query(
+                format!(
+                    "SELECT p.id, p.text, p.user_id, p.created_at, NULLIF(p.updated_at, p.created_at) AS edited_at, p.comments_count, p.repost_of, p.original_author_id, p.visibility 
+                        FROM posts p 
+                        WHERE p.user_id = $2 AND {NOT_REMOVED} AND {visible}
+                        ORDER BY p.created_at DESC, p.id DESC LIMIT $3 OFFSET $4"
+                ).as_str(), 
+                &[&viewer_id, &author_id, &limit.map(|v| v as i64), &offset.map(|v| v as i64)]
+            ).await?,
+            FeedPage::After { limit, cursor } => client.query(
+                format!(
+                    "SELECT p.id, p.text, p.user_id, p.created_at, NULLIF(p.updated_at, p.created_at) AS edited_at, p.comments_count, p.repost_of, p.original_author_id, p.visibility 
+                        FROM posts p 
+                        WHERE p.user_id = $2 AND {NOT_REMOVED} AND {visible} AND (p.created_at, p.id) < ($4, $5)
+                        ORDER BY p.created_at DESC, p.id DESC LIMIT $3"
+                ).as_str(), 
+                &[&viewer_id, &author_id, &limit.map(|v| v as i64), &cursor.timestamp, &cursor.post_id]
+            ).await?
+        };
+        Ok(res.iter().map(to_post).collect())
+    }
+
+    async fn claim_mentions(&self, post_id: Uuid) -> Result<Vec<Uuid>, PostRepositoryError> {
+        // Mentions the user may not see stay pending, so they are delivered if the post is opened up later
+        let query = format!(
+            "UPDATE post_mentions pm SET notified_at = NOW() 
+                FROM posts p 
+                WHERE pm.post_id = $1 AND p.id = pm.post_id AND pm.notified_at IS NULL AND pm.user_id <> p.user_id 
+                    AND {NOT_REMOVED} AND {} 
+                    AND NOT EXISTS (
+                        SELECT 1 FROM user_blocks b 
+                            WHERE b.user_id = p.user_id AND b.blocked_user_id = pm.user_id 
+                                OR b.user_id = pm.user_id AND b.blocked_user_id = p.user_id
+                    ) 
+                RETURNING pm.user_id", 
+            visible_to("pm.user_id")
+        );
+        let res = self.pool.get().await?.query(query.as_str(), &[&post_id]).await?;
+        Ok(res.iter().map(|row| row.get(0)).collect())
+    }
+
+    async fn followed_celebrities(&self, user_id: Uuid, threshold: i64) -> Result<Vec<Uuid>, PostRepositoryError> {
         let res = self.pool.get().await?.query(
-            "SELECT p.text,p.user_id,p.id, p.updated_at 
-                FROM (SELECT friend_id AS f_id FROM friends WHERE user_id=$1) q 
-                JOIN posts p ON q.f_id = p.user_id ORDER BY p.created_at DESC LIMIT $2 OFFSET $3", 
-            &[&user_id, &limit.map(|v| v as i64), &offset.map(|v| v as i64)]
-        ).await?;        
-        Ok(
-            res.iter().map(|row| {
-                let text: String = row.get(0);    
-                let author_user_id: Uuid = row.get(1);    
-                let id: Uuid = row.get(2);
-                let timestamp: chrono::DateTime<chrono::Utc> = row.get("updated_at");
-                Post {id, text, author_user_id, timestamp}
-            }).collect()
-        )    
+            "SELECT u.id FROM friends f JOIN users u ON u.id = f.user_id 
+                WHERE f.friend_id = $1 AND u.followers_count >= $2", 
+            &[&user_id, &threshold]
+        ).await?;
+        Ok(res.iter().map(|row| row.get(0)).collect())
     }
 }
\ No newline at end of file
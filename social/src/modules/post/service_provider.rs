@@ -5,37 +5,75 @@ use thiserror::Error;
 use uuid::Uuid;
 use async_trait::async_trait;
 
-use crate::modules::post::{cached_post_service::CachedPostService, model::Post, post_cache::PostCacheImpl, post_service::PostServiceImpl, publishing_service::PublishingServiceImpl, rabbitmq::RabbitPublisher, repository::{PostRepositoryError, PostRepositoryImpl}}; 
+use crate::modules::post::{cached_post_service::CachedPostService, cursor::FeedPage, event::EventPublisher, in_memory_post_cache::InMemoryPostCache, in_memory_repository::InMemoryPostRepository, model::{Post, PostRevision, Visibility}, moderating_post_service::ModeratingPostService, moderation::filter::{ContentFilter, FilterConfig, create_filters, create_local_filters}, local_post_cache::{LocalPostCache, LocalPostStore}, post_cache::{FeedLimits, PostCache, PostCacheImpl}, post_service::PostServiceImpl, publishing_service::PublishingServiceImpl, repository::{PostRepository, PostRepositoryError, PostRepositoryImpl}}; 
 
 #[derive(Error, Debug)]
 pub enum PostServiceError {
     #[error("Database error: {0}")]
     Database(#[from] PostRepositoryError),
 
+    #[error("Rejected: {0}")]
+    Rejected(String),
+
     #[error("Error: {0}")]
     Inner(#[from] Box<dyn std::error::Error>),
 }
 
 #[async_trait]
 pub trait PostService {
-    async fn create(&self, user_id: Uuid, text: &String) -> Result<Post, PostServiceError>;
-    async fn update(&self, user_id: Uuid, post_id: Uuid, text: &String) -> Result<Post, PostServiceError>;
+    /// `post_id` makes the call idempotent: a post which already exists with that id is returned as is
+    async fn create(&self, user_id: Uuid, text: &String, visibility: Visibility, post_id: Option<Uuid>) -> Result<Post, PostServiceError>;
+    async fn repost(&self, user_id: Uuid, post_id: Uuid, text: &Option<String>) -> Result<Post, PostServiceError>;
+    async fn update(&self, user_id: Uuid, post_id: Uuid, text: &String, visibility: Option<Visibility>) -> Result<Post, PostServiceError>;
     async fn delete(&self, user_id: Uuid, post_id: Uuid) -> Result<(), PostServiceError>;
-    async fn get(&self, post_id: Uuid) -> Result<Post, PostServiceError>;
-    async fn feed(&self, user_id: Uuid, limit: Option<u64>, offset: Option<u64>) -> Result<Vec<Post>, PostServiceError>;
+    async fn restore(&self, user_id: Uuid, post_id: Uuid) -> Result<Post, PostServiceError>;
+    async fn hide(&self, post_id: Uuid) -> Result<Post, PostServiceError>;
+    async fn unhide(&self, post_id: Uuid) -> Result<Post, PostServiceError>;
+    async fn get(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Post, PostServiceError>;
+    /// Posts of the ids the viewer may read, the others are left out
+    async fn get_many(&self, viewer_id: Uuid, post_ids: &Vec<Uuid>) -> Result<Vec<Post>, PostServiceError>;
+    async fn reposts(&self, post_id: Uuid) -> Result<Vec<Post>, PostServiceError>;
+    async fn revisions(&self, viewer_id: Uuid, post_id: Uuid) -> Result<Vec<PostRevision>, PostServiceError>;
+    async fn feed(&self, user_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostServiceError>;
+    async fn tag_feed(&self, viewer_id: Uuid, tag: &String, page: FeedPage) -> Result<Vec<Post>, PostServiceError>;
+    async fn author_feed(&self, viewer_id: Uuid, author_id: Uuid, page: FeedPage) -> Result<Vec<Post>, PostServiceError>;
+    async fn followed_celebrities(&self, user_id: Uuid) -> Result<Vec<Uuid>, PostServiceError>;
 }
 
-pub fn create_service(pool: Arc<deadpool_postgres::Pool>, redis: Arc<prelude::Pool>, rabbitmq: Arc<deadpool_lapin::Pool>, exchange: String) -> Arc<dyn PostService + Send + Sync> {    
-    let service = PostServiceImpl::new(
-        PostRepositoryImpl::new(Arc::clone(&pool))
-    );    
-    let cached_service = CachedPostService::new(
-        service,
-        PostCacheImpl::new(Arc::clone(&redis))
-    );
-    let publishing_service = PublishingServiceImpl::new(
-        cached_service, 
-        Arc::new(RabbitPublisher::new(rabbitmq, exchange)), 
+/// Loads feeds into the cache ahead of reads, used by the feed warm-up
+#[async_trait]
+pub trait FeedWarmer {
+    /// Loads the feed into cache, `force` replaces an existing one. Returns false if the feed was kept
+    /// because it is cached already or another rebuild of it holds the feed lock
+    async fn warm_feed(&self, user_id: Uuid, force: bool) -> Result<bool, PostServiceError>;
+}
+
+/// Assembles the decorator chain: caching over moderation over the repository
+fn build_service<R, C>(repository: R, cache: C, filters: Vec<Arc<dyn ContentFilter + Send + Sync>>, celebrity_threshold: u64, restore_window_days: i32, feed_limits: FeedLimits) -> CachedPostService<C, ModeratingPostService<PostServiceImpl<R>>>
+where
+    R: PostRepository + Send + Sync + 'static,
+    C: PostCache + Send + Sync + 'static {
+    let service = PostServiceImpl::new(repository, celebrity_threshold, restore_window_days);
+    let moderating_service = ModeratingPostService::new(service, filters);
+    CachedPostService::new(moderating_service, cache, feed_limits)
+}
+
+/// Domain events are written to the outbox by the repository and delivered by the outbox relay.
+/// The feed warmer shares the cache and the rebuild state of the service
+pub fn create_service(pool: Arc<deadpool_postgres::Pool>, redis: Arc<prelude::Pool>, filters: FilterConfig, celebrity_threshold: u64, restore_window_days: i32, feed_limits: FeedLimits, local_posts: Arc<LocalPostStore>, replica_reads: bool) -> (Arc<dyn PostService + Send + Sync>, Arc<dyn FeedWarmer + Send + Sync>) {    
+    let service = build_service(
+        PostRepositoryImpl::new(pool),
+        LocalPostCache::new(PostCacheImpl::new(Arc::clone(&redis), feed_limits, replica_reads), local_posts),
+        create_filters(filters, redis),
+        celebrity_threshold,
+        restore_window_days,
+        feed_limits
     );
-    Arc::new(publishing_service)
-}
\ No newline at end of file
+    (Arc::new(service.clone()), Arc::new(service))
+}
+
+/// Post service of the dev profile: in-memory storage and cache, events handled in process by `publisher`
+pub fn create_in_memory_service(repository: InMemoryPostRepository, cache: InMemoryPostCache, publisher: Arc<dyn EventPublisher + Send + Sync>, filters: FilterConfig, celebrity_threshold: u64, restore_window_days: i32, feed_limits: FeedLimits) -> (Arc<dyn PostService + Send + Sync>, Arc<dyn FeedWarmer + Send + Sync>) {
+    let service = build_service(repository, cache, create_local_filters(filters), celebrity_threshold, restore_window_days, feed_limits);
+    (Arc::new(PublishingServiceImpl::new(service.clone(), publisher)), Arc::new(service))
+}
@@ -0,0 +1,221 @@
+use std::{collections::HashMap, sync::Arc};
+use axum::extract::ws::Message;
+use chrono::Utc;
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+use uuid::Uuid;
+use crate::modules::{common::ws::ws_manager::WebSocketManager, friend::in_memory_repository::InMemoryFriendRepository, post::{cursor::FeedPage, event::DomainEvent, followers::{self, followers_service::FollowersService, in_process_publisher::InProcessPublisher, service_provider::ConsumerConfig}, in_memory_post_cache::InMemoryPostCache, in_memory_repository::InMemoryPostRepository, model::{Post, Visibility}, moderation::filter::FilterConfig, post_cache::{AUTHOR_TIMELINE_SIZE, AuthorCache, FeedCache, FeedLimits}, reaction::{self, model::Reaction, service_provider::ReactionService}, service_provider::{self, PostService}}};
+
+const ALL: FeedPage = FeedPage::Offset { limit: None, offset: None };
+
+/// The post and feed flow of the dev profile, wired as `AppState::init` does
+struct DevFlow {
+    friends: InMemoryFriendRepository,
+    cache: InMemoryPostCache,
+    followers: Arc<dyn FollowersService + Send + Sync>,
+    posts: Arc<dyn PostService + Send + Sync>,
+    reactions: Arc<dyn ReactionService + Send + Sync>,
+    ws: Arc<WebSocketManager>,
+    celebrity_threshold: u64,
+}
+
+impl DevFlow {
+    fn new(feed_size: u64) -> Self {
+        Self::with_threshold(feed_size, 10000)
+    }
+
+    fn with_threshold(feed_size: u64, celebrity_threshold: u64) -> Self {
+        let feed_limits = FeedLimits { size: feed_size, ttl_seconds: 86400 };
+        let friends = InMemoryFriendRepository::new();
+        let repository = InMemoryPostRepository::new(friends.clone());
+        let cache = InMemoryPostCache::new(feed_limits);
+        // The pool connects on first use and the consumer is never started, so no broker is needed
+        let rabbitmq = deadpool_lapin::Config::default().create_pool(Some(deadpool_lapin::Runtime::Tokio1)).unwrap();
+        let ws = Arc::new(WebSocketManager::new());
+        let followers_service = followers::service_provider::create_in_memory_service(
+            friends.clone(),
+            repository.clone(),
+            cache.clone(),
+            Arc::new(rabbitmq),
+            Arc::clone(&ws),
+            "post.feed.events".to_string(),
+            celebrity_threshold,
+            ConsumerConfig::from_env()
+        );
+        let reactions = reaction::service_provider::create_in_memory_service(repository.clone(), Arc::clone(&ws));
+        let (posts, _) = service_provider::create_in_memory_service(
+            repository,
+            cache.clone(),
+            Arc::new(InProcessPublisher::new(Arc::clone(&followers_service))),
+            FilterConfig::from_env(),
+            celebrity_threshold,
+            30,
+            feed_limits
+        );
+        DevFlow { friends, cache, followers: followers_service, posts, reactions, ws, celebrity_threshold }
+    }
+
+    fn befriend(&self, user_id: Uuid, friend_id: Uuid) {
+        self.friends.follow(friend_id, user_id, self.celebrity_threshold).unwrap();
+        self.friends.follow(user_id, friend_id, self.celebrity_threshold).unwrap();
+    }
+
+    /// Fan-out is done by the time the post is returned
+    async fn post(&self, user_id: Uuid, text: &str, visibility: Visibility) -> Post {
+        self.posts.create(user_id, &text.to_string(), visibility, None).await.unwrap()
+    }
+
+    /// Receiver of the WebSocket notifications sent to the user
+    fn connect(&self, user_id: Uuid) -> UnboundedReceiver<Message> {
+        let (tx, rx) = unbounded_channel();
+        self.ws.register(user_id, tx);
+        rx
+    }
+
+    async fn cached_feed(&self, user_id: Uuid) -> Vec<String> {
+        self.cache.get_user_feed(user_id, ALL).await.unwrap()
+    }
+
+    async fn feed(&self, user_id: Uuid) -> Vec<Uuid> {
+        self.posts.feed(user_id, ALL).await.unwrap().into_iter().map(|post| post.id).collect()
+    }
+}
+
+fn post_of(author_id: Uuid, seconds_ago: i64) -> Post {
+    Post {
+        id: Uuid::new_v4(),
+        text: "text".to_string(),
+        author_user_id: author_id,
+        created_at: Utc::now() - chrono::Duration::seconds(seconds_ago),
+        edited_at: None,
+        comments_count: 0,
+        repost_of: None,
+        original_author_user_id: None,
+        visibility: Visibility::Public
+    }
+}
+
+#[tokio::test]
+async fn created_post_is_pushed_into_cached_feed_of_friend() {
+    let flow = DevFlow::new(100);
+    let (author, reader) = (Uuid::new_v4(), Uuid::new_v4());
+    flow.befriend(author, reader);
+    let first = flow.post(author, "first", Visibility::Public).await;
+    // The first read builds the feed, later posts reach it through fan-out
+    assert_eq!(flow.feed(reader).await, vec!(first.id));
+
+    let second = flow.post(author, "second", Visibility::Public).await;
+
+    assert_eq!(flow.cached_feed(reader).await, vec!(second.id.to_string(), first.id.to_string()));
+    assert_eq!(flow.feed(reader).await, vec!(second.id, first.id));
+}
+
+#[tokio::test]
+async fn fan_out_skips_feeds_which_are_not_cached() {
+    let flow = DevFlow::new(100);
+    let (author, reader) = (Uuid::new_v4(), Uuid::new_v4());
+    flow.befriend(author, reader);
+
+    let post = flow.post(author, "text", Visibility::Public).await;
+
+    assert!(flow.cached_feed(reader).await.is_empty());
+    assert_eq!(flow.feed(reader).await, vec!(post.id));
+}
+
+#[tokio::test]
+async fn private_post_is_not_pushed_to_friends() {
+    let flow = DevFlow::new(100);
+    let (author, reader) = (Uuid::new_v4(), Uuid::new_v4());
+    flow.befriend(author, reader);
+    let public = flow.post(author, "public", Visibility::Public).await;
+    flow.feed(reader).await;
+
+    flow.post(author, "private", Visibility::Private).await;
+
+    assert_eq!(flow.cached_feed(reader).await, vec!(public.id.to_string()));
+    assert_eq!(flow.feed(reader).await, vec!(public.id));
+}
+
+#[tokio::test]
+async fn cached_feed_keeps_newest_posts_up_to_its_size() {
+    let flow = DevFlow::new(2);
+    let (author, reader) = (Uuid::new_v4(), Uuid::new_v4());
+    flow.befriend(author, reader);
+    flow.post(author, "first", Visibility::Public).await;
+    flow.feed(reader).await;
+
+    let second = flow.post(author, "second", Visibility::Public).await;
+    let third = flow.post(author, "third", Visibility::Public).await;
+
+    assert_eq!(flow.cached_feed(reader).await, vec!(third.id.to_string(), second.id.to_string()));
+}
+
+#[tokio::test]
+async fn saved_author_timeline_is_trimmed() {
+    let cache = InMemoryPostCache::new(FeedLimits { size: 100, ttl_seconds: 86400 });
+    let author = Uuid::new_v4();
+    let posts: Vec<Post> = (0..AUTHOR_TIMELINE_SIZE as i64 + 5).map(|seconds_ago| post_of(author, seconds_ago)).collect();
+
+    cache.save_author_timeline(author, &posts).await.unwrap();
+
+    assert_eq!(cache.author_timeline_size(author).await.unwrap(), AUTHOR_TIMELINE_SIZE);
+    let newest = cache.get_author_timeline(author, FeedPage::Offset { limit: Some(1), offset: None }).await.unwrap();
+    assert_eq!(newest, vec!(posts[0].id.to_string()));
+}
+
+#[tokio::test]
+async fn retried_create_does_not_bring_back_deleted_post() {
+    let flow = DevFlow::new(100);
+    let (author, reader) = (Uuid::new_v4(), Uuid::new_v4());
+    flow.befriend(author, reader);
+    let kept = flow.post(author, "kept", Visibility::Public).await;
+    flow.feed(reader).await;
+    let deleted = flow.post(author, "deleted", Visibility::Public).await;
+    flow.posts.delete(author, deleted.id).await.unwrap();
+
+    flow.followers.handle(DomainEvent::PostCreated { user_id: author, post: deleted }).await.unwrap();
+
+    assert_eq!(flow.cached_feed(reader).await, vec!(kept.id.to_string()));
+}
+
+#[tokio::test]
+async fn blocked_user_gets_no_mention_notification() {
+    let flow = DevFlow::new(100);
+    let (author, blocked, mentioned) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    flow.friends.unfollow(author, blocked, true, flow.celebrity_threshold).unwrap();
+    let mut blocked_messages = flow.connect(blocked);
+    let mut mentioned_messages = flow.connect(mentioned);
+
+    flow.post(author, &format!("hi @{} and @{}", blocked, mentioned), Visibility::Public).await;
+
+    assert!(mentioned_messages.try_recv().is_ok());
+    assert!(blocked_messages.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn public_post_of_celebrity_is_merged_on_read_instead_of_pushed() {
+    let flow = DevFlow::with_threshold(100, 1);
+    let (author, reader) = (Uuid::new_v4(), Uuid::new_v4());
+    flow.befriend(author, reader);
+    let first = flow.post(author, "first", Visibility::Public).await;
+    assert_eq!(flow.feed(reader).await, vec!(first.id));
+
+    let second = flow.post(author, "second", Visibility::Public).await;
+
+    assert!(!flow.cached_feed(reader).await.contains(&second.id.to_string()));
+    assert_eq!(flow.feed(reader).await, vec!(second.id, first.id));
+}
+
+#[tokio::test]
+async fn reactions_are_counted_without_a_database() {
+    let flow = DevFlow::new(100);
+    let (author, reader) = (Uuid::new_v4(), Uuid::new_v4());
+    let post = flow.post(author, "hello", Visibility::Public).await;
+
+    flow.reactions.react(reader, post.id, Reaction::Like).await.unwrap();
+    flow.reactions.react(author, post.id, Reaction::Love).await.unwrap();
+    flow.reactions.react(reader, post.id, Reaction::Wow).await.unwrap();
+
+    let summary = flow.reactions.summaries(reader, &vec!(post.id)).await.unwrap().remove(&post.id).unwrap();
+    assert_eq!(summary.counts, HashMap::from([(Reaction::Love, 1), (Reaction::Wow, 1)]));
+    assert_eq!(summary.my_reaction, Some(Reaction::Wow));
+}
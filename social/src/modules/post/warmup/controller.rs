@@ -0,0 +1,70 @@
+use openapi::apis::warmup::{Warmup, AdminFeedWarmupPostResponse, AdminFeedUserIdRebuildPostResponse};
+use axum_extra::headers::Host;
+use axum_extra::extract::CookieJar;
+use axum::http::Method;
+use async_trait::async_trait;
+use openapi::models::{self};
+use crate::modules::post::warmup::service_provider::WarmupServiceError;
+use crate::modules::auth::auth;
+use crate::Application;
+use uuid::Uuid;
+
+#[async_trait]
+impl Warmup for Application {
+    type Claims = auth::Claims;
+
+    async fn admin_feed_warmup_post(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+    ) -> Result<AdminFeedWarmupPostResponse, ()> {
+        match self.state.warmup_service.start(claims.user_id).await {
+            Ok(()) => Ok(AdminFeedWarmupPostResponse::Status200),
+            Err(WarmupServiceError::Forbidden(_)) => Ok(AdminFeedWarmupPostResponse::Status403),
+            Err(WarmupServiceError::AlreadyRunning) => Ok(AdminFeedWarmupPostResponse::Status409),
+            Err(e) => {
+                tracing::error!("Feed warm-up error: {:?}", e);
+                Ok(AdminFeedWarmupPostResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+
+    async fn admin_feed_user_id_rebuild_post(
+        &self,
+        _: &Method,
+        _: &Host,
+        _: &CookieJar,
+        claims: &Self::Claims,
+        path_params: &models::AdminFeedUserIdRebuildPostPathParams,
+    ) -> Result<AdminFeedUserIdRebuildPostResponse, ()> {
+        let user_id = match Uuid::parse_str(&path_params.user_id) {
+            Ok(id) => id,
+            Err(_) => return Ok(AdminFeedUserIdRebuildPostResponse::Status400)
+        };
+        match self.state.warmup_service.rebuild(claims.user_id, user_id).await {
+            Ok(()) => Ok(AdminFeedUserIdRebuildPostResponse::Status200),
+            Err(WarmupServiceError::Forbidden(_)) => Ok(AdminFeedUserIdRebuildPostResponse::Status403),
+            Err(WarmupServiceError::AlreadyRunning) => Ok(AdminFeedUserIdRebuildPostResponse::Status409),
+            Err(e) => {
+                tracing::error!("Feed rebuild error: {:?}", e);
+                Ok(AdminFeedUserIdRebuildPostResponse::Status500 {
+                    body: models::LoginPost500Response {
+                        message: "Internal Server Error".to_string(),
+                        request_id: None,
+                        code: None
+                    },
+                    retry_after: None,
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod controller;
+mod repository;
+mod warmup_service;
+pub mod service_provider;
@@ -0,0 +1,53 @@
+use uuid::Uuid;
+use deadpool_postgres::Pool;
+use thiserror::Error;
+use std::sync::Arc;
+use async_trait::async_trait;
+use mockall::automock;
+
+#[derive(Error, Debug)]
+pub enum WarmupRepositoryError {
+    #[error("Database error: {0}")]
+    Database(#[from] tokio_postgres::Error),
+
+    #[error("Pool error: {0}")]
+    Pool(#[from] deadpool_postgres::PoolError),
+}
+
+pub struct ActiveUser {
+    pub id: Uuid,
+    pub last_login_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[automock]
+#[async_trait]
+pub trait WarmupRepository {
+    /// Users logged in during the last `days`, the most recent first, starting after the `(last_login_at, id)` of the previous page
+    async fn active_users(&self, days: i32, limit: u64, after: Option<(chrono::DateTime<chrono::Utc>, Uuid)>) -> Result<Vec<ActiveUser>, WarmupRepositoryError>;
+}
+
+pub struct WarmupRepositoryImpl {
+    pool: Arc<Pool>
+}
+
+impl WarmupRepositoryImpl {
+    pub fn new(pool: Arc<Pool>) -> Self {
+        WarmupRepositoryImpl { pool }
+    }
+}
+
+#[async_trait]
+impl WarmupRepository for WarmupRepositoryImpl {
+
+    async fn active_users(&self, days: i32, limit: u64, after: Option<(chrono::DateTime<chrono::Utc>, Uuid)>) -> Result<Vec<ActiveUser>, WarmupRepositoryError> {
+        // Keyset paging: logins during the walk move users ahead of the cursor instead of shifting the pages
+        let (after_login, after_id) = after.unzip();
+        let res = self.pool.get().await?.query(
+            "SELECT id, last_login_at FROM users WHERE last_login_at >= NOW() - make_interval(days => $1)
+                AND ($3::timestamptz IS NULL OR (last_login_at, id) < ($3, $4))
+                ORDER BY last_login_at DESC, id DESC LIMIT $2",
+            &[&days, &(limit as i64), &after_login, &after_id]
+        ).await?;
+        Ok(res.iter().map(|row| ActiveUser { id: row.get("id"), last_login_at: row.get("last_login_at") }).collect())
+    }
+}
@@ -0,0 +1,65 @@
+use std::{env, sync::Arc, time::Duration};
+use deadpool_postgres;
+use thiserror::Error;
+use uuid::Uuid;
+use async_trait::async_trait;
+use crate::modules::post::service_provider::FeedWarmer;
+use crate::modules::post::warmup::{repository::{WarmupRepositoryError, WarmupRepositoryImpl}, warmup_service::WarmupServiceImpl};
+
+#[derive(Error, Debug)]
+pub enum WarmupServiceError {
+    #[error("Database error: {0}")]
+    Database(#[from] WarmupRepositoryError),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Already running")]
+    AlreadyRunning,
+
+    #[error("Post error: {0}")]
+    Post(String),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct WarmupConfig {
+    pub on_startup: bool,
+    pub active_days: i32,
+    pub max_users: u64,
+    pub batch_size: u64,
+    /// Pause between batches, keeps the database load of a warm-up bounded
+    pub batch_pause: Duration,
+}
+
+impl WarmupConfig {
+    pub fn from_env() -> Self {
+        WarmupConfig {
+            on_startup: env::var("FEED_WARMUP_ON_STARTUP").ok().and_then(|v| v.parse().ok()).unwrap_or(false),
+            active_days: env::var("FEED_WARMUP_ACTIVE_DAYS").ok().and_then(|v| v.parse().ok()).unwrap_or(7),
+            max_users: env::var("FEED_WARMUP_MAX_USERS").ok().and_then(|v| v.parse().ok()).unwrap_or(10000),
+            batch_size: env::var("FEED_WARMUP_BATCH_SIZE").ok().and_then(|v| v.parse().ok()).unwrap_or(100).max(1),
+            batch_pause: Duration::from_millis(env::var("FEED_WARMUP_BATCH_PAUSE_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(500)),
+        }
+    }
+}
+
+#[async_trait]
+pub trait WarmupService {
+    /// Starts a background warm-up of feeds of recently active users
+    async fn start(&self, admin_id: Uuid) -> Result<(), WarmupServiceError>;
+    /// Replaces the cached feed of the user with a fresh one, `AlreadyRunning` if another rebuild holds the feed
+    async fn rebuild(&self, admin_id: Uuid, user_id: Uuid) -> Result<(), WarmupServiceError>;
+    /// Runs the warm-up if it is enabled for startup
+    async fn run_on_startup(&self) -> ();
+}
+
+pub fn create_service(pool: Arc<deadpool_postgres::Pool>, feed_warmer: Arc<dyn FeedWarmer + Send + Sync>, admin_ids: Vec<Uuid>, config: WarmupConfig) -> Arc<dyn WarmupService + Send + Sync> {
+    Arc::new(
+        WarmupServiceImpl::new(
+            WarmupRepositoryImpl::new(pool),
+            feed_warmer,
+            admin_ids,
+            config
+        )
+    )
+}
@@ -0,0 +1,115 @@
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use uuid::Uuid;
+use async_trait::async_trait;
+use crate::modules::post::service_provider::FeedWarmer;
+use crate::modules::post::warmup::{repository::WarmupRepository, service_provider::{WarmupConfig, WarmupService, WarmupServiceError}};
+
+pub struct WarmupServiceImpl<R>
+where
+    R: WarmupRepository {
+    repository: Arc<R>,
+    feed_warmer: Arc<dyn FeedWarmer + Send + Sync>,
+    admin_ids: Vec<Uuid>,
+    config: WarmupConfig,
+    running: Arc<AtomicBool>,
+}
+
+impl <R> WarmupServiceImpl<R>
+where
+    R: WarmupRepository + Send + Sync + 'static {
+    pub fn new(repository: R, feed_warmer: Arc<dyn FeedWarmer + Send + Sync>, admin_ids: Vec<Uuid>, config: WarmupConfig) -> Self {
+        WarmupServiceImpl {
+            repository: Arc::new(repository),
+            feed_warmer,
+            admin_ids,
+            config,
+            running: Arc::new(AtomicBool::new(false))
+        }
+    }
+
+    fn check_admin(&self, user_id: Uuid) -> Result<(), WarmupServiceError> {
+        if self.admin_ids.contains(&user_id) {
+            Ok(())
+        } else {
+            Err(WarmupServiceError::Forbidden(format!("User {} is not an administrator", user_id)))
+        }
+    }
+
+    fn spawn_warm_up(&self) -> Result<(), WarmupServiceError> {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return Err(WarmupServiceError::AlreadyRunning);
+        }
+        let repository = Arc::clone(&self.repository);
+        let feed_warmer = Arc::clone(&self.feed_warmer);
+        let running = Arc::clone(&self.running);
+        let config = self.config;
+        tokio::spawn(async move {
+            warm_up(repository, feed_warmer, config).await;
+            running.store(false, Ordering::SeqCst);
+        });
+        Ok(())
+    }
+}
+
+/// Walks recently active users in batches, the most recent first. Feeds which are already cached are kept
+async fn warm_up<R>(repository: Arc<R>, feed_warmer: Arc<dyn FeedWarmer + Send + Sync>, config: WarmupConfig)
+where
+    R: WarmupRepository + Send + Sync {
+    tracing::info!("Feed warm-up started...");
+    let mut after = None;
+    let mut visited = 0;
+    let mut rebuilt = 0;
+    while visited < config.max_users {
+        let limit = config.batch_size.min(config.max_users - visited);
+        let users = match repository.active_users(config.active_days, limit, after).await {
+            Ok(users) => users,
+            Err(e) => {
+                tracing::warn!("Failed to load active users {:?}", e);
+                break;
+            }
+        };
+        for user in &users {
+            match feed_warmer.warm_feed(user.id, false).await {
+                Ok(true) => rebuilt += 1,
+                Ok(false) => {},
+                Err(e) => tracing::warn!("Failed to warm up feed {}: {:?}", user.id, e)
+            }
+        }
+        if (users.len() as u64) < limit {
+            break;
+        }
+        visited += limit;
+        after = users.last().map(|user| (user.last_login_at, user.id));
+        tokio::time::sleep(config.batch_pause).await;
+    }
+    tracing::info!("Feed warm-up finished, {} feeds rebuilt", rebuilt);
+}
+
+#[async_trait]
+impl <R> WarmupService for WarmupServiceImpl<R>
+where
+    R: WarmupRepository + Send + Sync + 'static {
+
+    async fn start(&self, admin_id: Uuid) -> Result<(), WarmupServiceError> {
+        self.check_admin(admin_id)?;
+        self.spawn_warm_up()?;
+        tracing::info!("Feed warm-up requested by {}", admin_id);
+        Ok(())
+    }
+
+    async fn rebuild(&self, admin_id: Uuid, user_id: Uuid) -> Result<(), WarmupServiceError> {
+        self.check_admin(admin_id)?;
+        let rebuilt = self.feed_warmer.warm_feed(user_id, true).await.map_err(|e| WarmupServiceError::Post(e.to_string()))?;
+        if !rebuilt {
+            return Err(WarmupServiceError::AlreadyRunning);
+        }
+        tracing::info!("Feed {} rebuilt by {}", user_id, admin_id);
+        Ok(())
+    }
+
+    async fn run_on_startup(&self) -> () {
+        if self.config.on_startup {
+            self.spawn_warm_up().ok();
+        }
+    }
+}
@@ -24,10 +24,14 @@ impl User for Application {
             Ok(id) => id,
             Err(_) => return Ok(UserGetIdGetResponse::Status400)
         };
-        match user_service::get_user_by_id(
-            self.state.get_replica_client().await, 
-            uuid
-        ).await {
+        let res = match &self.state.dev_users {
+            Some(users) => users.get(uuid),
+            None => user_service::get_user_by_id(
+                self.state.get_replica_client().await, 
+                uuid
+            ).await
+        };
+        match res {
             Ok(user) => Ok(UserGetIdGetResponse::Status200(to_user_dto(user))),
             Err(_) => Ok(UserGetIdGetResponse::Status400)
         }        
@@ -43,17 +47,18 @@ impl User for Application {
         Ok(
             match user_registration_request {                
                 Some(req) => {                    
-                    let res = user_service::register_user(
-                        self.state.get_master_client().await,
-                        user_service::UserRegistration {
-                            first_name: &req.first_name,
-                            last_name: &req.last_name,
-                            birthdate: &req.birthdate,
-                            biography: &req.biography,
-                            city: &req.city,
-                            password: &req.password,
-                        }
-                    ).await;
+                    let registration = user_service::UserRegistration {
+                        first_name: &req.first_name,
+                        last_name: &req.last_name,
+                        birthdate: &req.birthdate,
+                        biography: &req.biography,
+                        city: &req.city,
+                        password: &req.password,
+                    };
+                    let res = match &self.state.dev_users {
+                        Some(users) => users.register(registration),
+                        None => user_service::register_user(self.state.get_master_client().await, registration).await
+                    };
                     match res {
                         Ok(r) => UserRegisterPostResponse::Status200(
                             models::UserRegisterPost200Response {
@@ -76,17 +81,15 @@ impl User for Application {
         _: &Self::Claims,
         search_request: &models::UserSearchGetQueryParams,        
         ) -> Result<UserSearchGetResponse, ()> {
-        Ok(
-            UserSearchGetResponse::Status200(
-                to_user_dtos(
-                    user_service::search_by_first_and_last_name(
-                        self.state.get_replica_client().await, 
-                        &search_request.first_name, 
-                        &search_request.last_name
-                    ).await
-                )
-            )
-        )
+        let users = match &self.state.dev_users {
+            Some(users) => users.search(&search_request.first_name, &search_request.last_name),
+            None => user_service::search_by_first_and_last_name(
+                self.state.get_replica_client().await, 
+                &search_request.first_name, 
+                &search_request.last_name
+            ).await
+        };
+        Ok(UserSearchGetResponse::Status200(to_user_dtos(users)))
     }
 }
 
@@ -102,5 +105,7 @@ fn to_user_dto(user: user_service::User) -> openapi::models::User {
         birthdate: user.birthdate,
         biography: user.biography,
         city: user.city,
+        followers_count: Some(user.followers_count),
+        following_count: Some(user.following_count),
     }    
 }
\ No newline at end of file
@@ -0,0 +1,90 @@
+use std::{collections::HashMap, sync::{Arc, RwLock}};
+use chrono::NaiveDate;
+use uuid::Uuid;
+use crate::modules::auth::password_hash;
+use crate::modules::friend::in_memory_repository::InMemoryFriendRepository;
+use crate::modules::user::user_service::{User, UserRegistration, UserRegistrationResult};
+
+/// A `users` row
+struct StoredUser {
+    first_name: String,
+    last_name: String,
+    birthdate: NaiveDate,
+    biography: Option<String>,
+    city: String,
+    pwd: String,
+}
+
+/// Users of the dev profile, following the SQL of `user_service` and `auth_service`.
+/// Follower counts are taken from the in-memory follower graph. Clones share the same rows
+#[derive(Clone)]
+pub struct InMemoryUserRepository {
+    friends: InMemoryFriendRepository,
+    users: Arc<RwLock<HashMap<Uuid, StoredUser>>>,
+}
+
+impl InMemoryUserRepository {
+    pub fn new(friends: InMemoryFriendRepository) -> Self {
+        InMemoryUserRepository {
+            friends,
+            users: Arc::new(RwLock::new(HashMap::new()))
+        }
+    }
+
+    fn to_user(&self, id: Uuid, stored: &StoredUser) -> User {
+        User {
+            id: Some(id),
+            first_name: stored.first_name.clone(),
+            last_name: stored.last_name.clone(),
+            birthdate: stored.birthdate,
+            biography: stored.biography.clone(),
+            city: stored.city.clone(),
+            followers_count: self.friends.friend_ids(id).len() as i64,
+            following_count: self.friends.linked_to(id).len() as i64,
+        }
+    }
+
+    /// Mirrors `user_service::register_user`, last names are unique
+    pub fn register(&self, req: UserRegistration<'_>) -> Result<UserRegistrationResult, String> {
+        let mut users = self.users.write().unwrap();
+        if users.values().any(|user| user.last_name == *req.last_name) {
+            return Err("User with last name already exists".to_string());
+        }
+        let (_salt, pwd) = password_hash::hash_password(req.password.clone());
+        let id = Uuid::new_v4();
+        users.insert(id, StoredUser {
+            first_name: req.first_name.clone(),
+            last_name: req.last_name.clone(),
+            birthdate: *req.birthdate,
+            biography: Some(req.biography.clone()),
+            city: req.city.clone(),
+            pwd
+        });
+        Ok(UserRegistrationResult { user_id: Some(id) })
+    }
+
+    pub fn get(&self, id: Uuid) -> Result<User, String> {
+        let users = self.users.read().unwrap();
+        users.get(&id)
+            .map(|stored| self.to_user(id, stored))
+            .ok_or(format!("User {} not found", id))
+    }
+
+    /// Mirrors `user_service::search_by_first_and_last_name`: prefix match on both names, ordered by id
+    pub fn search(&self, first_name: &String, last_name: &String) -> Vec<User> {
+        let users = self.users.read().unwrap();
+        let mut found: Vec<User> = users.iter()
+            .filter(|(_, user)| user.first_name.starts_with(first_name.as_str()) && user.last_name.starts_with(last_name.as_str()))
+            .map(|(id, user)| self.to_user(*id, user))
+            .collect();
+        found.sort_by_key(|user| user.id);
+        found
+    }
+
+    /// Mirrors `auth_service::authenticate_user`
+    pub fn authenticate(&self, id: &Uuid, password: &String) -> Result<bool, String> {
+        let users = self.users.read().unwrap();
+        let user = users.get(id).ok_or(format!("User {} not found", id))?;
+        Ok(password_hash::check_password(password, user.pwd.clone()))
+    }
+}
@@ -1,2 +1,3 @@
 pub mod controller;
-mod user_service;
\ No newline at end of file
+pub mod user_service;
+pub mod in_memory_repository;
@@ -26,6 +26,8 @@ pub struct User {
     pub birthdate: chrono::naive::NaiveDate,
     pub biography: Option<String>,
     pub city: String,
+    pub followers_count: i64,
+    pub following_count: i64,
 }
 
 async fn check_if_user_exists(client: &Object, last_name: &String) -> Result<bool, String> {        
@@ -64,12 +66,14 @@ pub async fn register_user<'a>(client: Object, req: UserRegistration<'a>) -> Res
 }
 
 pub async fn get_user_by_id(client: Object, id: Uuid) -> Result<User, String> {    
-    let row = client.query_one("SELECT first_name, last_name, birthdate, biography, city FROM users WHERE id=$1", &[&id]).await.unwrap();
+    let row = client.query_one("SELECT first_name, last_name, birthdate, biography, city, followers_count, following_count FROM users WHERE id=$1", &[&id]).await.unwrap();
     let first_name: String = row.get(0);            
     let last_name: String = row.get(1);            
     let birthdate: NaiveDate = row.get(2);            
     let biography: Option<String> = row.get(3);            
     let city: String = row.get(4);   
+    let followers_count: i64 = row.get(5);
+    let following_count: i64 = row.get(6);
     Ok(
         User{
             id: Some(id),
@@ -77,13 +81,15 @@ pub async fn get_user_by_id(client: Object, id: Uuid) -> Result<User, String> {
             last_name,
             birthdate,
             biography,
-            city
+            city,
+            followers_count,
+            following_count
         }
     )
 }
 
 pub async fn search_by_first_and_last_name(client: Object, first_name: &String, last_name: &String) -> Vec<User> {    
-    let res = client.query("SELECT id, first_name, last_name, birthdate, biography, city FROM users WHERE (first_name LIKE $1) AND (last_name LIKE $2) ORDER BY id", &[&format!("{}%", first_name), &format!("{}%", last_name)]).await.unwrap();
+    let res = client.query("SELECT id, first_name, last_name, birthdate, biography, city, followers_count, following_count FROM users WHERE (first_name LIKE $1) AND (last_name LIKE $2) ORDER BY id", &[&format!("{}%", first_name), &format!("{}%", last_name)]).await.unwrap();
     res
         .into_iter()
         .map(|row| {
@@ -95,6 +101,8 @@ pub async fn search_by_first_and_last_name(client: Object, first_name: &String,
                 birthdate: row.get("birthdate"),
                 biography: row.get("biography"),
                 city: row.get("city"),
+                followers_count: row.get("followers_count"),
+                following_count: row.get("following_count"),
             }
         })
         .collect()